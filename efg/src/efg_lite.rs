@@ -1,6 +1,8 @@
 #[macro_use]
 extern crate approx;
 
+#[cfg(any(test, feature = "test-fixtures"))]
+pub mod fixtures;
 pub mod game;
 pub mod schema;
 pub mod strategy;
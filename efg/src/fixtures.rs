@@ -0,0 +1,108 @@
+// Small games shared by the tests of this crate and of the solvers built on it. Other crates
+// may use them in their tests by enabling the `test-fixtures` feature.
+
+use crate::game::{
+    ExtensiveFormGame, Infoset, PayoffMatrix, PayoffMatrixEntry, Player, SubgameOrFree,
+};
+use crate::treeplex::Treeplex;
+
+use std::sync::Arc;
+
+/// Normal form game where the leader commits to Up with probability 0.5 and the follower
+/// breaks ties by playing Right, giving the leader a payoff of 3.5. Against the uniform
+/// blueprint, the follower is indifferent between Left and Right, which give the leader 1.5
+/// and 3.5 respectively.
+/// ```text
+///                Left    Right
+///     Up        (2, 1)  (4, 0)
+///     Down      (1, 0)  (3, 1)
+/// ```
+pub fn commitment_game() -> ExtensiveFormGame {
+    let treeplex_pl1 = Treeplex::new(
+        Player::Player1,
+        3,
+        vec![Infoset::new(2, 0, 1)].into_boxed_slice(),
+    );
+    let treeplex_pl2 = Treeplex::new(
+        Player::Player2,
+        3,
+        vec![Infoset::new(2, 0, 1)].into_boxed_slice(),
+    );
+    let payoff_matrix = PayoffMatrix::new(vec![
+        PayoffMatrixEntry::new(0, 0, 1.0, 2.0, 1.0),
+        PayoffMatrixEntry::new(0, 1, 1.0, 4.0, 0.0),
+        PayoffMatrixEntry::new(1, 0, 1.0, 1.0, 0.0),
+        PayoffMatrixEntry::new(1, 1, 1.0, 3.0, 1.0),
+    ]);
+    ExtensiveFormGame::new(
+        Arc::new(treeplex_pl1),
+        Arc::new(treeplex_pl2),
+        payoff_matrix,
+        vec![],
+        vec![],
+        vec![],
+    )
+}
+
+/// The follower chooses between Out, worth (0, 0.25), and In. After In, the leader and
+/// follower play the commitment game in subgame 0, except that Right leads to subgame 1 nested
+/// within it. There, the commitment game is played again with the leader choosing between A
+/// and B (after either Up or Down) and the follower between C and D, which it plays without
+/// observing the leader's choices.
+/// ```text
+///                Left                     C       D
+///     Up        (2, 1)   Right ->   A   (2, 1)  (4, 0)
+///     Down      (1, 0)              B   (1, 0)  (3, 1)
+/// ```
+/// Leader sequences are A and B after Up (0, 1) and after Down (2, 3), then Up and Down (4, 5).
+/// Follower sequences are C and D (0, 1), Left and Right (2, 3) and Out and In (4, 5).
+pub fn nested_commitment_game() -> ExtensiveFormGame {
+    let treeplex_pl1 = Treeplex::new(
+        Player::Player1,
+        7,
+        vec![
+            Infoset::new(4, 0, 1),
+            Infoset::new(5, 2, 3),
+            Infoset::new(6, 4, 5),
+        ]
+        .into_boxed_slice(),
+    );
+    let treeplex_pl2 = Treeplex::new(
+        Player::Player2,
+        7,
+        vec![
+            Infoset::new(3, 0, 1),
+            Infoset::new(5, 2, 3),
+            Infoset::new(6, 4, 5),
+        ]
+        .into_boxed_slice(),
+    );
+    let mut entries = vec![];
+    for (seq_a, seq_b) in [(0, 1), (2, 3)].iter() {
+        entries.push(PayoffMatrixEntry::new(*seq_a, 0, 1.0, 2.0, 1.0));
+        entries.push(PayoffMatrixEntry::new(*seq_a, 1, 1.0, 4.0, 0.0));
+        entries.push(PayoffMatrixEntry::new(*seq_b, 0, 1.0, 1.0, 0.0));
+        entries.push(PayoffMatrixEntry::new(*seq_b, 1, 1.0, 3.0, 1.0));
+    }
+    entries.push(PayoffMatrixEntry::new(4, 2, 1.0, 2.0, 1.0));
+    entries.push(PayoffMatrixEntry::new(5, 2, 1.0, 1.0, 0.0));
+    entries.push(PayoffMatrixEntry::new(6, 4, 1.0, 0.0, 0.25));
+
+    ExtensiveFormGame::new(
+        Arc::new(treeplex_pl1),
+        Arc::new(treeplex_pl2),
+        PayoffMatrix::new(entries),
+        vec![
+            SubgameOrFree::Subgame(1),
+            SubgameOrFree::Subgame(1),
+            SubgameOrFree::Subgame(0),
+        ],
+        vec![
+            SubgameOrFree::Subgame(1),
+            SubgameOrFree::Subgame(0),
+            SubgameOrFree::Free,
+        ],
+        vec![SubgameOrFree::Free, SubgameOrFree::Subgame(0)],
+    )
+}
+
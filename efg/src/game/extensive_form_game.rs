@@ -1,4 +1,5 @@
 use crate::game::Player;
+use crate::game::{GameLoadError, GameValidationError};
 use crate::game::{PayoffMatrix, PayoffMatrixEntry};
 use crate::strategy::SequenceFormStrategy;
 
@@ -7,7 +8,8 @@ use crate::vector::TreeplexVector;
 
 use crate::schema::game_capnp;
 use capnp;
-use std::rc::Rc;
+use std::ops::Range;
+use std::sync::Arc;
 /// This library is a lite version of libefg, adapted from Gabriele Farina (gfarina@cs.cmu.edu).
 /// We have taken the liberty to modify the code such that the code is tailored towards
 /// the extensive-form case with some support for subgame-solving.
@@ -16,7 +18,12 @@ use std::rc::Rc;
 /// pl2 is the follower. This is to allow for future extensions for other 2-player
 /// equilibrium concepts beyond Stackelberg equliibria.
 ///
-/// Currently the code only supports games non-nested subgames.
+/// Subgames may be nested. Each subgame optionally declares a parent subgame, and the
+/// subgames form a forest whose roots are the top-level subgames. Information sets are
+/// labelled by the *innermost* subgame they belong to, so an information set inside
+/// subgame `j`, which is in turn nested inside subgame `i`, is labelled `Subgame(j)`.
+/// Nested subgames are considered part of their parent, i.e., queries such as
+/// `subgame_infosets` and `subgame_leaf_range` for `i` include everything inside `j`.
 ///
 /// The versions of `ExtensiveFormGame` are similar to Gabriele Farina and is written such that
 /// if one does not specify any subgames, then the two structs are in fact, functionally identical.
@@ -30,8 +37,8 @@ use std::rc::Rc;
 #[derive(Debug, Clone)]
 pub struct ExtensiveFormGame {
     // Full game treeplexes
-    treeplex_pl1: Rc<Treeplex>,
-    treeplex_pl2: Rc<Treeplex>,
+    treeplex_pl1: Arc<Treeplex>,
+    treeplex_pl2: Arc<Treeplex>,
 
     // Sparse sequence-form payoff matrix
     payoff_matrix: PayoffMatrix,
@@ -41,6 +48,19 @@ pub struct ExtensiveFormGame {
     subgames_pl2: Vec<SubgameOrFree>,
 
     num_subgames: usize,
+
+    // Parent of each subgame, `Free` if the subgame is a top-level one.
+    subgame_parents: Vec<SubgameOrFree>,
+
+    // Children and depth (0 for top-level subgames) of each subgame. These are
+    // reconstructible from `subgame_parents` and are not serialized.
+    subgame_children: Vec<Vec<usize>>,
+    subgame_depths: Vec<usize>,
+
+    // Innermost subgame of each payoff matrix entry, and the range of payoff matrix
+    // entries lying within each subgame (including nested ones).
+    leaf_subgames: Vec<SubgameOrFree>,
+    subgame_leaf_ranges: Vec<Range<usize>>,
 }
 
 /// Specifies if an object within any subgame, i.e., "free", or belonging to a subgame
@@ -69,26 +89,158 @@ impl SubgameOrFree {
 }
 
 impl ExtensiveFormGame {
+    /// Creates a new game. `subgame_parents` gives the parent of each subgame (`Free` for
+    /// top-level subgames). Subgames without an entry in `subgame_parents` (in particular,
+    /// all subgames if it is empty) are top-level. Similarly, empty `subgames_pl1` or
+    /// `subgames_pl2` mean that all infosets of that player are free.
+    ///
+    /// Panics with a description of the violated invariant if the game is malformed.
+    /// Use `try_new` to handle such errors instead.
     pub fn new(
-        treeplex_pl1: Rc<Treeplex>,
-        treeplex_pl2: Rc<Treeplex>,
+        treeplex_pl1: Arc<Treeplex>,
+        treeplex_pl2: Arc<Treeplex>,
         payoff_matrix: PayoffMatrix,
         subgames_pl1: Vec<SubgameOrFree>,
         subgames_pl2: Vec<SubgameOrFree>,
+        subgame_parents: Vec<SubgameOrFree>,
     ) -> ExtensiveFormGame {
+        match Self::try_new(
+            treeplex_pl1,
+            treeplex_pl2,
+            payoff_matrix,
+            subgames_pl1,
+            subgames_pl2,
+            subgame_parents,
+        ) {
+            Ok(efg) => efg,
+            Err(err) => panic!("Invalid extensive-form game: {}", err),
+        }
+    }
+
+    /// Same as `new`, but returns the first violated invariant (see `validate`) as an
+    /// error instead of panicking.
+    pub fn try_new(
+        treeplex_pl1: Arc<Treeplex>,
+        treeplex_pl2: Arc<Treeplex>,
+        payoff_matrix: PayoffMatrix,
+        subgames_pl1: Vec<SubgameOrFree>,
+        subgames_pl2: Vec<SubgameOrFree>,
+        mut subgame_parents: Vec<SubgameOrFree>,
+    ) -> Result<ExtensiveFormGame, GameValidationError> {
         let num_subgames = std::cmp::max(
-            Self::compute_num_subgames(&subgames_pl1),
-            Self::compute_num_subgames(&subgames_pl2),
+            std::cmp::max(
+                Self::compute_num_subgames(&subgames_pl1),
+                Self::compute_num_subgames(&subgames_pl2),
+            ),
+            subgame_parents.len(),
         );
+        subgame_parents.resize(num_subgames, SubgameOrFree::Free);
 
-        ExtensiveFormGame {
+        // Derived fields are filled in only after validation, since computing them
+        // relies on the invariants checked there.
+        let mut efg = ExtensiveFormGame {
             treeplex_pl1,
             treeplex_pl2,
             payoff_matrix,
             subgames_pl1,
             subgames_pl2,
             num_subgames,
+            subgame_parents,
+            subgame_children: vec![],
+            subgame_depths: vec![],
+            leaf_subgames: vec![],
+            subgame_leaf_ranges: vec![],
+        };
+        efg.validate()?;
+
+        efg.subgame_children = Self::compute_subgame_children(&efg.subgame_parents);
+        efg.subgame_depths = Self::compute_subgame_depths(&efg.subgame_parents);
+        efg.leaf_subgames = efg.compute_leaf_subgames(&efg.subgame_depths);
+        efg.subgame_leaf_ranges = efg.compute_subgame_leaf_ranges(&efg.leaf_subgames);
+        Ok(efg)
+    }
+
+    /// Checks the requirements on treeplexes, subgames and the payoff matrix, i.e.,
+    /// (a) both treeplexes are valid (see `Treeplex::validate`),
+    /// (b) subgames are given for either none or all infosets of each player,
+    /// (c) parents of subgames exist and do not form cycles,
+    /// (d) payoff entries refer to existing sequences, and
+    /// (e) leaves of each subgame are contiguous in the payoff matrix.
+    /// Returns the first violation found.
+    pub fn validate(&self) -> Result<(), GameValidationError> {
+        self.treeplex_pl1.validate()?;
+        self.treeplex_pl2.validate()?;
+
+        for player in [Player::Player1, Player::Player2].iter() {
+            let subgames = match player {
+                Player::Player1 => &self.subgames_pl1,
+                Player::Player2 => &self.subgames_pl2,
+            };
+            let num_infosets = self.treeplex(*player).num_infosets();
+            if !subgames.is_empty() && subgames.len() != num_infosets {
+                return Err(GameValidationError::SubgameListLength {
+                    player: *player,
+                    expected: num_infosets,
+                    found: subgames.len(),
+                });
+            }
         }
+
+        for (subgame_id, parent) in self.subgame_parents.iter().enumerate() {
+            if let SubgameOrFree::Subgame(parent_id) = *parent {
+                if parent_id >= self.num_subgames {
+                    return Err(GameValidationError::SubgameParentOutOfRange {
+                        subgame_id,
+                        parent_id,
+                    });
+                }
+            }
+        }
+        for subgame_id in 0..self.num_subgames {
+            // Any chain of parents longer than the number of subgames contains a cycle.
+            let mut cur = self.subgame_parents[subgame_id];
+            let mut depth = 0;
+            while let SubgameOrFree::Subgame(x) = cur {
+                depth += 1;
+                if x == subgame_id || depth > self.num_subgames {
+                    return Err(GameValidationError::SubgameParentCycle { subgame_id });
+                }
+                cur = self.subgame_parents[x];
+            }
+        }
+
+        for (entry_index, entry) in self.payoff_matrix.entries.iter().enumerate() {
+            for (player, sequence_id) in [
+                (Player::Player1, entry.seq_pl1),
+                (Player::Player2, entry.seq_pl2),
+            ]
+            .iter()
+            {
+                if !self.treeplex(*player).has_sequence(*sequence_id) {
+                    return Err(GameValidationError::PayoffSequenceOutOfRange {
+                        entry_index,
+                        player: *player,
+                        sequence_id: *sequence_id,
+                    });
+                }
+            }
+        }
+
+        let subgame_depths = Self::compute_subgame_depths(&self.subgame_parents);
+        let leaf_subgames = self.compute_leaf_subgames(&subgame_depths);
+        let subgame_leaf_ranges = self.compute_subgame_leaf_ranges(&leaf_subgames);
+        for (subgame_id, range) in subgame_leaf_ranges.into_iter().enumerate() {
+            for entry_index in range {
+                if !self.is_within_subgame(leaf_subgames[entry_index], subgame_id) {
+                    return Err(GameValidationError::NonContiguousSubgameLeaves {
+                        subgame_id,
+                        entry_index,
+                    });
+                }
+            }
+        }
+
+        Ok(())
     }
 
     pub fn evaluate_payoffs(
@@ -209,7 +361,7 @@ impl ExtensiveFormGame {
         self.payoff_matrix().entries.iter().all(|&x| ulps_eq!(x.payoff_pl1, -x.payoff_pl2))
     }
 
-    pub fn treeplex(&self, player: Player) -> &Treeplex {
+    pub fn treeplex(&self, player: Player) -> &Arc<Treeplex> {
         match player {
             Player::Player1 => &self.treeplex_pl1,
             Player::Player2 => &self.treeplex_pl2,
@@ -228,10 +380,15 @@ impl ExtensiveFormGame {
         self.payoff_matrix.entries.len()
     }
 
+    /// Returns the innermost subgame containing the infoset.
     pub fn subgame(&self, player: Player, infoset_id: usize) -> SubgameOrFree {
-        match player {
-            Player::Player1 => self.subgames_pl1[infoset_id],
-            Player::Player2 => self.subgames_pl2[infoset_id],
+        let subgames = match player {
+            Player::Player1 => &self.subgames_pl1,
+            Player::Player2 => &self.subgames_pl2,
+        };
+        match subgames.len() {
+            0 => SubgameOrFree::Free,
+            _ => subgames[infoset_id],
         }
     }
 
@@ -239,6 +396,94 @@ impl ExtensiveFormGame {
         self.num_subgames
     }
 
+    /// Parent of a subgame, or `Free` if the subgame is a top-level one.
+    pub fn subgame_parent(&self, subgame_id: usize) -> SubgameOrFree {
+        self.subgame_parents[subgame_id]
+    }
+
+    /// Subgames immediately nested within a subgame.
+    pub fn subgame_children(&self, subgame_id: usize) -> &Vec<usize> {
+        &self.subgame_children[subgame_id]
+    }
+
+    /// Subgames which are not nested within any other subgame.
+    pub fn top_level_subgames(&self) -> Vec<usize> {
+        (0..self.num_subgames)
+            .filter(|&x| self.subgame_parents[x] == SubgameOrFree::Free)
+            .collect()
+    }
+
+    /// Nesting depth of a subgame. Top-level subgames have a depth of 0.
+    pub fn subgame_depth(&self, subgame_id: usize) -> usize {
+        self.subgame_depths[subgame_id]
+    }
+
+    /// Returns true if `subgame` is equal to, or nested (possibly indirectly)
+    /// within the subgame indexed by `ancestor_id`.
+    pub fn is_within_subgame(&self, subgame: SubgameOrFree, ancestor_id: usize) -> bool {
+        let mut cur = subgame;
+        while let SubgameOrFree::Subgame(x) = cur {
+            if x == ancestor_id {
+                return true;
+            }
+            cur = self.subgame_parents[x];
+        }
+        false
+    }
+
+    /// Infosets of `player` lying within a subgame, including those within
+    /// nested subgames, in increasing order of infoset ids.
+    pub fn subgame_infosets(&self, player: Player, subgame_id: usize) -> Vec<usize> {
+        (0..self.treeplex(player).num_infosets())
+            .filter(|&x| self.is_within_subgame(self.subgame(player, x), subgame_id))
+            .collect()
+    }
+
+    /// Returns the same game, but with subgames nested fewer than `depth` levels deep merged
+    /// into the trunk, so that subgames at depth `depth` become top-level ones. Remaining
+    /// subgames are renumbered in increasing order of their ids, and the returned vector maps
+    /// each new subgame id to the original one. Treeplexes are shared with this game.
+    pub fn with_subgames_from_depth(&self, depth: usize) -> (ExtensiveFormGame, Vec<usize>) {
+        let original_subgame_ids = (0..self.num_subgames)
+            .filter(|&x| self.subgame_depths[x] >= depth)
+            .collect::<Vec<usize>>();
+        let mut new_subgame_ids = vec![None; self.num_subgames];
+        for (new_id, original_id) in original_subgame_ids.iter().enumerate() {
+            new_subgame_ids[*original_id] = Some(new_id);
+        }
+        let relabel = |subgame: &SubgameOrFree| match *subgame {
+            SubgameOrFree::Subgame(x) => match new_subgame_ids[x] {
+                Some(new_id) => SubgameOrFree::Subgame(new_id),
+                None => SubgameOrFree::Free,
+            },
+            SubgameOrFree::Free => SubgameOrFree::Free,
+        };
+
+        let game = ExtensiveFormGame::new(
+            self.treeplex_pl1.clone(),
+            self.treeplex_pl2.clone(),
+            self.payoff_matrix.clone(),
+            self.subgames_pl1.iter().map(relabel).collect(),
+            self.subgames_pl2.iter().map(relabel).collect(),
+            original_subgame_ids
+                .iter()
+                .map(|x| relabel(&self.subgame_parents[*x]))
+                .collect(),
+        );
+        (game, original_subgame_ids)
+    }
+
+    /// Range of payoff matrix indices for leaves lying within a subgame, including
+    /// those within nested subgames. The range is empty if the subgame contains no leaves.
+    pub fn subgame_leaf_range(&self, subgame_id: usize) -> Range<usize> {
+        self.subgame_leaf_ranges[subgame_id].clone()
+    }
+
+    /// Returns the innermost subgame containing the leaf at `payoff_index`. This is the
+    /// deeper of the subgames containing the terminal sequences of either player.
+    pub fn leaf_subgame(&self, payoff_index: usize) -> SubgameOrFree {
+        self.leaf_subgames[payoff_index]
+    }
 
     pub fn serialize<'b>(&self, builder: &mut game_capnp::game::Builder<'b>) {
         let mut treeplex_pl1_builder = builder.reborrow().init_treeplex_pl1();
@@ -265,14 +510,24 @@ impl ExtensiveFormGame {
         for (infoset_index, subgame) in self.subgames_pl2.iter().enumerate() {
             subgames_pl2_builder.set(infoset_index as u32, subgame.to_integer() as u32);
         }
+
+        let mut subgame_parents_builder = builder
+            .reborrow()
+            .init_subgame_parents(self.num_subgames as u32);
+        for (subgame_index, parent) in self.subgame_parents.iter().enumerate() {
+            subgame_parents_builder.set(subgame_index as u32, parent.to_integer() as u32);
+        }
     }
 
-    pub fn deserialize(game_reader: &game_capnp::game::Reader) -> capnp::Result<ExtensiveFormGame> {
-        let treeplex_pl1 = Rc::new(Treeplex::deserialize(
+    /// Deserializes the game, and validates it before returning it.
+    pub fn deserialize(
+        game_reader: &game_capnp::game::Reader,
+    ) -> Result<ExtensiveFormGame, GameLoadError> {
+        let treeplex_pl1 = Arc::new(Treeplex::deserialize(
             Player::Player1,
             &game_reader.get_treeplex_pl1()?,
         )?);
-        let treeplex_pl2 = Rc::new(Treeplex::deserialize(
+        let treeplex_pl2 = Arc::new(Treeplex::deserialize(
             Player::Player2,
             &game_reader.get_treeplex_pl2()?,
         )?);
@@ -288,13 +543,21 @@ impl ExtensiveFormGame {
             subgames_pl2.push(SubgameOrFree::from_integer(subgame as usize));
         }
 
-        Ok(ExtensiveFormGame::new(
+        // Games written before nested subgames were supported do not have this field,
+        // in which case the list is empty and all subgames are top-level.
+        let mut subgame_parents = vec![];
+        for parent in game_reader.get_subgame_parents()?.iter() {
+            subgame_parents.push(SubgameOrFree::from_integer(parent as usize));
+        }
+
+        Ok(ExtensiveFormGame::try_new(
             treeplex_pl1,
             treeplex_pl2,
             payoff_matrix,
             subgames_pl1,
             subgames_pl2,
-        ))
+            subgame_parents,
+        )?)
     }
 
     pub fn persist<W>(&self, writer: &mut W) -> std::io::Result<()>
@@ -309,7 +572,7 @@ impl ExtensiveFormGame {
         capnp::serialize::write_message(writer, &message_builder)
     }
 
-    fn compute_num_subgames(subgames: &Vec<SubgameOrFree>) -> usize {
+    fn compute_num_subgames(subgames: &[SubgameOrFree]) -> usize {
         subgames.iter().fold(0, |accum, x| {
             std::cmp::max(
                 accum,
@@ -320,11 +583,100 @@ impl ExtensiveFormGame {
             )
         })
     }
+
+    fn compute_subgame_children(subgame_parents: &[SubgameOrFree]) -> Vec<Vec<usize>> {
+        let mut subgame_children = vec![vec![]; subgame_parents.len()];
+        for (subgame_id, parent) in subgame_parents.iter().enumerate() {
+            if let SubgameOrFree::Subgame(x) = parent {
+                subgame_children[*x].push(subgame_id);
+            }
+        }
+        subgame_children
+    }
+
+    fn compute_subgame_depths(subgame_parents: &[SubgameOrFree]) -> Vec<usize> {
+        let mut subgame_depths = vec![0; subgame_parents.len()];
+        for (subgame_id, depth) in subgame_depths.iter_mut().enumerate() {
+            let mut cur = subgame_parents[subgame_id];
+            while let SubgameOrFree::Subgame(x) = cur {
+                *depth += 1;
+                cur = subgame_parents[x];
+            }
+        }
+        subgame_depths
+    }
+
+    /// Innermost subgame of the infoset each sequence belongs to (`Free` for the
+    /// empty sequence).
+    fn compute_sequence_subgames(&self, player: Player) -> Vec<SubgameOrFree> {
+        let treeplex = self.treeplex(player);
+        let mut sequence_subgames = vec![SubgameOrFree::Free; treeplex.num_sequences()];
+        for (infoset_id, infoset) in treeplex.infosets().iter().enumerate() {
+            for sequence_id in infoset.start_sequence..=infoset.end_sequence {
+                sequence_subgames[sequence_id] = self.subgame(player, infoset_id);
+            }
+        }
+        sequence_subgames
+    }
+
+    /// The subgame of a leaf is the deeper of the subgames containing the terminal
+    /// sequences of either player.
+    fn compute_leaf_subgames(&self, subgame_depths: &[usize]) -> Vec<SubgameOrFree> {
+        let sequence_subgames_pl1 = self.compute_sequence_subgames(Player::Player1);
+        let sequence_subgames_pl2 = self.compute_sequence_subgames(Player::Player2);
+        self.payoff_matrix
+            .entries
+            .iter()
+            .map(|entry| {
+                match (
+                    sequence_subgames_pl1[entry.seq_pl1],
+                    sequence_subgames_pl2[entry.seq_pl2],
+                ) {
+                    (SubgameOrFree::Free, x) => x,
+                    (x, SubgameOrFree::Free) => x,
+                    (SubgameOrFree::Subgame(x), SubgameOrFree::Subgame(y)) => {
+                        if subgame_depths[x] >= subgame_depths[y] {
+                            SubgameOrFree::Subgame(x)
+                        } else {
+                            SubgameOrFree::Subgame(y)
+                        }
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Computes the (smallest) range of payoff matrix entries which covers all leaves
+    /// within each subgame. If leaves in a subgame are contiguous, then the range
+    /// contains exactly these leaves.
+    fn compute_subgame_leaf_ranges(&self, leaf_subgames: &[SubgameOrFree]) -> Vec<Range<usize>> {
+        let mut subgame_leaf_ranges = vec![0..0; self.num_subgames];
+        for (payoff_index, leaf_subgame) in leaf_subgames.iter().enumerate() {
+            let mut cur = *leaf_subgame;
+            while let SubgameOrFree::Subgame(x) = cur {
+                let range = &mut subgame_leaf_ranges[x];
+                if range.start == range.end {
+                    *range = payoff_index..payoff_index + 1;
+                } else {
+                    range.end = payoff_index + 1;
+                }
+                cur = self.subgame_parents[x];
+            }
+        }
+        subgame_leaf_ranges
+    }
 }
 
 #[cfg(test)]
 pub mod test_fixtures {
+    use crate::fixtures::nested_commitment_game;
+    use crate::game::{
+        ExtensiveFormGame, GameValidationError, Infoset, PayoffMatrix, PayoffMatrixEntry, Player,
+        SubgameOrFree,
+    };
+    use crate::schema::game_capnp;
     use crate::treeplex::Treeplex;
+    use std::sync::Arc;
 
     // TODO(chunkail): Learn how to import test fixtures from other modules...
     //
@@ -349,4 +701,152 @@ pub mod test_fixtures {
         // vec![PayoffMatrixEntry(0, )
         };
     */
-}
\ No newline at end of file
+
+    /// Player 1 makes two consecutive moves, with the second move being in a subgame
+    /// nested within the subgame of the first. Player 2 moves once outside of any subgame.
+    pub fn nested_subgame_game() -> ExtensiveFormGame {
+        let treeplex_pl1 = Treeplex::new(
+            Player::Player1,
+            5,
+            vec![Infoset::new(2, 0, 1), Infoset::new(4, 2, 3)].into_boxed_slice(),
+        );
+        let treeplex_pl2 = Treeplex::new(
+            Player::Player2,
+            3,
+            vec![Infoset::new(2, 0, 1)].into_boxed_slice(),
+        );
+        let payoff_matrix = PayoffMatrix::new(vec![
+            PayoffMatrixEntry::new(3, 1, 1.0, 1.0, -1.0),
+            PayoffMatrixEntry::new(0, 0, 1.0, 2.0, -2.0),
+            PayoffMatrixEntry::new(1, 0, 1.0, 3.0, -3.0),
+        ]);
+
+        ExtensiveFormGame::new(
+            Arc::new(treeplex_pl1),
+            Arc::new(treeplex_pl2),
+            payoff_matrix,
+            vec![SubgameOrFree::Subgame(1), SubgameOrFree::Subgame(0)],
+            vec![SubgameOrFree::Free],
+            vec![SubgameOrFree::Free, SubgameOrFree::Subgame(0)],
+        )
+    }
+
+    #[test]
+    fn nested_subgames() {
+        let game = nested_commitment_game();
+        assert_eq!(game.num_subgames(), 2);
+        assert_eq!(game.top_level_subgames(), vec![0]);
+        assert_eq!(game.subgame_parent(1), SubgameOrFree::Subgame(0));
+        assert_eq!(game.subgame_children(0), &vec![1]);
+        assert_eq!(game.subgame_depth(0), 0);
+        assert_eq!(game.subgame_depth(1), 1);
+        assert!(game.is_within_subgame(SubgameOrFree::Subgame(1), 0));
+        assert!(!game.is_within_subgame(SubgameOrFree::Subgame(0), 1));
+        assert!(!game.is_within_subgame(SubgameOrFree::Free, 0));
+
+        assert_eq!(game.subgame_infosets(Player::Player1, 0), vec![0, 1, 2]);
+        assert_eq!(game.subgame_infosets(Player::Player1, 1), vec![0, 1]);
+        assert_eq!(game.subgame_infosets(Player::Player2, 0), vec![0, 1]);
+        assert_eq!(game.subgame_infosets(Player::Player2, 1), vec![0]);
+
+        assert_eq!(game.leaf_subgame(0), SubgameOrFree::Subgame(1));
+        assert_eq!(game.leaf_subgame(8), SubgameOrFree::Subgame(0));
+        assert_eq!(game.leaf_subgame(10), SubgameOrFree::Free);
+        assert_eq!(game.subgame_leaf_range(0), 0..10);
+        assert_eq!(game.subgame_leaf_range(1), 0..8);
+
+        // Merging the outer subgame into the trunk leaves the inner one at the top level.
+        let (inner_game, original_subgame_ids) = game.with_subgames_from_depth(1);
+        assert_eq!(original_subgame_ids, vec![1]);
+        assert_eq!(inner_game.top_level_subgames(), vec![0]);
+        assert_eq!(inner_game.subgame(Player::Player1, 0), SubgameOrFree::Subgame(0));
+        assert_eq!(inner_game.subgame(Player::Player1, 2), SubgameOrFree::Free);
+        assert_eq!(inner_game.subgame(Player::Player2, 0), SubgameOrFree::Subgame(0));
+        assert_eq!(inner_game.subgame(Player::Player2, 1), SubgameOrFree::Free);
+        assert_eq!(inner_game.subgame_leaf_range(0), 0..8);
+        assert_eq!(game.with_subgames_from_depth(0).1, vec![0, 1]);
+        assert_eq!(game.with_subgames_from_depth(2).0.num_subgames(), 0);
+    }
+
+    #[test]
+    fn nested_subgames_serialization() {
+        let game = nested_subgame_game();
+        let mut message_builder = capnp::message::Builder::new_default();
+        let mut game_builder = message_builder.init_root::<game_capnp::game::Builder>();
+        game.serialize(&mut game_builder);
+
+        let game_reader = game_builder.into_reader();
+        let deserialized = ExtensiveFormGame::deserialize(&game_reader).unwrap();
+        assert_eq!(deserialized.num_subgames(), 2);
+        assert_eq!(deserialized.subgame_parent(0), SubgameOrFree::Free);
+        assert_eq!(deserialized.subgame_parent(1), SubgameOrFree::Subgame(0));
+        assert_eq!(deserialized.subgame_leaf_range(1), 0..2);
+    }
+
+    #[test]
+    fn invalid_payoff_sequence() {
+        let game = nested_subgame_game();
+        let mut entries = game.payoff_matrix().entries.clone();
+        entries.push(PayoffMatrixEntry::new(5, 0, 1.0, 0.0, 0.0));
+
+        let result = ExtensiveFormGame::try_new(
+            game.treeplex(Player::Player1).clone(),
+            game.treeplex(Player::Player2).clone(),
+            PayoffMatrix::new(entries),
+            vec![],
+            vec![],
+            vec![],
+        );
+        assert_eq!(
+            result.err(),
+            Some(GameValidationError::PayoffSequenceOutOfRange {
+                entry_index: 3,
+                player: Player::Player1,
+                sequence_id: 5,
+            })
+        );
+    }
+
+    #[test]
+    fn non_contiguous_subgame_leaves() {
+        let game = nested_subgame_game();
+        let payoff_matrix = PayoffMatrix::new(vec![
+            PayoffMatrixEntry::new(0, 0, 1.0, 1.0, -1.0),
+            PayoffMatrixEntry::new(1, 2, 1.0, 2.0, -2.0),
+            PayoffMatrixEntry::new(3, 1, 1.0, 3.0, -3.0),
+        ]);
+
+        let result = ExtensiveFormGame::try_new(
+            game.treeplex(Player::Player1).clone(),
+            game.treeplex(Player::Player2).clone(),
+            payoff_matrix,
+            vec![SubgameOrFree::Free, SubgameOrFree::Subgame(0)],
+            vec![SubgameOrFree::Subgame(0)],
+            vec![],
+        );
+        assert_eq!(
+            result.err(),
+            Some(GameValidationError::NonContiguousSubgameLeaves {
+                subgame_id: 0,
+                entry_index: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn subgame_parent_cycle() {
+        let game = nested_subgame_game();
+        let result = ExtensiveFormGame::try_new(
+            game.treeplex(Player::Player1).clone(),
+            game.treeplex(Player::Player2).clone(),
+            game.payoff_matrix().clone(),
+            vec![SubgameOrFree::Subgame(1), SubgameOrFree::Subgame(0)],
+            vec![SubgameOrFree::Free],
+            vec![SubgameOrFree::Subgame(1), SubgameOrFree::Subgame(0)],
+        );
+        assert_eq!(
+            result.err(),
+            Some(GameValidationError::SubgameParentCycle { subgame_id: 0 })
+        );
+    }
+}
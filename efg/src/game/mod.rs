@@ -3,10 +3,12 @@ mod infoset;
 mod player;
 mod payoff;
 mod utility;
+mod validation;
 
 pub use self::extensive_form_game::ExtensiveFormGame;
 pub use self::extensive_form_game::SubgameOrFree;
 pub use self::player::Player;
 pub use self::infoset::Infoset;
 pub use self::payoff::{PayoffMatrix, PayoffMatrixEntry};
-pub use self::utility::{EFGTools};
\ No newline at end of file
+pub use self::utility::{EFGTools};
+pub use self::validation::{GameLoadError, GameValidationError};
\ No newline at end of file
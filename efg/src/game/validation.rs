@@ -0,0 +1,215 @@
+use crate::game::Player;
+use crate::treeplex::SequenceId;
+
+use capnp;
+use std::fmt;
+
+/// Invariants of `Treeplex` and `ExtensiveFormGame` which may be violated by a
+/// malformed game. Each variant identifies the offending infoset, sequence or
+/// payoff entry, using the numbering of the game being validated.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameValidationError {
+    /// The infoset has an end sequence smaller than its start sequence.
+    EmptyInfoset {
+        player: Player,
+        infoset_id: usize,
+    },
+    /// Sequences of the infoset lie outside of the treeplex, or include the empty sequence.
+    InfosetSequenceOutOfRange {
+        player: Player,
+        infoset_id: usize,
+        sequence_id: SequenceId,
+    },
+    /// The parent sequence of the infoset lies outside of the treeplex.
+    ParentSequenceOutOfRange {
+        player: Player,
+        infoset_id: usize,
+        parent_sequence: SequenceId,
+    },
+    /// The parent sequence of the infoset is numbered before its children sequences,
+    /// whereas the treeplex requires parents to have larger ids.
+    ParentSequenceOrder {
+        player: Player,
+        infoset_id: usize,
+        parent_sequence: SequenceId,
+    },
+    /// The sequence is claimed by two infosets.
+    OverlappingSequences {
+        player: Player,
+        sequence_id: SequenceId,
+        infoset_ids: (usize, usize),
+    },
+    /// The (nonempty) sequence does not belong to any infoset.
+    UncoveredSequence {
+        player: Player,
+        sequence_id: SequenceId,
+    },
+    /// Infosets sharing the same parent sequence are not contiguous.
+    NonContiguousChildInfosets {
+        player: Player,
+        parent_sequence: SequenceId,
+        infoset_id: usize,
+    },
+    /// The number of subgame labels is neither 0 nor the number of infosets.
+    SubgameListLength {
+        player: Player,
+        expected: usize,
+        found: usize,
+    },
+    /// The parent of a subgame does not exist.
+    SubgameParentOutOfRange {
+        subgame_id: usize,
+        parent_id: usize,
+    },
+    /// Following the parents of a subgame leads back to itself.
+    SubgameParentCycle {
+        subgame_id: usize,
+    },
+    /// A payoff entry refers to a sequence outside of the treeplex.
+    PayoffSequenceOutOfRange {
+        entry_index: usize,
+        player: Player,
+        sequence_id: SequenceId,
+    },
+    /// Leaves of the subgame are not contiguous in the payoff matrix. `entry_index` is an
+    /// entry outside of the subgame which lies between two entries inside it.
+    NonContiguousSubgameLeaves {
+        subgame_id: usize,
+        entry_index: usize,
+    },
+}
+
+impl fmt::Display for GameValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameValidationError::EmptyInfoset { player, infoset_id } => write!(
+                f,
+                "{:?}: infoset {} has no sequences",
+                player, infoset_id
+            ),
+            GameValidationError::InfosetSequenceOutOfRange {
+                player,
+                infoset_id,
+                sequence_id,
+            } => write!(
+                f,
+                "{:?}: infoset {} contains sequence {} which is out of range",
+                player, infoset_id, sequence_id
+            ),
+            GameValidationError::ParentSequenceOutOfRange {
+                player,
+                infoset_id,
+                parent_sequence,
+            } => write!(
+                f,
+                "{:?}: infoset {} has parent sequence {} which is out of range",
+                player, infoset_id, parent_sequence
+            ),
+            GameValidationError::ParentSequenceOrder {
+                player,
+                infoset_id,
+                parent_sequence,
+            } => write!(
+                f,
+                "{:?}: infoset {} has parent sequence {} which is not after its own sequences",
+                player, infoset_id, parent_sequence
+            ),
+            GameValidationError::OverlappingSequences {
+                player,
+                sequence_id,
+                infoset_ids,
+            } => write!(
+                f,
+                "{:?}: sequence {} belongs to both infosets {} and {}",
+                player, sequence_id, infoset_ids.0, infoset_ids.1
+            ),
+            GameValidationError::UncoveredSequence {
+                player,
+                sequence_id,
+            } => write!(
+                f,
+                "{:?}: sequence {} does not belong to any infoset",
+                player, sequence_id
+            ),
+            GameValidationError::NonContiguousChildInfosets {
+                player,
+                parent_sequence,
+                infoset_id,
+            } => write!(
+                f,
+                "{:?}: infosets under sequence {} are not contiguous (infoset {})",
+                player, parent_sequence, infoset_id
+            ),
+            GameValidationError::SubgameListLength {
+                player,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{:?}: expected {} subgame labels, found {}",
+                player, expected, found
+            ),
+            GameValidationError::SubgameParentOutOfRange {
+                subgame_id,
+                parent_id,
+            } => write!(
+                f,
+                "subgame {} has parent {} which does not exist",
+                subgame_id, parent_id
+            ),
+            GameValidationError::SubgameParentCycle { subgame_id } => {
+                write!(f, "subgame {} is nested within itself", subgame_id)
+            }
+            GameValidationError::PayoffSequenceOutOfRange {
+                entry_index,
+                player,
+                sequence_id,
+            } => write!(
+                f,
+                "payoff entry {} refers to sequence {} of {:?}, which is out of range",
+                entry_index, sequence_id, player
+            ),
+            GameValidationError::NonContiguousSubgameLeaves {
+                subgame_id,
+                entry_index,
+            } => write!(
+                f,
+                "leaves of subgame {} are not contiguous (payoff entry {} lies outside of it)",
+                subgame_id, entry_index
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GameValidationError {}
+
+/// Errors which may occur while loading a game from file: either the file could not
+/// be read, or the game it contains is malformed.
+#[derive(Debug)]
+pub enum GameLoadError {
+    Capnp(capnp::Error),
+    Invalid(GameValidationError),
+}
+
+impl fmt::Display for GameLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameLoadError::Capnp(err) => write!(f, "Error reading game: {}", err),
+            GameLoadError::Invalid(err) => write!(f, "Invalid game: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for GameLoadError {}
+
+impl From<capnp::Error> for GameLoadError {
+    fn from(err: capnp::Error) -> GameLoadError {
+        GameLoadError::Capnp(err)
+    }
+}
+
+impl From<GameValidationError> for GameLoadError {
+    fn from(err: GameValidationError) -> GameLoadError {
+        GameLoadError::Invalid(err)
+    }
+}
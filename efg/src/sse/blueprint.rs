@@ -6,6 +6,8 @@ use crate::vector::TreeplexVector;
 
 use crate::treeplex::SequenceId;
 
+use crate::sse::TieBreaking;
+
 /// Struct containing the best response information for blueprints.
 /// Computes follower's best response and persistently stores the following intermediate results:
 /// (a) follower_bp_br_behavioral
@@ -15,17 +17,17 @@ use crate::treeplex::SequenceId;
 /// (e) follower-bp_br_behavioral_index
 /// TODO (chunkail): Do we really need (a) with the trunk formulation?
 ///     Unlike the standard BR which we perform on a per-treeplex level, we require that the
-///     follower break ties in favour of the leader, so we cannot directly use the best_response
-///     method provided by efg. At any rate, this computation is done as a byproduct of computing
-///     (b), hence we do not require significantly more computation time.
-pub struct BlueprintBr<'a> {
-    game: &'a ExtensiveFormGame,
-    leader_blueprint: &'a SequenceFormStrategy<'a>,
+///     follower break ties according to a `TieBreaking` policy (by default, in favour of the
+///     leader), so we cannot directly use the best_response method provided by efg. At any rate,
+///     this computation is done as a byproduct of computing (b), hence we do not require
+///     significantly more computation time.
+pub struct BlueprintBr {
+    leader_blueprint: SequenceFormStrategy,
 
     // Follower's best 'stackelberg' response to the blueprint,
     // either in behavioral or sequence form.
-    follower_sequence: SequenceFormStrategy<'a>,
-    follower_behavioral: BehavioralStrategy<'a>,
+    follower_sequence: SequenceFormStrategy,
+    follower_behavioral: BehavioralStrategy,
 
     // Follower's best response by index, i.e., maps from an infoset index
     // to a sequence index.
@@ -34,22 +36,30 @@ pub struct BlueprintBr<'a> {
     // Follower's/Leader's value of each *follower* sequence, given leader's blueprint
     // and stackelberg response from follower. Note that these values are indexed
     // by the *follower* treeplex.
-    follower_seq_values: TreeplexVector<'a>,
-    leader_seq_values: TreeplexVector<'a>,
+    follower_seq_values: TreeplexVector,
+    leader_seq_values: TreeplexVector,
+
+    tie_breaking: TieBreaking,
 }
 
-impl<'a> BlueprintBr<'a> {
-    pub fn new(
-        game: &'a ExtensiveFormGame,
-        leader_blueprint: &'a SequenceFormStrategy,
-    ) -> BlueprintBr<'a> {
+impl BlueprintBr {
+    /// Computes the follower's best response, breaking ties in favour of the leader.
+    pub fn new(game: &ExtensiveFormGame, leader_blueprint: &SequenceFormStrategy) -> BlueprintBr {
+        Self::with_tie_breaking(game, leader_blueprint, TieBreaking::default())
+    }
+
+    pub fn with_tie_breaking(
+        game: &ExtensiveFormGame,
+        leader_blueprint: &SequenceFormStrategy,
+        tie_breaking: TieBreaking,
+    ) -> BlueprintBr {
         let mut grad_follower_payoffs =
             game.gradient_for_payoffs(Player::Player2, Player::Player2, leader_blueprint);
         let mut grad_leader_payoffs =
             game.gradient_for_payoffs(Player::Player2, Player::Player1, leader_blueprint);
 
 
-        // Now, we compute best responses while breaking ties favoring the leader at each infoset.
+        // Now, we compute best responses while breaking ties at each infoset.
         // This is almost identical to the best-response functions within `Treeplex`, except for
         // tiebreaking and the absence of inplace operations.
         let follower_treeplex = game.treeplex(Player::Player2);
@@ -80,24 +90,23 @@ impl<'a> BlueprintBr<'a> {
                 }
             }
 
-            // Now, go through the sequences again, and for values which are epsilon close
-            // to best_follower_value, perform tiebreaking. Tiebreaking is done by referencing
-            // the the leader's payoffs.
-            let mut best_follower_tiebreak_index = best_follower_index;
+            // Now, go through the sequences again, and collect those which are epsilon close
+            // to best_follower_value, starting with best_follower_index. Tiebreaking among these
+            // may reference the leader's payoffs, which are final for all sequences at this infoset.
+            let mut tied_sequences = vec![best_follower_index];
             for sequence_id in infoset.start_sequence..=infoset.end_sequence {
-                if abs_diff_eq!(
-                    best_follower_value,
-                    grad_follower_payoffs[sequence_id],
-                    epsilon = 1e-7
-                ) {
-                    // if relative_eq!(best_follower_value, grad_follower_payoffs[sequence_id]) {
-                    if grad_leader_payoffs[sequence_id]
-                        > grad_leader_payoffs[best_follower_tiebreak_index]
-                    {
-                        best_follower_tiebreak_index = sequence_id;
-                    }
+                if sequence_id != best_follower_index
+                    && abs_diff_eq!(
+                        best_follower_value,
+                        grad_follower_payoffs[sequence_id],
+                        epsilon = tie_breaking.epsilon
+                    )
+                {
+                    tied_sequences.push(sequence_id);
                 }
             }
+            let best_follower_tiebreak_index =
+                tie_breaking.choose(infoset_id, &tied_sequences, &grad_leader_payoffs);
             behavioral_br_index[infoset_id] = best_follower_tiebreak_index;
 
             // At this point, the best sequence to be chosen (after tiebreaks) is within
@@ -122,8 +131,7 @@ impl<'a> BlueprintBr<'a> {
         let behavioral_br = BehavioralStrategy::from_treeplex_vector(behavioral_br);
 
         BlueprintBr {
-            game,
-            leader_blueprint,
+            leader_blueprint: leader_blueprint.clone(),
             follower_seq_values: grad_follower_payoffs,
             follower_sequence: SequenceFormStrategy::from_behavioral_strategy(
                 behavioral_br.clone(),
@@ -131,15 +139,20 @@ impl<'a> BlueprintBr<'a> {
             follower_behavioral: behavioral_br,
             leader_seq_values: grad_leader_payoffs,
             follower_behavioral_index: behavioral_br_index,
+            tie_breaking,
         }
 
     }
 
-    pub fn leader_blueprint(&self) -> &SequenceFormStrategy<'a> {
-        self.leader_blueprint
+    pub fn tie_breaking(&self) -> &TieBreaking {
+        &self.tie_breaking
     }
 
-    pub fn follower_seq_values(&self) -> &TreeplexVector<'a> {
+    pub fn leader_blueprint(&self) -> &SequenceFormStrategy {
+        &self.leader_blueprint
+    }
+
+    pub fn follower_seq_values(&self) -> &TreeplexVector {
         &self.follower_seq_values
     }
 
@@ -147,7 +160,7 @@ impl<'a> BlueprintBr<'a> {
         self.follower_seq_values[seq_id]
     }
 
-    pub fn leader_seq_values(&self) -> &TreeplexVector<'a> {
+    pub fn leader_seq_values(&self) -> &TreeplexVector {
         &self.leader_seq_values
     }
 
@@ -167,11 +180,73 @@ impl<'a> BlueprintBr<'a> {
         &self.follower_behavioral
     }
 
-    pub fn follower_sequence(&self) -> &SequenceFormStrategy<'a> {
+    pub fn follower_sequence(&self) -> &SequenceFormStrategy {
         &self.follower_sequence
     }
 
     pub fn follower_infoset_value(&self, infoset_id: usize) -> f64 {
         self.follower_seq_value(self.follower_behavioral_index(infoset_id))
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+pub mod test_fixtures {
+    use crate::fixtures::commitment_game;
+    use crate::game::{ExtensiveFormGame, Player};
+    use crate::sse::{BlueprintBr, TieBreaking, TieBreakingRule};
+    use crate::strategy::SequenceFormStrategy;
+
+    use assert_approx_eq::assert_approx_eq;
+    use std::sync::Arc;
+
+    #[test]
+    fn shareable_across_threads() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<ExtensiveFormGame>();
+        assert_send_sync::<BlueprintBr>();
+        assert_send_sync::<crate::sse::GameBuilder>();
+        assert_send_sync::<crate::sse::GameMapper>();
+        assert_send_sync::<crate::sse::TreeplexMapper>();
+        assert_send_sync::<crate::vector::TreeplexVector>();
+        assert_send_sync::<SequenceFormStrategy>();
+        assert_send_sync::<crate::strategy::BehavioralStrategy>();
+
+        // Strategies and best responses share ownership of the treeplexes, so they may
+        // outlive the game they were computed from.
+        let blueprint_br = {
+            let game = commitment_game();
+            let blueprint =
+                SequenceFormStrategy::from_uniform_strategy(game.treeplex(Player::Player1));
+            BlueprintBr::new(&game, &blueprint)
+        };
+        let blueprint_br = std::thread::spawn(move || blueprint_br).join().unwrap();
+        assert_eq!(blueprint_br.follower_behavioral_index(0), 1);
+        assert_approx_eq!(blueprint_br.leader_blueprint().inner()[0], 0.5);
+        assert_approx_eq!(blueprint_br.follower_sequence().inner()[1], 1.0);
+    }
+
+    #[test]
+    fn tie_breaking_rules() {
+        let game = commitment_game();
+        let blueprint = SequenceFormStrategy::from_uniform_strategy(game.treeplex(Player::Player1));
+
+        let chosen_sequence = |rule: TieBreakingRule, epsilon: f64| {
+            let tie_breaking = TieBreaking::new(rule, epsilon);
+            BlueprintBr::with_tie_breaking(&game, &blueprint, tie_breaking)
+                .follower_behavioral_index(0)
+        };
+        assert_eq!(BlueprintBr::new(&game, &blueprint).follower_behavioral_index(0), 1);
+        assert_eq!(chosen_sequence(TieBreakingRule::LeaderFavouring, 1e-7), 1);
+        assert_eq!(chosen_sequence(TieBreakingRule::LeaderAdverse, 1e-7), 0);
+        assert_eq!(chosen_sequence(TieBreakingRule::LowestIndex, 1e-7), 0);
+        assert_eq!(
+            chosen_sequence(TieBreakingRule::Custom(Arc::new(|_, tied, _| tied[tied.len() - 1])), 1e-7),
+            1
+        );
+
+        // Payoffs to the leader are propagated along the chosen sequence.
+        let tie_breaking = TieBreaking::new(TieBreakingRule::LeaderAdverse, 1e-7);
+        let blueprint_br = BlueprintBr::with_tie_breaking(&game, &blueprint, tie_breaking);
+        assert_approx_eq!(blueprint_br.leader_seq_value(2), 1.5);
+        assert_approx_eq!(blueprint_br.follower_seq_value(2), 0.5);
+    }
+}
@@ -53,4 +53,12 @@ impl BoundedProblem {
             leaves_within_trunk,
         }
     }
-}
\ No newline at end of file
+
+    /// The problem of finding the Strong Stackelberg Equilibrium of an entire game: all
+    /// leaves are within the trunk, there are no bounds on the follower's values and all
+    /// probability mass enters the game.
+    pub fn from_full_game(game: &ExtensiveFormGame) -> BoundedProblem {
+        let leaves_within_trunk = (0..game.payoff_matrix().entries.len()).collect();
+        BoundedProblem::new(game.clone(), 1.0, vec![], leaves_within_trunk)
+    }
+}
@@ -1,9 +1,14 @@
 use crate::game::{
     ExtensiveFormGame, Infoset, PayoffMatrix, PayoffMatrixEntry, Player, SubgameOrFree,
 };
-use crate::sse::{BlueprintBr, TreeplexMapper, ValueBound};
+use crate::sse::{
+    BlueprintBr, FollowerTypes, GiftSplitting, TreeplexMapper, UniformGiftSplitting, ValueBound,
+};
+use crate::sse::gift_splitting::SlackMemo;
 use crate::treeplex::{SequenceId, Treeplex, TreeplexTools};
 
+use std::sync::Arc;
+
 /// This struct provides methods to compute the `safety-bounds' for the follower
 /// information sets with respect to some game and a given blueprint. This is done by
 /// the following tree-traversal steps.
@@ -17,14 +22,24 @@ use crate::treeplex::{SequenceId, Treeplex, TreeplexTools};
 ///
 /// The chief complexity here is the way in which these bounds are generated, similar
 /// to the `gift-splitting' procedure originally used in Libratus.
+///
+/// The follower model is that of the blueprint's best response, see `BlueprintBr::tie_breaking`.
+/// How gifts are split between infosets is decided by a `GiftSplitting` policy.
 
 pub struct BoundsGenerator<'a> {
     game: &'a ExtensiveFormGame,
     follower_treeplex_tools: &'a TreeplexTools,
     leader_treeplex_tools: &'a TreeplexTools,
-    blueprint_br: &'a BlueprintBr<'a>,
+    blueprint_br: &'a BlueprintBr,
     splitting_ratio: f64,
     gift_factor: f64,
+    gift_splitting: Arc<dyn GiftSplitting>,
+
+    // Probability of reaching each follower infoset under the blueprint, assuming that the
+    // follower plays to reach it.
+    infoset_reach: Vec<f64>,
+
+    slack_memo: SlackMemo,
 }
 
 impl<'a> BoundsGenerator<'a> {
@@ -32,11 +47,32 @@ impl<'a> BoundsGenerator<'a> {
         game: &'a ExtensiveFormGame,
         follower_treeplex_tools: &'a TreeplexTools,
         leader_treeplex_tools: &'a TreeplexTools,
-        blueprint_br: &'a BlueprintBr<'a>,
+        blueprint_br: &'a BlueprintBr,
         splitting_ratio: f64,
         gift_factor: f64,
+    ) -> BoundsGenerator<'a> {
+        Self::with_gift_splitting(
+            game,
+            follower_treeplex_tools,
+            leader_treeplex_tools,
+            blueprint_br,
+            splitting_ratio,
+            gift_factor,
+            Arc::new(UniformGiftSplitting),
+        )
+    }
+
+    pub fn with_gift_splitting(
+        game: &'a ExtensiveFormGame,
+        follower_treeplex_tools: &'a TreeplexTools,
+        leader_treeplex_tools: &'a TreeplexTools,
+        blueprint_br: &'a BlueprintBr,
+        splitting_ratio: f64,
+        gift_factor: f64,
+        gift_splitting: Arc<dyn GiftSplitting>,
     ) -> BoundsGenerator<'a> {
         assert!(splitting_ratio >= 0.0 && splitting_ratio <= 1.0);
+        let infoset_reach = Self::compute_infoset_reach(game, follower_treeplex_tools, blueprint_br);
         BoundsGenerator {
             game,
             follower_treeplex_tools,
@@ -44,9 +80,53 @@ impl<'a> BoundsGenerator<'a> {
             blueprint_br,
             splitting_ratio,
             gift_factor,
+            gift_splitting,
+            infoset_reach,
+            slack_memo: SlackMemo::default(),
         }
     }
 
+    pub fn game(&self) -> &'a ExtensiveFormGame {
+        self.game
+    }
+
+    pub fn follower_treeplex_tools(&self) -> &'a TreeplexTools {
+        self.follower_treeplex_tools
+    }
+
+    pub fn blueprint_br(&self) -> &'a BlueprintBr {
+        self.blueprint_br
+    }
+
+    pub fn splitting_ratio(&self) -> f64 {
+        self.splitting_ratio
+    }
+
+    pub fn gift_factor(&self) -> f64 {
+        self.gift_factor
+    }
+
+    /// Probability of reaching a follower infoset when the leader plays the blueprint,
+    /// including chance, assuming that the follower plays to reach it and best responds
+    /// to the blueprint afterwards.
+    pub fn infoset_reach(&self, infoset_id: usize) -> f64 {
+        self.infoset_reach[infoset_id]
+    }
+
+    pub(crate) fn slack_memo(&self) -> &SlackMemo {
+        &self.slack_memo
+    }
+
+    /// Threshold at a free trunk infoset based on the blueprint values alone, before taking
+    /// propagated bounds into account. The trunk sequence may be up to epsilon worse than the
+    /// best sequence after tiebreaking, in which case the threshold may not exceed its value.
+    pub fn local_threshold(&self, infoset_id: usize) -> f64 {
+        f64::min(
+            self.threshold_from_child_seqs(infoset_id),
+            self.blueprint_br.follower_infoset_value(infoset_id),
+        )
+    }
+
     pub fn follower_bounds(&self) -> Vec<ValueBound> {
         let treeplex = self.game.treeplex(Player::Player2);
         let mut follower_payoff_bounds =
@@ -54,18 +134,53 @@ impl<'a> BoundsGenerator<'a> {
 
         self.expand_seq_trunk(
             treeplex.empty_sequence_id(),
-            std::f64::NEG_INFINITY,
+            f64::NEG_INFINITY,
             &mut follower_payoff_bounds,
         );
 
         follower_payoff_bounds
     }
 
+    /// Bounds for a Bayesian game, kept separately for each follower type: the i-th entry
+    /// only contains the bounds of infosets belonging to type i, which are generated by
+    /// expanding the infosets of that type alone. Since all infosets below an infoset share
+    /// its type, the topmost infosets of each type must hang off the empty sequence, which
+    /// imposes no bound, so that no gifts are passed between types. The bounds of all types
+    /// taken together are thus those given by `follower_bounds`.
+    pub fn follower_bounds_by_type(&self, follower_types: &FollowerTypes) -> Vec<Vec<ValueBound>> {
+        let treeplex = self.game.treeplex(Player::Player2);
+        let empty_sequence_id = treeplex.empty_sequence_id();
+        (0..follower_types.num_types())
+            .map(|follower_type| {
+                let mut follower_payoff_bounds =
+                    std::vec::from_elem::<ValueBound>(ValueBound::None, treeplex.num_infosets());
+                for infoset_id in follower_types.infosets_of_type(follower_type) {
+                    let parent_sequence = treeplex.infosets()[infoset_id].parent_sequence;
+                    match self.follower_treeplex_tools.parent_infoset_of_seq(parent_sequence) {
+                        Some(parent_infoset_id) => assert_eq!(
+                            follower_types.infoset_type(parent_infoset_id),
+                            follower_type,
+                            "Infoset {} has a different type from its parent infoset {}",
+                            infoset_id,
+                            parent_infoset_id
+                        ),
+                        None => {
+                            assert_eq!(parent_sequence, empty_sequence_id);
+                            self.expand_infoset_trunk(
+                                infoset_id,
+                                f64::NEG_INFINITY,
+                                &mut follower_payoff_bounds,
+                            );
+                        }
+                    }
+                }
+                follower_payoff_bounds
+            })
+            .collect::<Vec<Vec<ValueBound>>>()
+    }
+
     /// Expands all infosets under a sequence within a trunk.
-    /// Gifts for now are split uniformly among children infosets:
-    /// TODO (chunkail) split between subgames! This may possibly be done by
-    /// bottom up traversal of the treeplex and doing some form of `forward-backward`
-    /// algorithm.
+    /// Gifts are split among children infosets according to the `GiftSplitting` policy.
     fn expand_seq_trunk(
         &self,
         sequence_id: SequenceId,
@@ -81,21 +196,22 @@ impl<'a> BoundsGenerator<'a> {
             lower_bound
         );
 
-        let gift_value = f64::max(0f64, bp_br_value - lower_bound) * self.gift_factor;
-        let gift_spread = gift_value
-            / (self
-                .follower_treeplex_tools
-                .seq_to_infoset_range(sequence_id)
-                .len() as f64);
+        let infoset_range = self.follower_treeplex_tools.seq_to_infoset_range(sequence_id);
 
-        for next_infoset_id in self
-            .follower_treeplex_tools
-            .seq_to_infoset_range(sequence_id)
-        {
+        // Sequences without a bound (i.e., the empty sequence) do not bound their children.
+        let gift_shares = if lower_bound == f64::NEG_INFINITY {
+            vec![f64::INFINITY; infoset_range.len()]
+        } else {
+            let gift_value = f64::max(0f64, bp_br_value - lower_bound) * self.gift_factor;
+            self.gift_splitting.split(self, sequence_id, gift_value)
+        };
+        assert_eq!(gift_shares.len(), infoset_range.len());
+
+        for (next_infoset_id, gift_share) in infoset_range.zip(gift_shares.iter()) {
             let next_infoset_value = self.blueprint_br.follower_infoset_value(next_infoset_id);
             self.expand_infoset_trunk(
                 next_infoset_id,
-                next_infoset_value - gift_spread,
+                next_infoset_value - gift_share,
                 follower_payoff_bounds,
             );
         }
@@ -149,11 +265,20 @@ impl<'a> BoundsGenerator<'a> {
             }
             SubgameOrFree::Free => {
                 // "Local" threshold by virtue of the blueprint values.
-                let threshold = self.threshold_from_child_seqs(infoset_id);
+                let threshold = self.local_threshold(infoset_id);
 
                 // Get tighter of the threshold compared to the propagated bounds.
                 let threshold = f64::max(threshold, lower_bound);
 
+                // Unless the follower breaks ties in favour of the leader (as the subgame
+                // MILPs assume), non-trunk sequences are kept strictly below the trunk, so
+                // that the follower does not leave the trunk by tiebreaking. This is only
+                // possible as far as the blueprint itself separates them.
+                let nontrunk_threshold = f64::max(
+                    threshold - self.blueprint_br.tie_breaking().separation_margin(),
+                    self.best_nontrunk_value(infoset_id),
+                );
+
                 // Expand each child sequence based on whether they are part of the trunk
                 // or otherwise.
                 let treeplex = self.game.treeplex(Player::Player2);
@@ -162,7 +287,11 @@ impl<'a> BoundsGenerator<'a> {
                     if self.blueprint_br.follower_behavioral_index(infoset_id) == seq_id {
                         self.expand_seq_trunk(seq_id, threshold, follower_payoff_bounds);
                     } else {
-                        self.expand_seq_nontrunk(seq_id, threshold, follower_payoff_bounds);
+                        self.expand_seq_nontrunk(
+                            seq_id,
+                            nontrunk_threshold,
+                            follower_payoff_bounds,
+                        );
                     }
                 }
             }
@@ -189,6 +318,16 @@ impl<'a> BoundsGenerator<'a> {
         }
     }
 
+    /// Highest value of the sequences at an infoset that the follower does not choose under
+    /// the blueprint, or -infinity if there is only one action.
+    fn best_nontrunk_value(&self, infoset_id: usize) -> f64 {
+        let infoset = self.game.treeplex(Player::Player2).infosets()[infoset_id];
+        (infoset.start_sequence..=infoset.end_sequence)
+            .filter(|x| *x != self.blueprint_br.follower_behavioral_index(infoset_id))
+            .map(|x| self.blueprint_br.follower_seq_value(x))
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+
     fn threshold_from_child_seqs(&self, infoset_id: usize) -> f64 {
         // Set an upper bound on the lower bound based off the top 2 sequences at this infoset.
         let treeplex = self.game.treeplex(Player::Player2);
@@ -198,7 +337,7 @@ impl<'a> BoundsGenerator<'a> {
         let (best_index, best_value) = (infoset.start_sequence..=infoset.end_sequence)
             .into_iter()
             .fold(
-                (treeplex.num_sequences(), std::f64::NEG_INFINITY),
+                (treeplex.num_sequences(), f64::NEG_INFINITY),
                 |s, x| {
                     let value = self.blueprint_br.follower_seq_value(x);
                     if value > s.1 {
@@ -212,7 +351,7 @@ impl<'a> BoundsGenerator<'a> {
         // Get second maximum sequence value. If there is only one action, then this is -infinity
         let second_best_value = (infoset.start_sequence..=infoset.end_sequence)
             .into_iter()
-            .fold(std::f64::NEG_INFINITY, |s, x| {
+            .fold(f64::NEG_INFINITY, |s, x| {
                 if x == best_index {
                     s
                 } else {
@@ -225,9 +364,33 @@ impl<'a> BoundsGenerator<'a> {
                 }
             });
 
-        // Our current implementation takes the average of best and second best action, though need
-        // not necessarily be the case.
-        second_best_value + (best_value - second_best_value) * self.splitting_ratio
+        // By default, this is a fixed fraction of the way from the second best to the best action.
+        let splitting_ratio = self.gift_splitting.splitting_ratio(self, infoset_id);
+        second_best_value + (best_value - second_best_value) * splitting_ratio
+    }
+
+    fn compute_infoset_reach(
+        game: &ExtensiveFormGame,
+        follower_treeplex_tools: &TreeplexTools,
+        blueprint_br: &BlueprintBr,
+    ) -> Vec<f64> {
+        // Each leaf contributes to the infosets above it from which the follower's best
+        // response leads to it.
+        let treeplex = game.treeplex(Player::Player2);
+        let leader_blueprint = blueprint_br.leader_blueprint().inner();
+        let mut infoset_reach = vec![0f64; treeplex.num_infosets()];
+        for payoff_entry in game.payoff_matrix().entries.iter() {
+            let mass = payoff_entry.chance_factor * leader_blueprint[payoff_entry.seq_pl1];
+            let mut sequence_id = payoff_entry.seq_pl2;
+            while let Some(infoset_id) = follower_treeplex_tools.parent_infoset_of_seq(sequence_id) {
+                if blueprint_br.follower_behavioral_index(infoset_id) != sequence_id {
+                    break;
+                }
+                infoset_reach[infoset_id] += mass;
+                sequence_id = treeplex.infosets()[infoset_id].parent_sequence;
+            }
+        }
+        infoset_reach
     }
 }
 
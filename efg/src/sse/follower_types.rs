@@ -0,0 +1,362 @@
+use crate::treeplex::{Treeplex, TreeplexTools};
+
+use std::io::{Error, ErrorKind, Read, Write};
+
+/// Follower types of a Bayesian Stackelberg game, where chance draws the type at the root
+/// according to a known prior and reveals it to the follower (but not the leader). Each
+/// follower infoset then belongs to exactly one type, and the chance factors of the payoffs
+/// already include the prior, so that the SSE of the extensive-form game is the Bayesian SSE.
+///
+/// Values attached to follower sequences and infosets (e.g., by `BlueprintBr`) are weighted by
+/// the prior; `conditional_values` recovers the values conditioned on each type.
+///
+/// Safety bounds are kept separately for each type, see `BoundsGenerator::follower_bounds_by_type`.
+#[derive(Debug, Clone)]
+pub struct FollowerTypes {
+    priors: Vec<f64>,
+    infoset_types: Vec<usize>,
+
+    // Follower infosets whose parent is the empty sequence.
+    root_infosets: Vec<usize>,
+}
+
+impl FollowerTypes {
+    /// Panics if the priors or types are invalid, see `try_new`.
+    pub fn new(treeplex: &Treeplex, priors: Vec<f64>, infoset_types: Vec<usize>) -> FollowerTypes {
+        match Self::try_new(treeplex, priors, infoset_types) {
+            Ok(follower_types) => follower_types,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Checks that priors are positive and sum to 1, and that each infoset of `treeplex` has
+    /// a valid type which is shared with its parent infoset.
+    pub fn try_new(
+        treeplex: &Treeplex,
+        priors: Vec<f64>,
+        infoset_types: Vec<usize>,
+    ) -> Result<FollowerTypes, String> {
+        if infoset_types.len() != treeplex.num_infosets() {
+            return Err(format!(
+                "Expected {} infoset types, got {}",
+                treeplex.num_infosets(),
+                infoset_types.len()
+            ));
+        }
+        if !priors.iter().all(|x| *x > 0.0) {
+            return Err("Priors of follower types must be positive".to_string());
+        }
+        if (priors.iter().sum::<f64>() - 1.0).abs() >= 1e-6 {
+            return Err("Priors of follower types must sum to 1".to_string());
+        }
+        if let Some(infoset_id) =
+            (0..infoset_types.len()).find(|x| infoset_types[*x] >= priors.len())
+        {
+            return Err(format!(
+                "Infoset {} has type {}, but there are only {} types",
+                infoset_id,
+                infoset_types[infoset_id],
+                priors.len()
+            ));
+        }
+
+        // The type is revealed at the root, so all infosets below an infoset share its type.
+        let treeplex_tools = TreeplexTools::new(treeplex);
+        for (infoset_id, infoset) in treeplex.infosets().iter().enumerate() {
+            if let Some(parent_infoset_id) =
+                treeplex_tools.parent_infoset_of_seq(infoset.parent_sequence)
+            {
+                if infoset_types[infoset_id] != infoset_types[parent_infoset_id] {
+                    return Err(format!(
+                        "Infoset {} has a different type from its parent infoset {}",
+                        infoset_id, parent_infoset_id
+                    ));
+                }
+            }
+        }
+
+        let root_infosets = treeplex_tools
+            .seq_to_infoset_range(treeplex.empty_sequence_id())
+            .collect::<Vec<usize>>();
+
+        Ok(FollowerTypes {
+            priors,
+            infoset_types,
+            root_infosets,
+        })
+    }
+
+    pub fn num_types(&self) -> usize {
+        self.priors.len()
+    }
+
+    pub fn prior(&self, follower_type: usize) -> f64 {
+        self.priors[follower_type]
+    }
+
+    pub fn infoset_type(&self, infoset_id: usize) -> usize {
+        self.infoset_types[infoset_id]
+    }
+
+    /// Infosets belonging to the given type, in increasing order.
+    pub fn infosets_of_type(&self, follower_type: usize) -> Vec<usize> {
+        (0..self.infoset_types.len())
+            .filter(|x| self.infoset_types[*x] == follower_type)
+            .collect::<Vec<usize>>()
+    }
+
+    /// Given the (prior-weighted) value of each follower infoset, returns the expected value
+    /// conditioned on each type. Payoffs at leaves without any follower action cannot be
+    /// attributed to a type and are ignored.
+    pub fn conditional_values<F: Fn(usize) -> f64>(&self, infoset_value: F) -> Vec<f64> {
+        let mut values = vec![0f64; self.num_types()];
+        for infoset_id in self.root_infosets.iter() {
+            values[self.infoset_types[*infoset_id]] += infoset_value(*infoset_id);
+        }
+        values
+            .iter()
+            .zip(self.priors.iter())
+            .map(|(value, prior)| value / prior)
+            .collect::<Vec<f64>>()
+    }
+
+    /// Writes the number of types and their priors on the first line, followed by the type of
+    /// each follower infoset on the second.
+    pub fn persist<W>(&self, writer: &mut W) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        write!(writer, "{}", self.num_types())?;
+        for prior in self.priors.iter() {
+            write!(writer, " {}", prior)?;
+        }
+        writeln!(writer)?;
+        let infoset_types = self
+            .infoset_types
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>();
+        writeln!(writer, "{}", infoset_types.join(" "))
+    }
+
+    /// Reads types written by `persist`. Types which are invalid or inconsistent with
+    /// `treeplex` are reported as `ErrorKind::InvalidData`.
+    pub fn load<R>(treeplex: &Treeplex, reader: &mut R) -> std::io::Result<FollowerTypes>
+    where
+        R: Read,
+    {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        let invalid_data = |err: String| Error::new(ErrorKind::InvalidData, err);
+
+        let mut tokens = contents.split_whitespace();
+        let num_types = tokens
+            .next()
+            .ok_or_else(|| invalid_data("Missing number of types".to_string()))?
+            .parse::<usize>()
+            .map_err(|err| invalid_data(err.to_string()))?;
+        let priors = tokens
+            .by_ref()
+            .take(num_types)
+            .map(|x| {
+                x.parse::<f64>()
+                    .map_err(|err| invalid_data(err.to_string()))
+            })
+            .collect::<std::io::Result<Vec<f64>>>()?;
+        let infoset_types = tokens
+            .map(|x| {
+                x.parse::<usize>()
+                    .map_err(|err| invalid_data(err.to_string()))
+            })
+            .collect::<std::io::Result<Vec<usize>>>()?;
+        if priors.len() != num_types || infoset_types.len() != treeplex.num_infosets() {
+            return Err(invalid_data(format!(
+                "Expected {} priors and {} infoset types, got {} and {}",
+                num_types,
+                treeplex.num_infosets(),
+                priors.len(),
+                infoset_types.len()
+            )));
+        }
+
+        FollowerTypes::try_new(treeplex, priors, infoset_types).map_err(invalid_data)
+    }
+}
+
+#[cfg(test)]
+pub mod test_fixtures {
+    use super::FollowerTypes;
+    use crate::fixtures::commitment_game;
+    use crate::game::{
+        ExtensiveFormGame, Infoset, PayoffMatrix, PayoffMatrixEntry, Player, SubgameOrFree,
+    };
+    use crate::sse::{BlueprintBr, BoundsGenerator, ValueBound};
+    use crate::strategy::SequenceFormStrategy;
+    use crate::treeplex::{Treeplex, TreeplexTools};
+
+    use assert_approx_eq::assert_approx_eq;
+    use std::sync::Arc;
+
+    /// Bayesian game where chance draws follower type 0 with probability 0.25 and type 1
+    /// otherwise. The leader chooses Up or Down without observing the type. Type 0 plays the
+    /// commitment game, see `fixtures::commitment_game`.
+    ///     Type 0      Left    Right           Type 1      Left    Right
+    ///     Up        (2, 1)  (4, 0)            Up        (1, 0)  (0, 2)
+    ///     Down      (1, 0)  (3, 1)            Down      (3, 1)  (0, 0)
+    pub fn bayesian_game() -> (ExtensiveFormGame, FollowerTypes) {
+        let commitment_game = commitment_game();
+        let treeplex_pl2 = Treeplex::new(
+            Player::Player2,
+            5,
+            vec![Infoset::new(4, 0, 1), Infoset::new(4, 2, 3)].into_boxed_slice(),
+        );
+        let mut entries = commitment_game
+            .payoff_matrix()
+            .entries
+            .iter()
+            .map(|entry| PayoffMatrixEntry {
+                chance_factor: 0.25,
+                ..*entry
+            })
+            .collect::<Vec<PayoffMatrixEntry>>();
+        entries.extend(vec![
+            PayoffMatrixEntry::new(0, 2, 0.75, 1.0, 0.0),
+            PayoffMatrixEntry::new(0, 3, 0.75, 0.0, 2.0),
+            PayoffMatrixEntry::new(1, 2, 0.75, 3.0, 1.0),
+            PayoffMatrixEntry::new(1, 3, 0.75, 0.0, 0.0),
+        ]);
+        let game = ExtensiveFormGame::new(
+            commitment_game.treeplex(Player::Player1).clone(),
+            Arc::new(treeplex_pl2),
+            PayoffMatrix::new(entries),
+            vec![],
+            vec![],
+            vec![],
+        );
+        let follower_types =
+            FollowerTypes::new(game.treeplex(Player::Player2), vec![0.25, 0.75], vec![0, 1]);
+        (game, follower_types)
+    }
+
+    /// The Bayesian game above, except that each type first chooses between Out, worth 0.25 to
+    /// type 0 and 0.5 to type 1 (and nothing to the leader), and In. The game after In is a
+    /// subgame, where the leader does not know which type chose In.
+    /// Follower sequences are Left and Right of type 0 (0, 1) and type 1 (2, 3), followed by
+    /// Out and In of type 0 (4, 5) and type 1 (6, 7).
+    pub fn bayesian_subgame_game() -> (ExtensiveFormGame, FollowerTypes) {
+        let (bayesian_game, _) = bayesian_game();
+        let treeplex_pl2 = Treeplex::new(
+            Player::Player2,
+            9,
+            vec![
+                Infoset::new(5, 0, 1),
+                Infoset::new(7, 2, 3),
+                Infoset::new(8, 4, 5),
+                Infoset::new(8, 6, 7),
+            ]
+            .into_boxed_slice(),
+        );
+        let mut entries = bayesian_game.payoff_matrix().entries.clone();
+        entries.push(PayoffMatrixEntry::new(2, 4, 0.25, 0.0, 0.25));
+        entries.push(PayoffMatrixEntry::new(2, 6, 0.75, 0.0, 0.5));
+        let game = ExtensiveFormGame::new(
+            bayesian_game.treeplex(Player::Player1).clone(),
+            Arc::new(treeplex_pl2),
+            PayoffMatrix::new(entries),
+            vec![SubgameOrFree::Subgame(0)],
+            vec![
+                SubgameOrFree::Subgame(0),
+                SubgameOrFree::Subgame(0),
+                SubgameOrFree::Free,
+                SubgameOrFree::Free,
+            ],
+            vec![],
+        );
+        let follower_types = FollowerTypes::new(
+            game.treeplex(Player::Player2),
+            vec![0.25, 0.75],
+            vec![0, 1, 0, 1],
+        );
+        (game, follower_types)
+    }
+
+    #[test]
+    fn conditional_values_and_persistence() {
+        let (game, follower_types) = bayesian_game();
+        let blueprint = SequenceFormStrategy::from_uniform_strategy(game.treeplex(Player::Player1));
+        let blueprint_br = BlueprintBr::new(&game, &blueprint);
+
+        // Type 0 is indifferent and plays Right, type 1 strictly prefers Right.
+        let leader_values = follower_types.conditional_values(|x| {
+            blueprint_br.leader_seq_value(blueprint_br.follower_behavioral_index(x))
+        });
+        let follower_values =
+            follower_types.conditional_values(|x| blueprint_br.follower_infoset_value(x));
+        assert_approx_eq!(leader_values[0], 3.5);
+        assert_approx_eq!(leader_values[1], 0.0);
+        assert_approx_eq!(follower_values[0], 0.5);
+        assert_approx_eq!(follower_values[1], 1.0);
+
+        let mut buffer = Vec::<u8>::new();
+        follower_types.persist(&mut buffer).unwrap();
+        let loaded = FollowerTypes::load(game.treeplex(Player::Player2), &mut &buffer[..]).unwrap();
+        assert_eq!(loaded.num_types(), 2);
+        assert_approx_eq!(loaded.prior(1), 0.75);
+        assert_eq!(loaded.infosets_of_type(1), vec![1]);
+
+        // Invalid types are reported as errors rather than panicking.
+        let treeplex = game.treeplex(Player::Player2);
+        for contents in [
+            "2 0.25 0.25\n0 1\n",
+            "2 0.0 1.0\n0 1\n",
+            "2 0.25 0.75\n0 2\n",
+            "2 0.25 0.75\n0\n",
+        ]
+        .iter()
+        {
+            let err = FollowerTypes::load(treeplex, &mut contents.as_bytes()).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn bounds_by_type() {
+        let (game, follower_types) = bayesian_subgame_game();
+        let blueprint = SequenceFormStrategy::from_uniform_strategy(game.treeplex(Player::Player1));
+        let blueprint_br = BlueprintBr::new(&game, &blueprint);
+        let follower_treeplex_tools = TreeplexTools::new(game.treeplex(Player::Player2));
+        let leader_treeplex_tools = TreeplexTools::new(game.treeplex(Player::Player1));
+        let bounds_generator = BoundsGenerator::new(
+            &game,
+            &follower_treeplex_tools,
+            &leader_treeplex_tools,
+            &blueprint_br,
+            0.5,
+            1.0,
+        );
+
+        // Both types choose In, worth 0.125 and 0.75 (weighted by the priors) against Out,
+        // worth 0.0625 and 0.375. The thresholds halfway in between are passed on to the heads
+        // of the subgame, each of which is only bounded for its own type.
+        let bounds_by_type = bounds_generator.follower_bounds_by_type(&follower_types);
+        let bounds = bounds_generator.follower_bounds();
+        let lower_bound = |bound: ValueBound| match bound {
+            ValueBound::LowerBound(x) => x,
+            _ => panic!("Expected a lower bound, got {:?}", bound),
+        };
+        assert_eq!(bounds_by_type.len(), 2);
+        assert_approx_eq!(lower_bound(bounds_by_type[0][0]), 0.09375);
+        assert_approx_eq!(lower_bound(bounds_by_type[1][1]), 0.5625);
+        assert!(matches!(bounds_by_type[0][1], ValueBound::None));
+        assert!(matches!(bounds_by_type[1][0], ValueBound::None));
+        for infoset_id in 2..4 {
+            assert!(bounds_by_type
+                .iter()
+                .all(|type_bounds| matches!(type_bounds[infoset_id], ValueBound::None)));
+        }
+
+        // Taken together, the bounds of both types are those of the whole game.
+        assert_approx_eq!(lower_bound(bounds[0]), 0.09375);
+        assert_approx_eq!(lower_bound(bounds[1]), 0.5625);
+    }
+}
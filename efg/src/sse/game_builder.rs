@@ -2,13 +2,13 @@ use crate::game::{
     ExtensiveFormGame, Infoset, PayoffMatrix, PayoffMatrixEntry, Player, SubgameOrFree,
 };
 use crate::sse::{
-    BlueprintBr, BoundedProblem, BoundsGenerator, GameMapper, TreeplexBuilder, TreeplexMapper,
-    ValueBound,
+    BlueprintBr, BoundedProblem, BoundsGenerator, GameMapper, GiftSplitting, TreeplexBuilder,
+    TreeplexMapper, UniformGiftSplitting, ValueBound,
 };
 use crate::strategy::{BehavioralStrategy, SequenceFormStrategy};
 use crate::vector::TreeplexVector;
 use crate::treeplex::{SequenceId, Treeplex, TreeplexTools};
-use std::rc::Rc;
+use std::sync::Arc;
 
 /* TODO (chunkail): Major overhaul to move away from recursive functions
 into iterative ones. */
@@ -26,7 +26,7 @@ pub struct GameBuilder<'a> {
     game: &'a ExtensiveFormGame,
     follower_treeplex_tools: &'a TreeplexTools,
     leader_treeplex_tools: &'a TreeplexTools,
-    blueprint_br: &'a BlueprintBr<'a>,
+    blueprint_br: &'a BlueprintBr,
 
     // Bounds on value for each of the follower infosets.
     follower_payoff_bounds: Vec<ValueBound>,
@@ -45,9 +45,30 @@ impl<'a> GameBuilder<'a> {
         game: &'a ExtensiveFormGame,
         follower_treeplex_tools: &'a TreeplexTools,
         leader_treeplex_tools: &'a TreeplexTools,
-        blueprint_br: &'a BlueprintBr<'a>,
+        blueprint_br: &'a BlueprintBr,
         splitting_ratio: f64,
         gift_factor: f64,
+    ) -> GameBuilder<'a> {
+        Self::with_gift_splitting(
+            game,
+            follower_treeplex_tools,
+            leader_treeplex_tools,
+            blueprint_br,
+            splitting_ratio,
+            gift_factor,
+            Arc::new(UniformGiftSplitting),
+        )
+    }
+
+    /// Same as `new`, but with bounds generated using the given gift splitting policy.
+    pub fn with_gift_splitting(
+        game: &'a ExtensiveFormGame,
+        follower_treeplex_tools: &'a TreeplexTools,
+        leader_treeplex_tools: &'a TreeplexTools,
+        blueprint_br: &'a BlueprintBr,
+        splitting_ratio: f64,
+        gift_factor: f64,
+        gift_splitting: Arc<dyn GiftSplitting>,
     ) -> GameBuilder<'a> {
         let (follower_heads_per_subgame, follower_seq_to_head) =
             Self::preprocess_treeplex_subgames(game, Player::Player2, follower_treeplex_tools);
@@ -61,13 +82,14 @@ impl<'a> GameBuilder<'a> {
         println!("Follower seq to head {:?}", follower_seq_to_head);
         */
 
-        let bounds_generator = BoundsGenerator::new(
+        let bounds_generator = BoundsGenerator::with_gift_splitting(
             game,
             follower_treeplex_tools,
             leader_treeplex_tools,
             blueprint_br,
             splitting_ratio,
             gift_factor,
+            gift_splitting,
         );
         let follower_payoff_bounds = bounds_generator.follower_bounds();
 
@@ -84,6 +106,11 @@ impl<'a> GameBuilder<'a> {
         }
     }
 
+    /// Bounds on the value of each follower infoset, see `BoundsGenerator::follower_bounds`.
+    pub fn follower_payoff_bounds(&self) -> &Vec<ValueBound> {
+        &self.follower_payoff_bounds
+    }
+
     /// Construct skinny game for a given subgame. This involves:
     /// (I) Constructing the 2 new skinny treeplexes.
     /// (II) Mapping the SequenceIDs (and possibly infoset ids) from the new treeplexes to old treeplexes.
@@ -92,6 +119,8 @@ impl<'a> GameBuilder<'a> {
     ///       (b) Modulates leaf chance factors by follower sequences before subgames.
     /// (IV) For each follower infoset that is the root of the given subgame, give bounds on the maximum/minimum
     /// payoff for that infoset.
+    /// Only top-level subgames may be resolved this way. Subgames nested within `subgame_id`
+    /// are treated as part of it.
     /// TODO (chunkail) Is there any possibility of having different resolving schemes for different
     /// information sets?
     pub fn bounded_problem(&self, subgame_id: usize) -> (BoundedProblem, 
@@ -99,6 +128,11 @@ impl<'a> GameBuilder<'a> {
                                                          Vec<f64>, // TODO change to returning strategy.
                                                          Vec<f64>) {
         assert!(subgame_id < self.game.num_subgames(), "Invalid subgame id");
+        assert!(
+            self.game.subgame_parent(subgame_id) == SubgameOrFree::Free,
+            "Subgame {:?} is nested within another subgame",
+            subgame_id
+        );
 
         // First construct SkinnyGame.
         let (treeplex_follower, mapper_follower) =
@@ -107,11 +141,12 @@ impl<'a> GameBuilder<'a> {
         let skinny_payoff_matrix =
             self.skinny_payoff_matrix(subgame_id, &mapper_leader, &mapper_follower);
         let skinny_game = ExtensiveFormGame::new(
-            Rc::<Treeplex>::new(treeplex_leader),
-            Rc::<Treeplex>::new(treeplex_follower),
+            Arc::<Treeplex>::new(treeplex_leader),
+            Arc::<Treeplex>::new(treeplex_follower),
             skinny_payoff_matrix,
             vec![],
             vec![],
+            vec![],
         );
 
         // Compute total probability mass entering subgame, assuming *both* players
@@ -263,7 +298,7 @@ impl<'a> GameBuilder<'a> {
     }
 
     fn get_mapped_blueprint_solution(&self, 
-                                     treeplex: &Treeplex,
+                                     treeplex: &Arc<Treeplex>,
                                      game_mapper: &GameMapper,
                                      player: Player) ->  Vec<f64>{
         // Now we construct a feasible strategy in the skinny game using the BP strategy.
@@ -293,7 +328,7 @@ impl<'a> GameBuilder<'a> {
             v[seq_id] = beh_strategy.inner()[original_seq_id];
         }
         // println!("{:?} {:?}", v, treeplex.num_infosets());
-        let q = TreeplexVector::from_vec(&treeplex, v.clone());
+        let q = TreeplexVector::from_vec(treeplex, v.clone());
         let w = BehavioralStrategy::from_treeplex_vector(q);
         let r = SequenceFormStrategy::from_behavioral_strategy(w);
         r.inner().entries.clone()
@@ -448,17 +483,13 @@ impl<'a> GameBuilder<'a> {
         p
     }
 
-    /// Extract infosets relevant to a given subgame and return vector
-    /// of bools.
+    /// Extract infosets relevant to a given subgame (including those in nested
+    /// subgames) and return vector of bools.
     fn relevant_infosets(&self, player: Player, subgame_id: usize) -> Vec<bool> {
         let treeplex = self.game.treeplex(player);
         let mut is_relevant_infoset = std::vec::from_elem::<bool>(false, treeplex.num_infosets());
 
-        let subgame = SubgameOrFree::Subgame(subgame_id);
-        for infoset_id in (0..treeplex.num_infosets())
-            .into_iter()
-            .filter(|infoset_id| subgame == self.game.subgame(player, *infoset_id))
-        {
+        for infoset_id in self.game.subgame_infosets(player, subgame_id) {
             is_relevant_infoset[infoset_id] = true;
         }
         is_relevant_infoset
@@ -469,6 +500,8 @@ impl<'a> GameBuilder<'a> {
     /// (I) Record the head information set(s) in each subgame.
     /// (II) For each sequence, store which *infoset* is the head
     /// of the subgame the sequence belongs to (or none, if it is not a subgame).
+    /// Since the traversal stops at the first subgame encountered, only heads of
+    /// top-level subgames are recorded.
     /// We return a 2-tuple for (I) and (II) respectively, in the form of
     /// a Vector of Vector (containing head infosets), for each subgame, and
     /// a Vector (of size num_sequences), containing the head information set
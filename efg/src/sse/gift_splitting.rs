@@ -0,0 +1,332 @@
+use crate::game::{Player, SubgameOrFree};
+use crate::sse::BoundsGenerator;
+use crate::treeplex::SequenceId;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::str::FromStr;
+
+/// Decides how `BoundsGenerator` passes gifts down the trunk. At a trunk sequence, the gift
+/// (the amount by which the follower's blueprint value exceeds the bound on the sequence) is
+/// split among the infosets immediately following it, and each infoset's lower bound is
+/// lowered by its share. Shares which reach a subgame become slack for resolving it.
+pub trait GiftSplitting: Send + Sync {
+    /// Splits the nonnegative `gift` at the trunk sequence `sequence_id`, returning one share
+    /// for each infoset in `seq_to_infoset_range(sequence_id)`. Shares should be nonnegative
+    /// and sum up to `gift`.
+    fn split(
+        &self,
+        bounds_generator: &BoundsGenerator,
+        sequence_id: SequenceId,
+        gift: f64,
+    ) -> Vec<f64>;
+
+    /// Position of the threshold at a free trunk infoset between the values of its second
+    /// best (0) and best (1) sequences. Defaults to the splitting ratio of `bounds_generator`.
+    fn splitting_ratio(&self, bounds_generator: &BoundsGenerator, _infoset_id: usize) -> f64 {
+        bounds_generator.splitting_ratio()
+    }
+}
+
+/// Splits gifts evenly among child infosets.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UniformGiftSplitting;
+
+/// Splits gifts in proportion to the probability of reaching each child infoset under the
+/// blueprint, see `BoundsGenerator::infoset_reach`. Falls back to uniform splitting if none
+/// of them are reached.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MassProportionalGiftSplitting;
+
+/// Splits gifts so as to maximize the total slack given to subgames. Gifts are lost when they
+/// lower the bound on a free infoset past its local threshold, and are scaled by the gift
+/// factor at every trunk sequence, so the slack obtained from giving g to an infoset is a
+/// concave piecewise linear function of g. These functions are computed bottom-up and the
+/// gift is allocated greedily to the steepest pieces.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OptimizedGiftSplitting;
+
+impl GiftSplitting for UniformGiftSplitting {
+    fn split(
+        &self,
+        bounds_generator: &BoundsGenerator,
+        sequence_id: SequenceId,
+        gift: f64,
+    ) -> Vec<f64> {
+        let num_infosets = bounds_generator
+            .follower_treeplex_tools()
+            .seq_to_infoset_range(sequence_id)
+            .len();
+        vec![gift / (num_infosets as f64); num_infosets]
+    }
+}
+
+impl GiftSplitting for MassProportionalGiftSplitting {
+    fn split(
+        &self,
+        bounds_generator: &BoundsGenerator,
+        sequence_id: SequenceId,
+        gift: f64,
+    ) -> Vec<f64> {
+        let masses = bounds_generator
+            .follower_treeplex_tools()
+            .seq_to_infoset_range(sequence_id)
+            .map(|infoset_id| bounds_generator.infoset_reach(infoset_id))
+            .collect::<Vec<f64>>();
+        let total_mass: f64 = masses.iter().sum();
+        if total_mass <= 0.0 {
+            return UniformGiftSplitting.split(bounds_generator, sequence_id, gift);
+        }
+        masses.iter().map(|mass| gift * mass / total_mass).collect()
+    }
+}
+
+/// Piece of a concave piecewise linear function, i.e., `capacity` units at rate `slope`.
+/// Functions are lists of pieces in decreasing order of slope.
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    capacity: f64,
+    slope: f64,
+}
+
+/// Slack functions of trunk infosets computed by `OptimizedGiftSplitting`, kept by the
+/// `BoundsGenerator` so that each is computed once rather than for every trunk sequence above.
+#[derive(Debug, Default)]
+pub struct SlackMemo {
+    infoset_pieces: RefCell<HashMap<usize, Rc<Vec<Piece>>>>,
+}
+
+impl OptimizedGiftSplitting {
+    /// Slack obtained from gifts given to the infosets following a trunk sequence, with each
+    /// piece tagged by the (offset of the) infoset it belongs to.
+    fn sequence_pieces(
+        bounds_generator: &BoundsGenerator,
+        sequence_id: SequenceId,
+    ) -> Vec<(usize, Piece)> {
+        let mut pieces = Vec::<(usize, Piece)>::new();
+        let infoset_range = bounds_generator
+            .follower_treeplex_tools()
+            .seq_to_infoset_range(sequence_id);
+        for (offset, infoset_id) in infoset_range.enumerate() {
+            let infoset_pieces = Self::infoset_pieces(bounds_generator, infoset_id);
+            pieces.extend(infoset_pieces.iter().map(|piece| (offset, *piece)));
+        }
+        pieces.sort_by(|a, b| b.1.slope.partial_cmp(&a.1.slope).unwrap());
+        pieces
+    }
+
+    /// Slack obtained from gifts given to a trunk infoset, memoized in `bounds_generator`.
+    fn infoset_pieces(bounds_generator: &BoundsGenerator, infoset_id: usize) -> Rc<Vec<Piece>> {
+        let memo = &bounds_generator.slack_memo().infoset_pieces;
+        if let Some(pieces) = memo.borrow().get(&infoset_id) {
+            return pieces.clone();
+        }
+        let pieces = Rc::new(Self::compute_infoset_pieces(bounds_generator, infoset_id));
+        memo.borrow_mut().insert(infoset_id, pieces.clone());
+        pieces
+    }
+
+    fn compute_infoset_pieces(bounds_generator: &BoundsGenerator, infoset_id: usize) -> Vec<Piece> {
+        let game = bounds_generator.game();
+        if let SubgameOrFree::Subgame(_) = game.subgame(Player::Player2, infoset_id) {
+            return vec![Piece {
+                capacity: f64::INFINITY,
+                slope: 1.0,
+            }];
+        }
+
+        // Only the part of the gift which keeps the bound above the local threshold is passed
+        // on to the trunk sequence, after scaling by the gift factor.
+        let gift_factor = bounds_generator.gift_factor();
+        if gift_factor <= 0.0 {
+            return vec![];
+        }
+        let blueprint_br = bounds_generator.blueprint_br();
+        let mut remaining_capacity = f64::max(
+            0.0,
+            blueprint_br.follower_infoset_value(infoset_id)
+                - bounds_generator.local_threshold(infoset_id),
+        );
+        let trunk_sequence = blueprint_br.follower_behavioral_index(infoset_id);
+        let mut pieces = vec![];
+        for (_, piece) in Self::sequence_pieces(bounds_generator, trunk_sequence) {
+            if remaining_capacity <= 0.0 {
+                break;
+            }
+            let capacity = f64::min(piece.capacity / gift_factor, remaining_capacity);
+            remaining_capacity -= capacity;
+            pieces.push(Piece {
+                capacity,
+                slope: piece.slope * gift_factor,
+            });
+        }
+        pieces
+    }
+}
+
+impl GiftSplitting for OptimizedGiftSplitting {
+    fn split(
+        &self,
+        bounds_generator: &BoundsGenerator,
+        sequence_id: SequenceId,
+        gift: f64,
+    ) -> Vec<f64> {
+        let num_infosets = bounds_generator
+            .follower_treeplex_tools()
+            .seq_to_infoset_range(sequence_id)
+            .len();
+        let mut shares = vec![0f64; num_infosets];
+        let mut remaining_gift = gift;
+        for (offset, piece) in Self::sequence_pieces(bounds_generator, sequence_id) {
+            if remaining_gift <= 0.0 {
+                break;
+            }
+            let share = f64::min(piece.capacity, remaining_gift);
+            shares[offset] += share;
+            remaining_gift -= share;
+        }
+
+        // Whatever is left cannot reach any subgame, so it does not matter where it goes.
+        if remaining_gift > 0.0 && num_infosets > 0 {
+            for share in shares.iter_mut() {
+                *share += remaining_gift / (num_infosets as f64);
+            }
+        }
+        shares
+    }
+}
+
+/// Gift splitting policies selectable by name, e.g., from the command line.
+#[derive(Debug, Clone, Copy)]
+pub enum GiftSplittingRule {
+    Uniform,
+    MassProportional,
+    Optimized,
+}
+
+impl GiftSplittingRule {
+    pub fn policy(&self) -> Arc<dyn GiftSplitting> {
+        match self {
+            GiftSplittingRule::Uniform => Arc::new(UniformGiftSplitting),
+            GiftSplittingRule::MassProportional => Arc::new(MassProportionalGiftSplitting),
+            GiftSplittingRule::Optimized => Arc::new(OptimizedGiftSplitting),
+        }
+    }
+}
+
+impl FromStr for GiftSplittingRule {
+    type Err = String;
+    fn from_str(gift_splitting_rule: &str) -> Result<Self, Self::Err> {
+        match gift_splitting_rule {
+            "uniform" => Ok(GiftSplittingRule::Uniform),
+            "mass" | "mass_proportional" => Ok(GiftSplittingRule::MassProportional),
+            "optimized" => Ok(GiftSplittingRule::Optimized),
+            s => Err(format!("Unknown gift splitting rule: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test_fixtures {
+    use super::GiftSplittingRule;
+    use crate::game::{
+        ExtensiveFormGame, Infoset, PayoffMatrix, PayoffMatrixEntry, Player, SubgameOrFree,
+    };
+    use crate::sse::{BlueprintBr, BoundsGenerator, ValueBound};
+    use crate::strategy::SequenceFormStrategy;
+    use crate::treeplex::{Treeplex, TreeplexTools};
+
+    use assert_approx_eq::assert_approx_eq;
+    use std::sync::Arc;
+
+    /// The follower chooses between Out (value 1) and In, after which it acts at both a subgame
+    /// head A (value 0.5, reached with probability 0.25) and a free infoset B (value 1, reached
+    /// with probability 0.5). B's trunk sequence (over a second best of 0.8) leads to a subgame
+    /// head C. The leader does not act.
+    pub fn trunk_with_free_infoset_game() -> ExtensiveFormGame {
+        let treeplex_pl1 = Treeplex::new(
+            Player::Player1,
+            2,
+            vec![Infoset::new(1, 0, 0)].into_boxed_slice(),
+        );
+        let treeplex_pl2 = Treeplex::new(
+            Player::Player2,
+            9,
+            vec![
+                Infoset::new(2, 0, 1),
+                Infoset::new(6, 2, 3),
+                Infoset::new(6, 4, 5),
+                Infoset::new(8, 6, 7),
+            ]
+            .into_boxed_slice(),
+        );
+        let payoff_matrix = PayoffMatrix::new(vec![
+            PayoffMatrixEntry::new(0, 4, 0.25, 0.0, 2.0),
+            PayoffMatrixEntry::new(0, 5, 0.25, 0.0, 0.0),
+            PayoffMatrixEntry::new(0, 0, 0.5, 0.0, 2.0),
+            PayoffMatrixEntry::new(0, 1, 0.5, 0.0, 0.0),
+            PayoffMatrixEntry::new(0, 3, 0.5, 0.0, 1.6),
+            PayoffMatrixEntry::new(0, 7, 1.0, 0.0, 1.0),
+        ]);
+        ExtensiveFormGame::new(
+            Arc::new(treeplex_pl1),
+            Arc::new(treeplex_pl2),
+            payoff_matrix,
+            vec![SubgameOrFree::Free],
+            vec![
+                SubgameOrFree::Subgame(1),
+                SubgameOrFree::Free,
+                SubgameOrFree::Subgame(0),
+                SubgameOrFree::Free,
+            ],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn gift_splitting_rules() {
+        let game = trunk_with_free_infoset_game();
+        let blueprint = SequenceFormStrategy::from_uniform_strategy(game.treeplex(Player::Player1));
+        let blueprint_br = BlueprintBr::new(&game, &blueprint);
+        let follower_treeplex_tools = TreeplexTools::new(game.treeplex(Player::Player2));
+        let leader_treeplex_tools = TreeplexTools::new(game.treeplex(Player::Player1));
+
+        let head_bounds = |rule: &str| {
+            let bounds_generator = BoundsGenerator::with_gift_splitting(
+                &game,
+                &follower_treeplex_tools,
+                &leader_treeplex_tools,
+                &blueprint_br,
+                0.5,
+                0.5,
+                rule.parse::<GiftSplittingRule>().unwrap().policy(),
+            );
+            assert_approx_eq!(bounds_generator.infoset_reach(1), 0.5);
+            assert_approx_eq!(bounds_generator.infoset_reach(2), 0.25);
+            let bounds = bounds_generator.follower_bounds();
+            match (bounds[2], bounds[0]) {
+                (ValueBound::LowerBound(a), ValueBound::LowerBound(c)) => (a, c),
+                _ => panic!("Expected lower bounds at subgame heads, got {:?}", bounds),
+            }
+        };
+
+        // The threshold at the root is 1.25, so a gift of 0.125 is passed to A and B. Of B's
+        // share, only what keeps its bound above its threshold of 0.9 is (halved and) passed to C.
+        let (a, c) = head_bounds("uniform");
+        assert_approx_eq!(a, 0.5 - 0.0625);
+        assert_approx_eq!(c, 1.0 - 0.03125);
+
+        let (a, c) = head_bounds("mass");
+        assert_approx_eq!(a, 0.5 - 0.125 / 3.0);
+        assert_approx_eq!(c, 1.0 - 0.125 / 3.0);
+
+        // Gifts to A reach the subgame in full, while only half of those to B reach C.
+        let (a, c) = head_bounds("optimized");
+        assert_approx_eq!(a, 0.5 - 0.125);
+        assert_approx_eq!(c, 1.0);
+
+        assert!("proportional".parse::<GiftSplittingRule>().is_err());
+    }
+}
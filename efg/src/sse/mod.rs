@@ -1,5 +1,11 @@
 mod blueprint;
 mod bounds;
+mod follower_types;
+mod gift_splitting;
+mod quantal_response;
+mod robust_value;
+mod safety_certificate;
+mod tie_breaking;
 mod game_builder;
 
 mod treeplex_builder;
@@ -11,6 +17,17 @@ mod bounded_problem;
 
 pub use self::blueprint::BlueprintBr;
 pub use self::bounds::BoundsGenerator;
+pub use self::follower_types::FollowerTypes;
+pub use self::gift_splitting::{
+    GiftSplitting, GiftSplittingRule, MassProportionalGiftSplitting, OptimizedGiftSplitting,
+    UniformGiftSplitting,
+};
+pub use self::quantal_response::{QuantalResponse, QuantalResponseOptimizer};
+pub use self::robust_value::worst_case_leader_value;
+pub use self::safety_certificate::{
+    BoundCheck, BoundStatus, SafetyCertificate, SubgameContribution,
+};
+pub use self::tie_breaking::{TieBreaking, TieBreakingCallback, TieBreakingRule};
 pub use self::game_builder::GameBuilder;
 pub use self::treeplex_mapper::TreeplexMapper;
 pub use self::value_bound::ValueBound;
@@ -0,0 +1,369 @@
+use crate::game::{ExtensiveFormGame, Player};
+use crate::strategy::{BehavioralStrategy, SequenceFormStrategy};
+use crate::treeplex::{DilatedEntropy, Entropy};
+use crate::vector::TreeplexVector;
+
+/// Follower's logit quantal response to a leader strategy, computed per infoset in the manner
+/// of agent-form QRE: bottom-up over the follower treeplex, each infoset plays the softmax of
+/// `lambda` times the (quantal) values of its sequences conditioned on reaching the infoset, and
+/// passes the resulting expected value on to its parent sequence. The conditioning divides the
+/// values by the infoset's reach mass, i.e., the total chance and leader reach of its histories,
+/// so that `lambda` does not shrink with the probability of reaching the infoset. `lambda = 0`
+/// is the uniformly random follower, while the best response is recovered as `lambda` goes to
+/// infinity.
+///
+/// Since the response is smooth in the leader strategy, we also keep the gradient of the leader's
+/// expected payoff with respect to the leader's sequence-form strategy. This is obtained by
+/// backpropagating through the softmax at each infoset, top-down over the follower treeplex.
+pub struct QuantalResponse {
+    lambda: f64,
+
+    // Follower's quantal response, either in behavioral or sequence form.
+    follower_behavioral: BehavioralStrategy,
+    follower_sequence: SequenceFormStrategy,
+
+    // Follower's/Leader's value of each *follower* sequence under the quantal response.
+    follower_seq_values: TreeplexVector,
+    leader_seq_values: TreeplexVector,
+
+    // Gradient of the leader's expected payoff, indexed by the *leader* treeplex.
+    leader_gradient: TreeplexVector,
+}
+
+impl QuantalResponse {
+    pub fn new(
+        game: &ExtensiveFormGame,
+        leader_strategy: &SequenceFormStrategy,
+        lambda: f64,
+    ) -> QuantalResponse {
+        assert!(lambda >= 0.0, "Quantal response requires a nonnegative lambda");
+        let mut follower_seq_values =
+            game.gradient_for_payoffs(Player::Player2, Player::Player2, leader_strategy);
+        let mut leader_seq_values =
+            game.gradient_for_payoffs(Player::Player2, Player::Player1, leader_strategy);
+
+        let follower_treeplex = game.treeplex(Player::Player2);
+
+        // Reach mass of each follower sequence, computed bottom-up like the sequence values with
+        // all payoffs set to one. Every sequence of an infoset has the reach mass of the infoset,
+        // whatever the follower plays below it, so we take the mean over the infoset.
+        let mut seq_reach = TreeplexVector::from_constant(follower_treeplex, 0f64);
+        for p in game.payoff_matrix().entries.iter() {
+            seq_reach[p.seq_pl2] += p.chance_factor * leader_strategy.inner()[p.seq_pl1];
+        }
+        let mut infoset_reach = vec![0f64; follower_treeplex.num_infosets()];
+        for (infoset_id, infoset) in follower_treeplex.infosets().iter().enumerate() {
+            let sequences = infoset.start_sequence..=infoset.end_sequence;
+            infoset_reach[infoset_id] = sequences
+                .clone()
+                .map(|sequence_id| seq_reach[sequence_id])
+                .sum::<f64>()
+                / sequences.count() as f64;
+            seq_reach[infoset.parent_sequence] += infoset_reach[infoset_id];
+        }
+
+        // The softmax is taken over the values divided by the reach mass, so we scale `lambda`
+        // instead. Infosets which are never reached play uniformly.
+        let scaled_lambda = |infoset_id: usize| {
+            if infoset_reach[infoset_id] > 0.0 {
+                lambda / infoset_reach[infoset_id]
+            } else {
+                0.0
+            }
+        };
+
+        let mut behavioral = TreeplexVector::from_constant(follower_treeplex, 0f64);
+        let mut follower_infoset_values = vec![0f64; follower_treeplex.num_infosets()];
+        let mut leader_infoset_values = vec![0f64; follower_treeplex.num_infosets()];
+
+        for (infoset_id, infoset) in follower_treeplex.infosets().iter().enumerate() {
+            let sequences = infoset.start_sequence..=infoset.end_sequence;
+            let scale = scaled_lambda(infoset_id);
+
+            // Shifted by the largest value for numerical stability.
+            let max_value = sequences
+                .clone()
+                .map(|sequence_id| follower_seq_values[sequence_id])
+                .fold(f64::NEG_INFINITY, f64::max);
+            let mut normalizer = 0f64;
+            for sequence_id in sequences.clone() {
+                behavioral[sequence_id] =
+                    (scale * (follower_seq_values[sequence_id] - max_value)).exp();
+                normalizer += behavioral[sequence_id];
+            }
+            for sequence_id in sequences {
+                behavioral[sequence_id] /= normalizer;
+                follower_infoset_values[infoset_id] +=
+                    behavioral[sequence_id] * follower_seq_values[sequence_id];
+                leader_infoset_values[infoset_id] +=
+                    behavioral[sequence_id] * leader_seq_values[sequence_id];
+            }
+
+            follower_seq_values[infoset.parent_sequence] += follower_infoset_values[infoset_id];
+            leader_seq_values[infoset.parent_sequence] += leader_infoset_values[infoset_id];
+        }
+        behavioral[follower_treeplex.empty_sequence_id()] = 1.0;
+
+        let follower_behavioral = BehavioralStrategy::from_treeplex_vector(behavioral);
+        let follower_sequence =
+            SequenceFormStrategy::from_behavioral_strategy(follower_behavioral.clone());
+
+        // Adjoints of the follower's sequence values, i.e., the derivative of the leader's
+        // expected payoff with respect to them. The follower's value at the empty sequence
+        // does not affect the leader. The reach masses also depend on the leader strategy, so
+        // we keep the adjoints of the sequence reach masses as well. Through the softmax, the
+        // leader's payoff depends on the reach mass of an infoset as it does on its values
+        // scaled by `-value / reach`.
+        let y = follower_sequence.inner();
+        let mut adjoints = TreeplexVector::from_constant(follower_treeplex, 0f64);
+        let mut reach_adjoints = TreeplexVector::from_constant(follower_treeplex, 0f64);
+        for (infoset_id, infoset) in follower_treeplex.infosets().iter().enumerate().rev() {
+            let sequences = infoset.start_sequence..=infoset.end_sequence;
+            let scale = scaled_lambda(infoset_id);
+            let parent_reach = y[infoset.parent_sequence];
+            let parent_adjoint = adjoints[infoset.parent_sequence];
+            let mut infoset_reach_adjoint = reach_adjoints[infoset.parent_sequence];
+            for sequence_id in sequences.clone() {
+                let prob = follower_behavioral.inner()[sequence_id];
+                let leader_deviation =
+                    leader_seq_values[sequence_id] - leader_infoset_values[infoset_id];
+                let follower_deviation =
+                    follower_seq_values[sequence_id] - follower_infoset_values[infoset_id];
+                let softmax_adjoint = scale
+                    * prob
+                    * (parent_reach * leader_deviation + parent_adjoint * follower_deviation);
+                adjoints[sequence_id] = parent_adjoint * prob + softmax_adjoint;
+                if infoset_reach[infoset_id] > 0.0 {
+                    infoset_reach_adjoint -= softmax_adjoint * follower_seq_values[sequence_id]
+                        / infoset_reach[infoset_id];
+                }
+            }
+            let num_sequences = sequences.clone().count() as f64;
+            for sequence_id in sequences {
+                reach_adjoints[sequence_id] = infoset_reach_adjoint / num_sequences;
+            }
+        }
+
+        // The leader's payoffs enter directly, weighted by the follower's sequence-form
+        // strategy, and indirectly through the follower's payoffs and the reach masses,
+        // weighted by their adjoints.
+        let mut leader_gradient =
+            TreeplexVector::from_constant(game.treeplex(Player::Player1), 0f64);
+        for p in game.payoff_matrix().entries.iter() {
+            leader_gradient[p.seq_pl1] += p.chance_factor
+                * (y[p.seq_pl2] * p.payoff_pl1
+                    + adjoints[p.seq_pl2] * p.payoff_pl2
+                    + reach_adjoints[p.seq_pl2]);
+        }
+
+        QuantalResponse {
+            lambda,
+            follower_behavioral,
+            follower_sequence,
+            follower_seq_values,
+            leader_seq_values,
+            leader_gradient,
+        }
+    }
+
+    pub fn lambda(&self) -> f64 {
+        self.lambda
+    }
+
+    pub fn follower_behavioral(&self) -> &BehavioralStrategy {
+        &self.follower_behavioral
+    }
+
+    pub fn follower_sequence(&self) -> &SequenceFormStrategy {
+        &self.follower_sequence
+    }
+
+    pub fn follower_seq_values(&self) -> &TreeplexVector {
+        &self.follower_seq_values
+    }
+
+    pub fn leader_seq_values(&self) -> &TreeplexVector {
+        &self.leader_seq_values
+    }
+
+    /// Leader's expected payoff against the quantal response.
+    pub fn leader_value(&self) -> f64 {
+        self.leader_seq_values.empty_sequence_value()
+    }
+
+    /// Follower's expected payoff under the quantal response.
+    pub fn follower_value(&self) -> f64 {
+        self.follower_seq_values.empty_sequence_value()
+    }
+
+    /// Gradient of `leader_value()` with respect to the leader's sequence-form strategy.
+    pub fn leader_gradient(&self) -> &TreeplexVector {
+        &self.leader_gradient
+    }
+}
+
+/// Optimizes the leader's strategy against a quantal-responding follower by mirror ascent
+/// over the sequence-form polytope, using the dilated entropy. Since the leader's payoff is
+/// not concave in its strategy, this only finds a local optimum, so we start from the uniform
+/// strategy and return the best iterate found.
+#[derive(Debug, Clone)]
+pub struct QuantalResponseOptimizer {
+    pub lambda: f64,
+    pub step_size: f64,
+    pub num_iterations: usize,
+}
+
+impl QuantalResponseOptimizer {
+    pub fn new(lambda: f64, step_size: f64, num_iterations: usize) -> QuantalResponseOptimizer {
+        assert!(step_size > 0.0, "Step size must be positive");
+        QuantalResponseOptimizer {
+            lambda,
+            step_size,
+            num_iterations,
+        }
+    }
+
+    /// Returns the best leader strategy found, together with its value against the
+    /// quantal response.
+    pub fn optimize(&self, game: &ExtensiveFormGame) -> (SequenceFormStrategy, f64) {
+        let dgf = DilatedEntropy::uniform(game.treeplex(Player::Player1), Entropy);
+        let mut strategy = dgf.center();
+        let mut best_strategy = strategy.clone();
+        let mut best_value = f64::NEG_INFINITY;
+        for _ in 0..self.num_iterations {
+            let quantal_response = QuantalResponse::new(game, &strategy, self.lambda);
+            if quantal_response.leader_value() > best_value {
+                best_value = quantal_response.leader_value();
+                best_strategy = strategy.clone();
+            }
+            strategy = dgf.prox(quantal_response.leader_gradient(), &strategy, self.step_size);
+        }
+
+        let final_value = QuantalResponse::new(game, &strategy, self.lambda).leader_value();
+        if final_value > best_value {
+            return (strategy, final_value);
+        }
+        (best_strategy, best_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QuantalResponse, QuantalResponseOptimizer};
+    use crate::fixtures::commitment_game;
+    use crate::game::{ExtensiveFormGame, Infoset, PayoffMatrix, PayoffMatrixEntry, Player};
+    use crate::strategy::SequenceFormStrategy;
+    use crate::treeplex::Treeplex;
+    use crate::vector::TreeplexVector;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    use std::sync::Arc;
+
+    /// The leader plays Up or Down, without the follower observing it. After Up, chance ends
+    /// the game with probability 0.5, giving (1, 2). Otherwise, and after Down, the follower
+    /// plays Left or Right, and after Right chooses again between X and Y.
+    /// ```text
+    ///                Left    Right, X  Right, Y
+    ///     Up        (2, 1)    (4, 0)    (0, 2)
+    ///     Down      (1, 0)    (3, 1)    (2, 0)
+    /// ```
+    /// Follower sequences are X and Y (0, 1), then Left and Right (2, 3).
+    fn chance_game() -> ExtensiveFormGame {
+        let treeplex_pl1 = Treeplex::new(
+            Player::Player1,
+            3,
+            vec![Infoset::new(2, 0, 1)].into_boxed_slice(),
+        );
+        let treeplex_pl2 = Treeplex::new(
+            Player::Player2,
+            5,
+            vec![Infoset::new(3, 0, 1), Infoset::new(4, 2, 3)].into_boxed_slice(),
+        );
+        let payoff_matrix = PayoffMatrix::new(vec![
+            PayoffMatrixEntry::new(0, 4, 0.5, 1.0, 2.0),
+            PayoffMatrixEntry::new(0, 2, 0.5, 2.0, 1.0),
+            PayoffMatrixEntry::new(0, 0, 0.5, 4.0, 0.0),
+            PayoffMatrixEntry::new(0, 1, 0.5, 0.0, 2.0),
+            PayoffMatrixEntry::new(1, 2, 1.0, 1.0, 0.0),
+            PayoffMatrixEntry::new(1, 0, 1.0, 3.0, 1.0),
+            PayoffMatrixEntry::new(1, 1, 1.0, 2.0, 0.0),
+        ]);
+        ExtensiveFormGame::new(
+            Arc::new(treeplex_pl1),
+            Arc::new(treeplex_pl2),
+            payoff_matrix,
+            vec![],
+            vec![],
+            vec![],
+        )
+    }
+
+    fn leader_strategy(game: &ExtensiveFormGame, prob_up: f64) -> SequenceFormStrategy {
+        SequenceFormStrategy::from_treeplex_vector(TreeplexVector::from_vec(
+            game.treeplex(Player::Player1),
+            vec![prob_up, 1.0 - prob_up, 1.0],
+        ))
+    }
+
+    /// Derivative of the leader's value along Up, against finite differences.
+    fn check_gradient(game: &ExtensiveFormGame, prob_up: f64, lambda: f64) {
+        let step = 1e-6;
+        let gradient = QuantalResponse::new(game, &leader_strategy(game, prob_up), lambda)
+            .leader_gradient()
+            .clone();
+        let value = |prob_up| {
+            QuantalResponse::new(game, &leader_strategy(game, prob_up), lambda).leader_value()
+        };
+        let finite_difference = (value(prob_up + step) - value(prob_up - step)) / (2.0 * step);
+        assert_approx_eq!(gradient[0] - gradient[1], finite_difference, 1e-5);
+    }
+
+    #[test]
+    fn quantal_response_and_gradient() {
+        let game = commitment_game();
+
+        // Follower values Left and Right at 0.6 and 0.4 respectively.
+        let quantal_response = QuantalResponse::new(&game, &leader_strategy(&game, 0.6), 2.0);
+        let prob_left = 1.0 / (1.0 + (-0.4f64).exp());
+        assert_approx_eq!(quantal_response.follower_behavioral().inner()[0], prob_left);
+        assert_approx_eq!(
+            quantal_response.leader_value(),
+            prob_left * 1.6 + (1.0 - prob_left) * 3.6
+        );
+        check_gradient(&game, 0.6, 2.0);
+
+        // Against a uniformly random follower, the leader should simply play Up.
+        let (strategy, value) = QuantalResponseOptimizer::new(0.0, 1.0, 100).optimize(&game);
+        assert_approx_eq!(strategy.inner()[0], 1.0);
+        assert_approx_eq!(value, 3.0);
+
+        // Against a nearly rational follower, the leader should do almost as well as the SSE.
+        let (_, value) = QuantalResponseOptimizer::new(100.0, 0.01, 2000).optimize(&game);
+        assert!(value > 3.3 && value < 3.5);
+    }
+
+    #[test]
+    fn quantal_response_conditioned_on_reach() {
+        let game = chance_game();
+
+        // The follower's infosets are reached with mass 0.3 + 0.4 = 0.7, over which X and Y are
+        // worth 0.4 and 0.6, and Left is worth 0.3.
+        let quantal_response = QuantalResponse::new(&game, &leader_strategy(&game, 0.6), 2.0);
+        let prob_x = 1.0 / (1.0 + (2.0 * 0.2f64 / 0.7).exp());
+        let right_value = prob_x * 0.4 + (1.0 - prob_x) * 0.6;
+        let prob_left = 1.0 / (1.0 + (2.0 * (right_value - 0.3) / 0.7).exp());
+        let behavioral = quantal_response.follower_behavioral().inner();
+        assert_approx_eq!(behavioral[0], prob_x);
+        assert_approx_eq!(behavioral[2], prob_left);
+        assert_approx_eq!(
+            quantal_response.leader_value(),
+            0.3 + 0.3 * (prob_left * 2.0 + (1.0 - prob_left) * prob_x * 4.0)
+                + 0.4 * (prob_left + (1.0 - prob_left) * (prob_x * 3.0 + (1.0 - prob_x) * 2.0))
+        );
+
+        check_gradient(&game, 0.6, 2.0);
+        check_gradient(&game, 0.2, 5.0);
+        check_gradient(&game, 0.95, 0.5);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::game::{ExtensiveFormGame, Player};
+use crate::strategy::SequenceFormStrategy;
+
+/// Leader's worst-case payoff of a strategy against an epsilon-rational follower, i.e., one who
+/// may choose any action whose value (assuming optimal play afterwards) is strictly within
+/// `epsilon` of the best action at every infoset, and does so in the way that hurts the leader
+/// most. Since the follower's action sets do not depend on choices made below, this is computed
+/// bottom-up over the follower's treeplex.
+pub fn worst_case_leader_value(
+    game: &ExtensiveFormGame,
+    leader_strategy: &SequenceFormStrategy,
+    epsilon: f64,
+) -> f64 {
+    assert!(epsilon > 0.0, "Robust values require a positive epsilon");
+    let mut follower_seq_values =
+        game.gradient_for_payoffs(Player::Player2, Player::Player2, leader_strategy);
+    let mut leader_seq_values =
+        game.gradient_for_payoffs(Player::Player2, Player::Player1, leader_strategy);
+
+    let treeplex = game.treeplex(Player::Player2);
+    for infoset in treeplex.infosets().iter() {
+        let sequences = infoset.start_sequence..=infoset.end_sequence;
+        let best_follower_value = sequences
+            .clone()
+            .map(|sequence_id| follower_seq_values[sequence_id])
+            .fold(f64::NEG_INFINITY, f64::max);
+        let worst_leader_value = sequences
+            .filter(|sequence_id| follower_seq_values[*sequence_id] > best_follower_value - epsilon)
+            .map(|sequence_id| leader_seq_values[sequence_id])
+            .fold(f64::INFINITY, f64::min);
+
+        follower_seq_values[infoset.parent_sequence] += best_follower_value;
+        leader_seq_values[infoset.parent_sequence] += worst_leader_value;
+    }
+
+    leader_seq_values[treeplex.empty_sequence_id()]
+}
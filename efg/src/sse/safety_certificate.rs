@@ -0,0 +1,263 @@
+use crate::game::{ExtensiveFormGame, Player, SubgameOrFree};
+use crate::sse::{BlueprintBr, ValueBound};
+use crate::strategy::SequenceFormStrategy;
+
+/// Whether the follower's value at a subgame head under the refined strategy satisfies the
+/// bound imposed on it while resolving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundStatus {
+    Slack,
+    Tight,
+    Violated,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BoundCheck {
+    pub infoset_id: usize,
+    pub bound: ValueBound,
+    pub refined_value: f64,
+    pub status: BoundStatus,
+}
+
+/// Leader's payoff from the leaves of a top-level subgame (including nested ones), under the
+/// blueprint and the refined strategy, each against its own follower best response.
+#[derive(Debug, Clone, Copy)]
+pub struct SubgameContribution {
+    pub subgame_id: usize,
+    pub blueprint_value: f64,
+    pub refined_value: f64,
+}
+
+/// Certifies that a refined leader strategy, obtained by stitching resolved subgames into the
+/// blueprint, is safe, i.e., that the follower's best response in the trunk is unchanged and the
+/// bounds at each subgame head hold. Best responses to the refined strategy use the same
+/// follower model (tie breaking) as the blueprint's.
+#[derive(Debug, Clone)]
+pub struct SafetyCertificate {
+    // Free infosets reached by the follower's best response to the blueprint where the best
+    // response to the refined strategy differs.
+    pub deviating_infosets: Vec<usize>,
+
+    pub blueprint_leader_value: f64,
+    pub refined_leader_value: f64,
+
+    pub bound_checks: Vec<BoundCheck>,
+    pub subgame_contributions: Vec<SubgameContribution>,
+}
+
+impl SafetyCertificate {
+    /// Verifies `refined_strategy` against the blueprint of `blueprint_br` and the bounds on
+    /// follower infosets used while resolving (e.g., those of `BoundsGenerator`). Bounds are
+    /// considered tight if they hold with equality up to `tolerance`.
+    pub fn new(
+        game: &ExtensiveFormGame,
+        blueprint_br: &BlueprintBr,
+        refined_strategy: &SequenceFormStrategy,
+        follower_bounds: &[ValueBound],
+        tolerance: f64,
+    ) -> SafetyCertificate {
+        let follower_treeplex = game.treeplex(Player::Player2);
+        assert_eq!(follower_bounds.len(), follower_treeplex.num_infosets());
+        let refined_br = BlueprintBr::with_tie_breaking(
+            game,
+            refined_strategy,
+            blueprint_br.tie_breaking().clone(),
+        );
+
+        let blueprint_follower = blueprint_br.follower_sequence().inner();
+        let deviating_infosets = follower_treeplex
+            .infosets()
+            .iter()
+            .enumerate()
+            .filter(|(infoset_id, infoset)| {
+                game.subgame(Player::Player2, *infoset_id) == SubgameOrFree::Free
+                    && blueprint_follower[infoset.parent_sequence] > 0.0
+                    && blueprint_br.follower_behavioral_index(*infoset_id)
+                        != refined_br.follower_behavioral_index(*infoset_id)
+            })
+            .map(|(infoset_id, _)| infoset_id)
+            .collect::<Vec<usize>>();
+
+        let bound_checks = follower_bounds
+            .iter()
+            .enumerate()
+            .filter_map(|(infoset_id, bound)| {
+                let refined_value = refined_br.follower_infoset_value(infoset_id);
+                let slack = match *bound {
+                    ValueBound::LowerBound(x) => refined_value - x,
+                    ValueBound::UpperBound(x) => x - refined_value,
+                    ValueBound::None => return None,
+                };
+                let status = if slack < -tolerance {
+                    BoundStatus::Violated
+                } else if slack <= tolerance {
+                    BoundStatus::Tight
+                } else {
+                    BoundStatus::Slack
+                };
+                Some(BoundCheck {
+                    infoset_id,
+                    bound: *bound,
+                    refined_value,
+                    status,
+                })
+            })
+            .collect::<Vec<BoundCheck>>();
+
+        let blueprint_strategy = blueprint_br.leader_blueprint();
+        let subgame_contributions = game
+            .top_level_subgames()
+            .into_iter()
+            .map(|subgame_id| SubgameContribution {
+                subgame_id,
+                blueprint_value: Self::subgame_leader_value(
+                    game,
+                    subgame_id,
+                    blueprint_strategy,
+                    blueprint_br.follower_sequence(),
+                ),
+                refined_value: Self::subgame_leader_value(
+                    game,
+                    subgame_id,
+                    refined_strategy,
+                    refined_br.follower_sequence(),
+                ),
+            })
+            .collect::<Vec<SubgameContribution>>();
+
+        let empty_sequence_id = follower_treeplex.empty_sequence_id();
+        SafetyCertificate {
+            deviating_infosets,
+            blueprint_leader_value: blueprint_br.leader_seq_value(empty_sequence_id),
+            refined_leader_value: refined_br.leader_seq_value(empty_sequence_id),
+            bound_checks,
+            subgame_contributions,
+        }
+    }
+
+    /// True if the follower's best response is unchanged in the trunk.
+    pub fn trunk_preserved(&self) -> bool {
+        self.deviating_infosets.is_empty()
+    }
+
+    pub fn violated_bounds(&self) -> Vec<BoundCheck> {
+        self.bound_checks
+            .iter()
+            .filter(|x| x.status == BoundStatus::Violated)
+            .cloned()
+            .collect()
+    }
+
+    pub fn num_tight_bounds(&self) -> usize {
+        self.bound_checks
+            .iter()
+            .filter(|x| x.status == BoundStatus::Tight)
+            .count()
+    }
+
+    pub fn is_safe(&self) -> bool {
+        self.trunk_preserved() && self.violated_bounds().is_empty()
+    }
+
+    fn subgame_leader_value(
+        game: &ExtensiveFormGame,
+        subgame_id: usize,
+        leader_strategy: &SequenceFormStrategy,
+        follower_strategy: &SequenceFormStrategy,
+    ) -> f64 {
+        let x = leader_strategy.inner();
+        let y = follower_strategy.inner();
+        game.subgame_leaf_range(subgame_id)
+            .map(|payoff_index| {
+                let entry = game.payoff_entry(payoff_index);
+                entry.chance_factor * x[entry.seq_pl1] * y[entry.seq_pl2] * entry.payoff_pl1
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+pub mod test_fixtures {
+    use super::{BoundStatus, SafetyCertificate};
+    use crate::fixtures::{commitment_game, nested_commitment_game};
+    use crate::game::Player;
+    use crate::sse::{BlueprintBr, ValueBound};
+    use crate::strategy::SequenceFormStrategy;
+    use crate::vector::TreeplexVector;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn safety_certificate() {
+        let game = commitment_game();
+        let leader_strategy = |prob_up: f64| {
+            SequenceFormStrategy::from_treeplex_vector(TreeplexVector::from_vec(
+                game.treeplex(Player::Player1),
+                vec![prob_up, 1.0 - prob_up, 1.0],
+            ))
+        };
+        let blueprint = leader_strategy(0.5);
+        let blueprint_br = BlueprintBr::new(&game, &blueprint);
+
+        // Refinements keeping the follower on Right are safe, while those leading it to
+        // Left are not.
+        let refined = leader_strategy(0.4);
+        let certificate =
+            SafetyCertificate::new(&game, &blueprint_br, &refined, &[ValueBound::None], 1e-9);
+        assert!(certificate.is_safe());
+        assert_approx_eq!(certificate.blueprint_leader_value, 3.5);
+        assert_approx_eq!(certificate.refined_leader_value, 3.4);
+
+        let refined = leader_strategy(0.6);
+        let certificate =
+            SafetyCertificate::new(&game, &blueprint_br, &refined, &[ValueBound::None], 1e-9);
+        assert!(!certificate.trunk_preserved());
+        assert_eq!(certificate.deviating_infosets, vec![0]);
+
+        // Bounds are checked against the follower's value under the refined strategy, 0.6.
+        let bounds = [ValueBound::LowerBound(0.6)];
+        let certificate = SafetyCertificate::new(&game, &blueprint_br, &refined, &bounds, 1e-9);
+        assert_eq!(certificate.bound_checks[0].status, BoundStatus::Tight);
+        let bounds = [ValueBound::UpperBound(0.5)];
+        let certificate = SafetyCertificate::new(&game, &blueprint_br, &refined, &bounds, 1e-9);
+        assert_eq!(certificate.violated_bounds().len(), 1);
+    }
+
+    #[test]
+    fn subgame_contributions() {
+        let game = nested_commitment_game();
+        let leader_strategy = |prob_a: f64| {
+            SequenceFormStrategy::from_treeplex_vector(TreeplexVector::from_vec(
+                game.treeplex(Player::Player1),
+                vec![
+                    0.5 * prob_a,
+                    0.5 * (1.0 - prob_a),
+                    0.5 * prob_a,
+                    0.5 * (1.0 - prob_a),
+                    0.5,
+                    0.5,
+                    1.0,
+                ],
+            ))
+        };
+        let blueprint_br = BlueprintBr::new(&game, &leader_strategy(0.25));
+
+        // The follower keeps playing In, Right and D, but its value at the head of subgame 0
+        // drops from 0.75 to 0.5.
+        let bounds = [
+            ValueBound::None,
+            ValueBound::LowerBound(0.5),
+            ValueBound::None,
+        ];
+        let certificate =
+            SafetyCertificate::new(&game, &blueprint_br, &leader_strategy(0.5), &bounds, 1e-9);
+        assert!(certificate.is_safe());
+        assert_eq!(certificate.num_tight_bounds(), 1);
+        assert_eq!(certificate.subgame_contributions.len(), 1);
+        let contribution = certificate.subgame_contributions[0];
+        assert_eq!(contribution.subgame_id, 0);
+        assert_approx_eq!(contribution.blueprint_value, 3.25);
+        assert_approx_eq!(contribution.refined_value, 3.5);
+        assert_approx_eq!(certificate.refined_leader_value, 3.5);
+    }
+}
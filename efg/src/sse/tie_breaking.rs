@@ -0,0 +1,126 @@
+use crate::treeplex::SequenceId;
+use crate::vector::TreeplexVector;
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Chooses a sequence among those the follower is indifferent between at an infoset, given
+/// (infoset_id, tied sequences, leader's value of each follower sequence). Tied sequences start
+/// with the one of highest value to the follower, and the leader's values are final for all
+/// sequences at and below the infoset. Must return one of the tied sequences.
+pub type TieBreakingCallback =
+    Arc<dyn Fn(usize, &[SequenceId], &TreeplexVector) -> SequenceId + Send + Sync>;
+
+/// How the follower chooses among (epsilon-)optimal sequences at each infoset.
+#[derive(Clone)]
+pub enum TieBreakingRule {
+    /// Ties are broken in favour of the leader, as in the Strong Stackelberg Equilibrium.
+    LeaderFavouring,
+
+    /// Ties are broken against the leader, as in the Weak (pessimistic) Stackelberg Equilibrium.
+    LeaderAdverse,
+
+    /// Ties are broken by picking the sequence with the lowest index.
+    LowestIndex,
+
+    /// Ties are broken by a user-supplied callback.
+    Custom(TieBreakingCallback),
+}
+
+impl fmt::Debug for TieBreakingRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TieBreakingRule::LeaderFavouring => write!(f, "LeaderFavouring"),
+            TieBreakingRule::LeaderAdverse => write!(f, "LeaderAdverse"),
+            TieBreakingRule::LowestIndex => write!(f, "LowestIndex"),
+            TieBreakingRule::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+impl FromStr for TieBreakingRule {
+    type Err = String;
+    fn from_str(tie_breaking_rule: &str) -> Result<Self, Self::Err> {
+        match tie_breaking_rule {
+            "strong" | "leader_favouring" => Ok(TieBreakingRule::LeaderFavouring),
+            "weak" | "pessimistic" | "leader_adverse" => Ok(TieBreakingRule::LeaderAdverse),
+            "lowest_index" => Ok(TieBreakingRule::LowestIndex),
+            s => Err(format!("Unknown tie breaking rule: {}", s)),
+        }
+    }
+}
+
+/// Follower model used when computing best responses to the blueprint. Sequences whose
+/// values are within `epsilon` of the best one are considered tied.
+#[derive(Debug, Clone)]
+pub struct TieBreaking {
+    pub rule: TieBreakingRule,
+    pub epsilon: f64,
+}
+
+impl TieBreaking {
+    pub fn new(rule: TieBreakingRule, epsilon: f64) -> TieBreaking {
+        assert!(epsilon >= 0.0, "Tie breaking epsilon must be nonnegative");
+        TieBreaking { rule, epsilon }
+    }
+
+    /// Whether the follower's choice among ties coincides with the SSE assumption, under
+    /// which the subgame MILPs also break ties.
+    pub fn is_leader_favouring(&self) -> bool {
+        matches!(self.rule, TieBreakingRule::LeaderFavouring)
+    }
+
+    /// Gap that safety bounds keep between the follower's blueprint sequence and the others
+    /// at an infoset, so that they are never tied after resolving. Zero when ties are broken
+    /// in favour of the leader.
+    pub fn separation_margin(&self) -> f64 {
+        match self.is_leader_favouring() {
+            true => 0.0,
+            false => 2.0 * self.epsilon,
+        }
+    }
+
+    /// Picks a sequence among `tied_sequences`, which is nonempty and starts with the
+    /// sequence of highest value to the follower.
+    pub fn choose(
+        &self,
+        infoset_id: usize,
+        tied_sequences: &[SequenceId],
+        leader_seq_values: &TreeplexVector,
+    ) -> SequenceId {
+        // Replaces the current choice only if the leader is strictly better off (or worse off),
+        // so that ties in the leader's values go to the follower's best sequence.
+        let pick = |better: fn(f64, f64) -> bool| {
+            tied_sequences[1..]
+                .iter()
+                .fold(tied_sequences[0], |chosen, &sequence_id| {
+                    match better(leader_seq_values[sequence_id], leader_seq_values[chosen]) {
+                        true => sequence_id,
+                        false => chosen,
+                    }
+                })
+        };
+        match &self.rule {
+            TieBreakingRule::LeaderFavouring => pick(|a, b| a > b),
+            TieBreakingRule::LeaderAdverse => pick(|a, b| a < b),
+            TieBreakingRule::LowestIndex => *tied_sequences.iter().min().unwrap(),
+            TieBreakingRule::Custom(callback) => {
+                let sequence_id = callback(infoset_id, tied_sequences, leader_seq_values);
+                assert!(
+                    tied_sequences.contains(&sequence_id),
+                    "Tie breaking callback chose sequence {:?}, which is not among {:?}",
+                    sequence_id,
+                    tied_sequences
+                );
+                sequence_id
+            }
+        }
+    }
+}
+
+impl Default for TieBreaking {
+    fn default() -> TieBreaking {
+        TieBreaking::new(TieBreakingRule::LeaderFavouring, 1e-7)
+    }
+}
@@ -1,6 +1,7 @@
 use crate::strategy::SequenceFormStrategy;
 use crate::treeplex::Treeplex;
 use crate::vector::TreeplexVector;
+use std::sync::Arc;
 
 use assert_approx_eq::assert_approx_eq;
 const THRESHOLD_ACCURACY: f64 = 1e-6;
@@ -8,15 +9,15 @@ const EFFECTIVELY_ZERO: f64 = 1e-6;
 
 /// `BehavioralStrategy` is a specialized `TreeplexVector` with some additional functionality.
 #[derive(Debug, Clone)]
-pub struct BehavioralStrategy<'a>(TreeplexVector<'a>);
-impl<'a> BehavioralStrategy<'a> {
-    pub fn from_treeplex_vector(vector: TreeplexVector<'a>) -> BehavioralStrategy {
+pub struct BehavioralStrategy(TreeplexVector);
+impl BehavioralStrategy {
+    pub fn from_treeplex_vector(vector: TreeplexVector) -> BehavioralStrategy {
         BehavioralStrategy::validate(&vector);
         BehavioralStrategy(vector)
     }
 
     /// Validate if the given vector is a legitimate behavioral strategy. Panics upon failure.
-    pub fn validate(vector: &TreeplexVector<'a>) {
+    pub fn validate(vector: &TreeplexVector) {
         for infoset_id in 0..vector.treeplex().num_infosets() {
             let infoset = vector.treeplex().infosets()[infoset_id];
             let mut total_mass = 0f64;
@@ -30,7 +31,7 @@ impl<'a> BehavioralStrategy<'a> {
     }
 
     pub fn from_sequence_form_strategy(
-        sequence_form_strategy: SequenceFormStrategy<'a>,
+        sequence_form_strategy: SequenceFormStrategy,
     ) -> BehavioralStrategy {
         let mut vector = sequence_form_strategy.into_inner();
         for infoset_id in 0..vector.treeplex().num_infosets() {
@@ -58,8 +59,8 @@ impl<'a> BehavioralStrategy<'a> {
         BehavioralStrategy(vector)
     }
 
-    pub fn from_uniform_strategy(treeplex: &'a Treeplex) -> BehavioralStrategy {
-        let mut vector: TreeplexVector<'a> = TreeplexVector::from_constant(treeplex, -10f64);
+    pub fn from_uniform_strategy(treeplex: &Arc<Treeplex>) -> BehavioralStrategy {
+        let mut vector: TreeplexVector = TreeplexVector::from_constant(treeplex, -10f64);
         vector[treeplex.empty_sequence_id()] = 1.0;
         for infoset in treeplex.infosets().iter() {
             let num_sequences = infoset.end_sequence - infoset.start_sequence + 1;
@@ -71,11 +72,11 @@ impl<'a> BehavioralStrategy<'a> {
         Self::from_treeplex_vector(vector)
     }
 
-    pub fn into_inner(self) -> TreeplexVector<'a> {
+    pub fn into_inner(self) -> TreeplexVector {
         self.0
     }
 
-    pub fn inner(&self) -> &TreeplexVector<'a> {
+    pub fn inner(&self) -> &TreeplexVector {
         &self.0
     }
 
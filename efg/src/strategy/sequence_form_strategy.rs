@@ -2,20 +2,21 @@ use crate::strategy::BehavioralStrategy;
 use crate::vector::TreeplexVector;
 use crate::treeplex::Treeplex;
 use assert_approx_eq::assert_approx_eq;
+use std::sync::Arc;
 
 const THRESHOLD_ACCURACY: f64 = 1e-6;
 
 /// `SequenceFormStrategy` is a specialized `TreeplexVector` with some additional functionality.
 #[derive(Debug, Clone)]
-pub struct SequenceFormStrategy<'a>(TreeplexVector<'a>);
-impl<'a> SequenceFormStrategy<'a> {
-    pub fn from_treeplex_vector(vector: TreeplexVector<'a>) -> SequenceFormStrategy {
+pub struct SequenceFormStrategy(TreeplexVector);
+impl SequenceFormStrategy {
+    pub fn from_treeplex_vector(vector: TreeplexVector) -> SequenceFormStrategy {
         SequenceFormStrategy::validate(&vector);
         SequenceFormStrategy(vector)
     }
 
     pub fn from_behavioral_strategy(
-        behavioral_strategy: BehavioralStrategy<'a>,
+        behavioral_strategy: BehavioralStrategy,
     ) -> SequenceFormStrategy {
         // Perform converstion from behavioral to sequence form in-place via top down traversal
         // of the treeplex.
@@ -35,7 +36,7 @@ impl<'a> SequenceFormStrategy<'a> {
     }
 
     /// Validate if the given vector is a legitimate sequence-form strategy. Panics upon failure.
-    pub fn validate(vector: &TreeplexVector<'a>) {
+    pub fn validate(vector: &TreeplexVector) {
         assert_approx_eq!(vector.empty_sequence_value(), 1.0, THRESHOLD_ACCURACY);
         for infoset_id in 0..vector.treeplex().num_infosets() {
             let infoset = vector.treeplex().infosets()[infoset_id];
@@ -53,15 +54,15 @@ impl<'a> SequenceFormStrategy<'a> {
 
     }
 
-    pub fn from_uniform_strategy(treeplex: &'a Treeplex) -> SequenceFormStrategy<'a>{
+    pub fn from_uniform_strategy(treeplex: &Arc<Treeplex>) -> SequenceFormStrategy {
         Self::from_behavioral_strategy(BehavioralStrategy::from_uniform_strategy(treeplex))
     }
 
-    pub fn into_inner(self) -> TreeplexVector<'a> {
+    pub fn into_inner(self) -> TreeplexVector {
         self.0
     }
 
-    pub fn inner(&self) -> &TreeplexVector<'a> {
+    pub fn inner(&self) -> &TreeplexVector {
         &self.0
     }
 }
\ No newline at end of file
@@ -0,0 +1,330 @@
+use crate::strategy::{BehavioralStrategy, SequenceFormStrategy};
+use crate::treeplex::Treeplex;
+use crate::vector::TreeplexVector;
+
+use std::sync::Arc;
+
+/// Strongly convex function over the probability simplex. Dilated distance-generating
+/// functions are built by applying a `SimplexRegularizer` to the behavioral strategy
+/// at each infoset.
+pub trait SimplexRegularizer {
+    /// Value of the regularizer at `b`.
+    fn value(&self, b: &[f64]) -> f64;
+
+    /// Writes the gradient of the regularizer at `b` into `gradient`.
+    fn gradient(&self, b: &[f64], gradient: &mut [f64]);
+
+    /// Writes argmax_b <g, b> - weight * psi(b) over the simplex into `b`, returning
+    /// the maximum value.
+    fn smoothed_max(&self, g: &[f64], weight: f64, b: &mut [f64]) -> f64;
+}
+
+/// Negative entropy, psi(b) = sum_i b_i log(b_i).
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Entropy;
+
+/// Squared Euclidean norm, psi(b) = 0.5 * ||b||^2.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Euclidean;
+
+impl SimplexRegularizer for Entropy {
+    fn value(&self, b: &[f64]) -> f64 {
+        b.iter().filter(|x| **x > 0.0).map(|x| x * x.ln()).sum()
+    }
+
+    fn gradient(&self, b: &[f64], gradient: &mut [f64]) {
+        // Clamp probabilities away from zero, which may occur due to underflow.
+        for (g, x) in gradient.iter_mut().zip(b.iter()) {
+            *g = x.max(f64::MIN_POSITIVE).ln() + 1.0;
+        }
+    }
+
+    fn smoothed_max(&self, g: &[f64], weight: f64, b: &mut [f64]) -> f64 {
+        // Softmax with temperature `weight`, shifted by the largest entry for stability.
+        let max_g = g.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        for (x, g_i) in b.iter_mut().zip(g.iter()) {
+            *x = ((g_i - max_g) / weight).exp();
+        }
+        let normalizer: f64 = b.iter().sum();
+        for x in b.iter_mut() {
+            *x /= normalizer;
+        }
+        max_g + weight * normalizer.ln()
+    }
+}
+
+impl SimplexRegularizer for Euclidean {
+    fn value(&self, b: &[f64]) -> f64 {
+        0.5 * b.iter().map(|x| x * x).sum::<f64>()
+    }
+
+    fn gradient(&self, b: &[f64], gradient: &mut [f64]) {
+        gradient.copy_from_slice(b);
+    }
+
+    fn smoothed_max(&self, g: &[f64], weight: f64, b: &mut [f64]) -> f64 {
+        for (x, g_i) in b.iter_mut().zip(g.iter()) {
+            *x = g_i / weight;
+        }
+        project_to_simplex(b);
+        let linear_term: f64 = g.iter().zip(b.iter()).map(|(g_i, x)| g_i * x).sum();
+        linear_term - weight * self.value(b)
+    }
+}
+
+/// Inplace Euclidean projection onto the probability simplex, using the sorting-based
+/// algorithm of Held, Wolfe and Crowder.
+pub fn project_to_simplex(v: &mut [f64]) {
+    let mut sorted = v.to_vec();
+    sorted.sort_by(|a, b| b.partial_cmp(a).unwrap());
+
+    let mut cumulative_sum = 0f64;
+    let mut threshold = 0f64;
+    for (index, u) in sorted.iter().enumerate() {
+        cumulative_sum += u;
+        let candidate = (cumulative_sum - 1.0) / ((index + 1) as f64);
+        if u - candidate > 0.0 {
+            threshold = candidate;
+        }
+    }
+    for x in v.iter_mut() {
+        *x = (*x - threshold).max(0.0);
+    }
+}
+
+/// Dilated distance-generating function over the sequence-form polytope of a treeplex,
+///     d(x) = sum_I w_I x_{p(I)} psi(x_I / x_{p(I)}),
+/// where p(I) is the parent sequence of infoset I, x_I are the sequences of I and
+/// w_I > 0 is the weight of I. All optimization problems are posed as maximization,
+/// in keeping with `Treeplex::inplace_sequence_form_br`.
+pub struct DilatedDgf<R: SimplexRegularizer> {
+    treeplex: Arc<Treeplex>,
+    weights: Vec<f64>,
+    regularizer: R,
+}
+
+pub type DilatedEntropy = DilatedDgf<Entropy>;
+pub type DilatedEuclidean = DilatedDgf<Euclidean>;
+
+impl<R: SimplexRegularizer> DilatedDgf<R> {
+    /// Constructs a dilated DGF with a weight for each infoset of `treeplex`.
+    pub fn new(treeplex: &Arc<Treeplex>, regularizer: R, weights: Vec<f64>) -> DilatedDgf<R> {
+        assert_eq!(weights.len(), treeplex.num_infosets());
+        assert!(weights.iter().all(|x| *x > 0.0), "Infoset weights must be positive.");
+        DilatedDgf {
+            treeplex: treeplex.clone(),
+            weights,
+            regularizer,
+        }
+    }
+
+    /// Constructs a dilated DGF where all infosets have unit weight.
+    pub fn uniform(treeplex: &Arc<Treeplex>, regularizer: R) -> DilatedDgf<R> {
+        Self::new(treeplex, regularizer, vec![1.0; treeplex.num_infosets()])
+    }
+
+    pub fn treeplex(&self) -> &Arc<Treeplex> {
+        &self.treeplex
+    }
+
+    pub fn weights(&self) -> &Vec<f64> {
+        &self.weights
+    }
+
+    /// Value of the DGF at the sequence-form strategy `strategy`.
+    pub fn value(&self, strategy: &SequenceFormStrategy) -> f64 {
+        let x = strategy.inner();
+        let mut value = 0f64;
+        let mut local_strategy = Vec::<f64>::new();
+        for (infoset_id, infoset) in self.treeplex.infosets().iter().enumerate() {
+            let parent_mass = x[infoset.parent_sequence];
+            if parent_mass <= 0.0 {
+                continue;
+            }
+            local_strategy.clear();
+            local_strategy.extend(
+                (infoset.start_sequence..=infoset.end_sequence).map(|s| x[s] / parent_mass),
+            );
+            value += self.weights[infoset_id] * parent_mass * self.regularizer.value(&local_strategy);
+        }
+        value
+    }
+
+    /// Gradient of the DGF at `strategy`. Infosets reached with probability zero
+    /// are treated as if their behavioral strategy was uniform.
+    pub fn gradient(&self, strategy: &SequenceFormStrategy) -> TreeplexVector {
+        let behavioral = BehavioralStrategy::from_sequence_form_strategy(strategy.clone());
+        let b = behavioral.inner();
+        let mut gradient = TreeplexVector::from_constant(&self.treeplex, 0f64);
+        let mut local_gradient = Vec::<f64>::new();
+        for (infoset_id, infoset) in self.treeplex.infosets().iter().enumerate() {
+            let weight = self.weights[infoset_id];
+            let local_strategy = &b.entries[infoset.start_sequence..=infoset.end_sequence];
+            local_gradient.resize(local_strategy.len(), 0f64);
+            self.regularizer.gradient(local_strategy, &mut local_gradient);
+
+            // The term w * x_p * psi(x_I / x_p) contributes w * grad psi(b) to each
+            // sequence in the infoset, and w * (psi(b) - <grad psi(b), b>) to the parent.
+            let mut inner_product = 0f64;
+            for (offset, sequence_id) in (infoset.start_sequence..=infoset.end_sequence).enumerate() {
+                gradient[sequence_id] += weight * local_gradient[offset];
+                inner_product += local_gradient[offset] * local_strategy[offset];
+            }
+            gradient[infoset.parent_sequence] +=
+                weight * (self.regularizer.value(local_strategy) - inner_product);
+        }
+        gradient
+    }
+
+    /// Computes inplace argmax_x <g, x> - d(x) over the sequence-form polytope, where
+    /// g is given by `gradient`. Returns the optimal value together with the maximizer.
+    /// This is performed bottom up, in the same way as the best response in
+    /// `Treeplex::inplace_behavioral_br`.
+    pub fn smoothed_br(&self, mut gradient: TreeplexVector) -> (f64, SequenceFormStrategy) {
+        assert_eq!(gradient.len(), self.treeplex.num_sequences());
+        let mut local_strategy = Vec::<f64>::new();
+        for (infoset_id, infoset) in self.treeplex.infosets().iter().enumerate() {
+            let local_gradient = &gradient.entries[infoset.start_sequence..=infoset.end_sequence];
+            local_strategy.resize(local_gradient.len(), 0f64);
+            let value = self.regularizer.smoothed_max(
+                local_gradient,
+                self.weights[infoset_id],
+                &mut local_strategy,
+            );
+            gradient.entries[infoset.start_sequence..=infoset.end_sequence]
+                .copy_from_slice(&local_strategy);
+            gradient[infoset.parent_sequence] += value;
+        }
+
+        let empty_sequence_id = self.treeplex.empty_sequence_id();
+        let value = gradient[empty_sequence_id];
+        gradient[empty_sequence_id] = 1.0;
+
+        let behavioral = BehavioralStrategy::from_treeplex_vector(gradient);
+        (value, SequenceFormStrategy::from_behavioral_strategy(behavioral))
+    }
+
+    /// The minimizer of d, also known as the prox center.
+    pub fn center(&self) -> SequenceFormStrategy {
+        self.smoothed_br(TreeplexVector::from_constant(&self.treeplex, 0f64)).1
+    }
+
+    /// Bregman divergence D(x || center) = d(x) - d(center) - <grad d(center), x - center>.
+    pub fn bregman_divergence(
+        &self,
+        strategy: &SequenceFormStrategy,
+        center: &SequenceFormStrategy,
+    ) -> f64 {
+        let center_gradient = self.gradient(center);
+        let difference = strategy.inner() - center.inner();
+        self.value(strategy) - self.value(center) - center_gradient.inner(&difference)
+    }
+
+    /// Proximal (mirror ascent) step from `center` along `gradient`, i.e.,
+    ///     argmax_x step_size * <g, x> - D(x || center).
+    pub fn prox(
+        &self,
+        gradient: &TreeplexVector,
+        center: &SequenceFormStrategy,
+        step_size: f64,
+    ) -> SequenceFormStrategy {
+        let shifted_gradient = gradient * step_size + &self.gradient(center);
+        self.smoothed_br(shifted_gradient).1
+    }
+}
+
+#[cfg(test)]
+pub mod test_fixtures {
+    use super::{DilatedDgf, DilatedEntropy, DilatedEuclidean, Entropy, Euclidean, SimplexRegularizer};
+    use crate::strategy::SequenceFormStrategy;
+    use crate::treeplex::treeplex::test_fixtures::KUHN_TREEPLEX_PL1;
+    use crate::vector::TreeplexVector;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    fn test_gradient() -> TreeplexVector {
+        TreeplexVector::from_vec(
+            &KUHN_TREEPLEX_PL1,
+            (0..13)
+                .map(|x| ((x * 7) % 5) as f64 - 2.0 + 0.1 * (x as f64))
+                .collect::<Vec<f64>>(),
+        )
+    }
+
+    fn check_smoothed_br<R: SimplexRegularizer>(dgf: &DilatedDgf<R>) {
+        let gradient = test_gradient();
+        let (value, strategy) = dgf.smoothed_br(gradient.clone());
+
+        // The optimal value should be attained by the maximizer...
+        assert_approx_eq!(value, gradient.inner(strategy.inner()) - dgf.value(&strategy));
+
+        // ...and should not be beaten by other strategies.
+        let uniform = SequenceFormStrategy::from_uniform_strategy(&KUHN_TREEPLEX_PL1);
+        assert!(value >= gradient.inner(uniform.inner()) - dgf.value(&uniform));
+        let (_, pure) = KUHN_TREEPLEX_PL1.sequence_form_br(gradient.clone());
+        assert!(value >= gradient.inner(pure.inner()) - dgf.value(&pure));
+    }
+
+    fn check_prox<R: SimplexRegularizer>(dgf: &DilatedDgf<R>) {
+        let gradient = test_gradient();
+        let center = dgf.prox(&gradient, &dgf.center(), 0.5);
+
+        // Zero step sizes do not move away from the center.
+        let unmoved = dgf.prox(&gradient, &center, 0.0);
+        assert_approx_eq!((unmoved.inner() - center.inner()).max_norm(), 0.0);
+        assert_approx_eq!(dgf.bregman_divergence(&center, &center), 0.0);
+
+        // Moving along the gradient increases the linear term.
+        let moved = dgf.prox(&gradient, &center, 1.0);
+        assert!(gradient.inner(moved.inner()) > gradient.inner(center.inner()));
+        assert!(dgf.bregman_divergence(&moved, &center) > 0.0);
+    }
+
+    #[test]
+    fn dilated_entropy() {
+        let dgf = DilatedEntropy::uniform(&KUHN_TREEPLEX_PL1, Entropy);
+
+        // The prox center plays uniformly at the bottom infosets, but favors sequences
+        // leading to further infosets, since these reduce the value of the DGF.
+        let center = dgf.center();
+        assert_approx_eq!(center.inner()[6], 2.0 / 3.0);
+        assert_approx_eq!(center.inner()[0], 1.0 / 3.0);
+        let uniform = SequenceFormStrategy::from_uniform_strategy(&KUHN_TREEPLEX_PL1);
+        assert_approx_eq!(dgf.value(&uniform), 6.0 * 0.5f64.ln() * 0.75);
+        assert!(dgf.value(&center) < dgf.value(&uniform));
+
+        check_smoothed_br(&dgf);
+        check_prox(&dgf);
+    }
+
+    #[test]
+    fn dilated_euclidean() {
+        let dgf = DilatedEuclidean::new(&KUHN_TREEPLEX_PL1, Euclidean, vec![2.0; 6]);
+        check_smoothed_br(&dgf);
+        check_prox(&dgf);
+
+        // Large gradients recover the best response.
+        let gradient = test_gradient() * 1000.0;
+        let (_, smoothed) = dgf.smoothed_br(gradient.clone());
+        let (_, pure) = KUHN_TREEPLEX_PL1.sequence_form_br(gradient);
+        assert_approx_eq!((smoothed.inner() - pure.inner()).max_norm(), 0.0);
+    }
+
+    #[test]
+    fn simplex_projection() {
+        let mut v = vec![0.5, 0.5, 0.5];
+        super::project_to_simplex(&mut v);
+        assert_approx_eq!(v[0], 1.0 / 3.0);
+
+        let mut v = vec![2.0, 0.0, -1.0];
+        super::project_to_simplex(&mut v);
+        assert_approx_eq!(v[0], 1.0);
+        assert_approx_eq!(v[1], 0.0);
+
+        let mut v = vec![0.9, 0.3, -0.5];
+        super::project_to_simplex(&mut v);
+        assert_approx_eq!(v[0], 0.8);
+        assert_approx_eq!(v[1], 0.2);
+        assert_approx_eq!(v[2], 0.0);
+    }
+}
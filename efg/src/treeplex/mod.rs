@@ -1,7 +1,13 @@
+mod dgf;
 mod treeplex;
 mod utility;
 
 pub use self::treeplex::SequenceId;
 pub use self::treeplex::Treeplex;
 
-pub use self::utility::TreeplexTools;
\ No newline at end of file
+pub use self::utility::TreeplexTools;
+
+pub use self::dgf::{
+    project_to_simplex, DilatedDgf, DilatedEntropy, DilatedEuclidean, Entropy, Euclidean,
+    SimplexRegularizer,
+};
\ No newline at end of file
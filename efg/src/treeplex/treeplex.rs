@@ -1,3 +1,4 @@
+use crate::game::GameValidationError;
 use crate::game::Infoset;
 use crate::game::Player;
 
@@ -99,12 +100,81 @@ impl Treeplex {
         self.player
     }
 
+    /// Checks that the treeplex obeys the numbering requirements, i.e., (a) each
+    /// nonempty sequence belongs to exactly one infoset, (b) parent sequences are numbered
+    /// after their children, and (c) infosets sharing the same parent sequence are
+    /// contiguous. Returns the first violation found.
+    pub fn validate(&self) -> Result<(), GameValidationError> {
+        let player = self.player;
+        let mut sequence_owner = vec![None; self.num_sequences];
+        let mut last_infoset_of_seq = vec![None; self.num_sequences];
+
+        for (infoset_id, infoset) in self.infosets.iter().enumerate() {
+            if infoset.start_sequence > infoset.end_sequence {
+                return Err(GameValidationError::EmptyInfoset { player, infoset_id });
+            }
+            // The empty sequence may not belong to any infoset.
+            if infoset.end_sequence + 1 >= self.num_sequences {
+                return Err(GameValidationError::InfosetSequenceOutOfRange {
+                    player,
+                    infoset_id,
+                    sequence_id: infoset.end_sequence,
+                });
+            }
+            if infoset.parent_sequence >= self.num_sequences {
+                return Err(GameValidationError::ParentSequenceOutOfRange {
+                    player,
+                    infoset_id,
+                    parent_sequence: infoset.parent_sequence,
+                });
+            }
+            if infoset.parent_sequence <= infoset.end_sequence {
+                return Err(GameValidationError::ParentSequenceOrder {
+                    player,
+                    infoset_id,
+                    parent_sequence: infoset.parent_sequence,
+                });
+            }
+
+            for sequence_id in infoset.start_sequence..=infoset.end_sequence {
+                if let Some(other_infoset_id) = sequence_owner[sequence_id] {
+                    return Err(GameValidationError::OverlappingSequences {
+                        player,
+                        sequence_id,
+                        infoset_ids: (other_infoset_id, infoset_id),
+                    });
+                }
+                sequence_owner[sequence_id] = Some(infoset_id);
+            }
+
+            // Infosets under the same parent sequence must immediately follow one another.
+            if let Some(last_infoset_id) = last_infoset_of_seq[infoset.parent_sequence] {
+                if last_infoset_id + 1 != infoset_id {
+                    return Err(GameValidationError::NonContiguousChildInfosets {
+                        player,
+                        parent_sequence: infoset.parent_sequence,
+                        infoset_id,
+                    });
+                }
+            }
+            last_infoset_of_seq[infoset.parent_sequence] = Some(infoset_id);
+        }
+
+        if let Some(sequence_id) = (0..self.num_sequences.saturating_sub(1))
+            .find(|&sequence_id| sequence_owner[sequence_id].is_none())
+        {
+            return Err(GameValidationError::UncoveredSequence {
+                player,
+                sequence_id,
+            });
+        }
+
+        Ok(())
+    }
+
     /// Computes inplace the best *behavioral* response given the *sequence form*
     /// payoff vector in `gradient`.
-    pub fn inplace_behavioral_br<'a>(
-        &self,
-        mut gradient: TreeplexVector<'a>,
-    ) -> (f64, BehavioralStrategy<'a>) {
+    pub fn inplace_behavioral_br(&self, mut gradient: TreeplexVector) -> (f64, BehavioralStrategy) {
         let best_response_value = self._inplace_behavioral_br(&mut gradient);
         let behavioral_strategy = BehavioralStrategy::from_treeplex_vector(gradient);
 
@@ -113,10 +183,10 @@ impl Treeplex {
 
     /// Computes inplace the best *sequence* response given the *sequence form*
     /// payoff vector in `gradient`.
-    pub fn inplace_sequence_form_br<'a>(
+    pub fn inplace_sequence_form_br(
         &self,
-        mut gradient: TreeplexVector<'a>,
-    ) -> (f64, SequenceFormStrategy<'a>) {
+        mut gradient: TreeplexVector,
+    ) -> (f64, SequenceFormStrategy) {
         // To compute the sequence form best response, we first compute the
         // behavioral form best response.
         let best_response_value = self._inplace_behavioral_br(&mut gradient);
@@ -133,7 +203,7 @@ impl Treeplex {
     /// Computes the best *behavioral form*  response given the *sequence form*
     /// payoff vector in `gradient`.
     /// TODO(chunkail): No need for cloning and runnign inplace_xxx_br?
-    pub fn behavioral_br<'a>(&self, gradient: TreeplexVector<'a>) -> (f64, BehavioralStrategy<'a>) {
+    pub fn behavioral_br(&self, gradient: TreeplexVector) -> (f64, BehavioralStrategy) {
         let br = gradient.clone();
         self.inplace_behavioral_br(br)
     }
@@ -141,10 +211,7 @@ impl Treeplex {
     /// Computes the best *sequence form*  response given the *sequence form*
     /// payoff vector in `gradient`.
     /// TODO(chunkail): No need for cloning and cloning inplace_xxx_br?
-    pub fn sequence_form_br<'a>(
-        &self,
-        gradient: TreeplexVector<'a>,
-    ) -> (f64, SequenceFormStrategy<'a>) {
+    pub fn sequence_form_br(&self, gradient: TreeplexVector) -> (f64, SequenceFormStrategy) {
         let br = gradient.clone();
         self.inplace_sequence_form_br(br)
     }
@@ -211,15 +278,17 @@ impl Treeplex {
 
 #[cfg(test)]
 pub mod test_fixtures {
+    use crate::game::GameValidationError;
     use crate::game::Infoset;
     use crate::game::Player;
     use crate::treeplex::Treeplex;
     use crate::vector::TreeplexVector;
     use assert_approx_eq::assert_approx_eq;
     use lazy_static::lazy_static;
+    use std::sync::Arc;
 
     lazy_static! {
-        pub static ref KUHN_TREEPLEX_PL1: Treeplex = Treeplex::new(
+        pub static ref KUHN_TREEPLEX_PL1: Arc<Treeplex> = Arc::new(Treeplex::new(
             Player::Player1,
             13,
             vec![
@@ -231,9 +300,9 @@ pub mod test_fixtures {
                 Infoset::new(12, 10, 11)
             ]
             .into_boxed_slice()
-        );
+        ));
 
-        pub static ref KUHN_TREEPLEX_PL2: Treeplex = Treeplex::new(
+        pub static ref KUHN_TREEPLEX_PL2: Arc<Treeplex> = Arc::new(Treeplex::new(
             Player::Player2,
             13,
             vec![
@@ -245,7 +314,7 @@ pub mod test_fixtures {
                 Infoset::new(12, 10, 11),
             ]
             .into_boxed_slice()
-        );
+        ));
     }
 
     #[test]
@@ -280,6 +349,59 @@ pub mod test_fixtures {
         );
         assert_approx_eq!(value_br, 17.0);
     }
+
+    #[test]
+    fn validate() {
+        assert_eq!(KUHN_TREEPLEX_PL1.validate(), Ok(()));
+        assert_eq!(KUHN_TREEPLEX_PL2.validate(), Ok(()));
+
+        let treeplex = Treeplex::new(
+            Player::Player1,
+            5,
+            vec![Infoset::new(1, 0, 1), Infoset::new(4, 2, 3)].into_boxed_slice(),
+        );
+        assert_eq!(
+            treeplex.validate(),
+            Err(GameValidationError::ParentSequenceOrder {
+                player: Player::Player1,
+                infoset_id: 0,
+                parent_sequence: 1,
+            })
+        );
+
+        let treeplex = Treeplex::new(
+            Player::Player1,
+            5,
+            vec![Infoset::new(4, 0, 1), Infoset::new(4, 1, 3)].into_boxed_slice(),
+        );
+        assert_eq!(
+            treeplex.validate(),
+            Err(GameValidationError::OverlappingSequences {
+                player: Player::Player1,
+                sequence_id: 1,
+                infoset_ids: (0, 1),
+            })
+        );
+
+        let treeplex = Treeplex::new(
+            Player::Player1,
+            7,
+            vec![
+                Infoset::new(6, 0, 1),
+                Infoset::new(5, 2, 3),
+                Infoset::new(6, 4, 5),
+            ]
+            .into_boxed_slice(),
+        );
+        assert_eq!(
+            treeplex.validate(),
+            Err(GameValidationError::NonContiguousChildInfosets {
+                player: Player::Player1,
+                parent_sequence: 6,
+                infoset_id: 2,
+            })
+        );
+    }
 }
 
 #[cfg(test)]
@@ -3,40 +3,42 @@ use crate::treeplex::{SequenceId, Treeplex};
 
 use capnp;
 use std::ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Sub, SubAssign};
+use std::sync::Arc;
 
 /// A vector associated with a treeplex, with length equal to the number of sequences
-/// in that treeplex.
+/// in that treeplex. The treeplex is shared, so vectors (and strategies built on them) may
+/// outlive the game they were created from and be sent across threads.
 #[derive(Debug, Clone)]
-pub struct TreeplexVector<'a> {
-    treeplex: &'a Treeplex,
+pub struct TreeplexVector {
+    treeplex: Arc<Treeplex>,
 
     pub entries: Vec<f64>,
 }
 
-impl<'a> TreeplexVector<'a> {
-    pub fn from_constant(treeplex: &'a Treeplex, c: f64) -> TreeplexVector<'a> {
+impl TreeplexVector {
+    pub fn from_constant(treeplex: &Arc<Treeplex>, c: f64) -> TreeplexVector {
         TreeplexVector {
-            treeplex,
+            treeplex: treeplex.clone(),
             entries: vec![c; treeplex.num_sequences()],
         }
     }
 
     /// Converts array into `TreeplexVector`. Note this clones content of the array.
     /// Should not consume `initial_values`.
-    pub fn from_array(treeplex: &'a Treeplex, initial_entries: &'a [f64]) -> TreeplexVector<'a> {
+    pub fn from_array(treeplex: &Arc<Treeplex>, initial_entries: &[f64]) -> TreeplexVector {
         assert_eq!(treeplex.num_sequences(), initial_entries.len());
         TreeplexVector {
-            treeplex,
+            treeplex: treeplex.clone(),
             entries: initial_entries.to_vec(),
         }
     }
 
     /// Converts array into `TreeplexVector`. Note this clones content of the array.
     /// *Will* consume `initial_values`.
-    pub fn from_vec(treeplex: &'a Treeplex, initial_entries: Vec<f64>) -> TreeplexVector<'a> {
+    pub fn from_vec(treeplex: &Arc<Treeplex>, initial_entries: Vec<f64>) -> TreeplexVector {
         assert_eq!(treeplex.num_sequences(), initial_entries.len());
         TreeplexVector {
-            treeplex,
+            treeplex: treeplex.clone(),
             entries: initial_entries,
         }
     }
@@ -49,8 +51,8 @@ impl<'a> TreeplexVector<'a> {
         self.entries[self.treeplex.empty_sequence_id()]
     }
 
-    pub fn treeplex(&self) -> &'a Treeplex {
-        self.treeplex
+    pub fn treeplex(&self) -> &Arc<Treeplex> {
+        &self.treeplex
     }
 
     pub fn num_sequences(&self) -> usize {
@@ -101,8 +103,8 @@ impl<'a> TreeplexVector<'a> {
 
     pub fn deserialize(
         vector_reader: &vector_capnp::vector::Reader,
-        treeplex: &'a Treeplex,
-    ) -> capnp::Result<TreeplexVector<'a>> {
+        treeplex: &Arc<Treeplex>,
+    ) -> capnp::Result<TreeplexVector> {
         let mut entries = vec![];
         for entry in vector_reader.get_entries()?.iter() {
             entries.push(entry);
@@ -124,7 +126,7 @@ impl<'a> TreeplexVector<'a> {
     }
 }
 
-impl<'a> Index<SequenceId> for TreeplexVector<'a> {
+impl Index<SequenceId> for TreeplexVector {
     type Output = f64;
 
     fn index(&self, index: SequenceId) -> &f64 {
@@ -132,30 +134,30 @@ impl<'a> Index<SequenceId> for TreeplexVector<'a> {
         &self.entries[index]
     }
 }
-impl<'a> IndexMut<SequenceId> for TreeplexVector<'a> {
+impl IndexMut<SequenceId> for TreeplexVector {
     fn index_mut(&mut self, index: SequenceId) -> &mut f64 {
         assert!(self.treeplex().has_sequence(index));
         &mut self.entries[index]
     }
 }
 
-impl<'a, 'b> SubAssign<&'b TreeplexVector<'b>> for TreeplexVector<'a> {
-    fn sub_assign(&mut self, other: &'b TreeplexVector<'b>) {
+impl<'b> SubAssign<&'b TreeplexVector> for TreeplexVector {
+    fn sub_assign(&mut self, other: &'b TreeplexVector) {
         for index in 0..self.num_sequences() {
             self[index] -= other[index];
         }
     }
 }
 
-impl<'a, 'b> AddAssign<&'b TreeplexVector<'b>> for TreeplexVector<'a> {
-    fn add_assign(&mut self, other: &'b TreeplexVector<'b>) {
+impl<'b> AddAssign<&'b TreeplexVector> for TreeplexVector {
+    fn add_assign(&mut self, other: &'b TreeplexVector) {
         for index in 0..self.num_sequences() {
             self[index] += other[index];
         }
     }
 }
 
-impl<'a, 'b> MulAssign<&'b TreeplexVector<'a>> for TreeplexVector<'a> {
+impl<'b> MulAssign<&'b TreeplexVector> for TreeplexVector {
     fn mul_assign(&mut self, other: &'b Self) {
         for index in 0..self.num_sequences() {
             self[index] *= other[index];
@@ -163,74 +165,74 @@ impl<'a, 'b> MulAssign<&'b TreeplexVector<'a>> for TreeplexVector<'a> {
     }
 }
 
-impl<'a, 'b> Sub<&'b TreeplexVector<'a>> for TreeplexVector<'a> {
-    type Output = TreeplexVector<'a>;
-    fn sub(self, other: &'b Self) -> TreeplexVector<'a> {
+impl<'b> Sub<&'b TreeplexVector> for TreeplexVector {
+    type Output = TreeplexVector;
+    fn sub(self, other: &'b Self) -> TreeplexVector {
         let mut obj = self;
         obj -= other;
         obj
     }
 }
-impl<'a, 'b> Add<&'b TreeplexVector<'a>> for TreeplexVector<'a> {
-    type Output = TreeplexVector<'a>;
-    fn add(self, other: &'b Self) -> TreeplexVector<'a> {
+impl<'b> Add<&'b TreeplexVector> for TreeplexVector {
+    type Output = TreeplexVector;
+    fn add(self, other: &'b Self) -> TreeplexVector {
         let mut obj = self;
         obj += other;
         obj
     }
 }
 
-impl<'a, 'b> Mul<&'b TreeplexVector<'a>> for TreeplexVector<'a> {
-    type Output = TreeplexVector<'a>;
-    fn mul(self, other: &'b Self) -> TreeplexVector<'a> {
+impl<'b> Mul<&'b TreeplexVector> for TreeplexVector {
+    type Output = TreeplexVector;
+    fn mul(self, other: &'b Self) -> TreeplexVector {
         let mut obj = self;
         obj *= other;
         obj
     }
 }
 
-impl<'a, 'b> Sub<&'b TreeplexVector<'a>> for &TreeplexVector<'a> {
-    type Output = TreeplexVector<'a>;
-    fn sub(self, other: &'b TreeplexVector<'a>) -> TreeplexVector<'a> {
+impl<'b> Sub<&'b TreeplexVector> for &TreeplexVector {
+    type Output = TreeplexVector;
+    fn sub(self, other: &'b TreeplexVector) -> TreeplexVector {
         let mut obj = self.clone();
         obj -= other;
         obj
     }
 }
 
-impl<'a, 'b> Add<&'b TreeplexVector<'a>> for &TreeplexVector<'a> {
-    type Output = TreeplexVector<'a>;
-    fn add(self, other: &'b TreeplexVector<'a>) -> TreeplexVector<'a> {
+impl<'b> Add<&'b TreeplexVector> for &TreeplexVector {
+    type Output = TreeplexVector;
+    fn add(self, other: &'b TreeplexVector) -> TreeplexVector {
         let mut obj = self.clone();
         obj += other;
         obj
     }
 }
 
-impl<'a, 'b> Mul<&'b TreeplexVector<'a>> for &TreeplexVector<'a> {
-    type Output = TreeplexVector<'a>;
-    fn mul(self, other: &'b TreeplexVector<'a>) -> TreeplexVector<'a> {
+impl<'b> Mul<&'b TreeplexVector> for &TreeplexVector {
+    type Output = TreeplexVector;
+    fn mul(self, other: &'b TreeplexVector) -> TreeplexVector {
         let mut obj = self.clone();
         obj *= other;
         obj
     }
 }
 
-impl<'a> SubAssign<f64> for TreeplexVector<'a> {
+impl SubAssign<f64> for TreeplexVector {
     fn sub_assign(&mut self, sub: f64) {
         for index in 0..self.num_sequences() {
             self[index] -= sub;
         }
     }
 }
-impl<'a> AddAssign<f64> for TreeplexVector<'a> {
+impl AddAssign<f64> for TreeplexVector {
     fn add_assign(&mut self, add: f64) {
         for index in 0..self.num_sequences() {
             self[index] += add;
         }
     }
 }
-impl<'a> MulAssign<f64> for TreeplexVector<'a> {
+impl MulAssign<f64> for TreeplexVector {
     fn mul_assign(&mut self, mul: f64) {
         for index in 0..self.num_sequences() {
             self[index] *= mul;
@@ -238,54 +240,54 @@ impl<'a> MulAssign<f64> for TreeplexVector<'a> {
     }
 }
 
-impl<'a> Sub<f64> for TreeplexVector<'a> {
-    type Output = TreeplexVector<'a>;
-    fn sub(self, sub: f64) -> TreeplexVector<'a> {
+impl Sub<f64> for TreeplexVector {
+    type Output = TreeplexVector;
+    fn sub(self, sub: f64) -> TreeplexVector {
         let mut obj = self;
         obj -= sub;
         obj
     }
 }
 
-impl<'a> Add<f64> for TreeplexVector<'a> {
-    type Output = TreeplexVector<'a>;
-    fn add(self, add: f64) -> TreeplexVector<'a> {
+impl Add<f64> for TreeplexVector {
+    type Output = TreeplexVector;
+    fn add(self, add: f64) -> TreeplexVector {
         let mut obj = self;
         obj += add;
         obj
     }
 }
 
-impl<'a> Mul<f64> for TreeplexVector<'a> {
-    type Output = TreeplexVector<'a>;
-    fn mul(self, mul: f64) -> TreeplexVector<'a> {
+impl Mul<f64> for TreeplexVector {
+    type Output = TreeplexVector;
+    fn mul(self, mul: f64) -> TreeplexVector {
         let mut obj = self;
         obj *= mul;
         obj
     }
 }
 
-impl<'a> Sub<f64> for &TreeplexVector<'a> {
-    type Output = TreeplexVector<'a>;
-    fn sub(self, sub: f64) -> TreeplexVector<'a> {
+impl Sub<f64> for &TreeplexVector {
+    type Output = TreeplexVector;
+    fn sub(self, sub: f64) -> TreeplexVector {
         let mut obj = self.clone();
         obj -= sub;
         obj
     }
 }
 
-impl<'a> Add<f64> for &TreeplexVector<'a> {
-    type Output = TreeplexVector<'a>;
-    fn add(self, add: f64) -> TreeplexVector<'a> {
+impl Add<f64> for &TreeplexVector {
+    type Output = TreeplexVector;
+    fn add(self, add: f64) -> TreeplexVector {
         let mut obj = self.clone();
         obj += add;
         obj
     }
 }
 
-impl<'a> Mul<f64> for &TreeplexVector<'a> {
-    type Output = TreeplexVector<'a>;
-    fn mul(self, mul: f64) -> TreeplexVector<'a> {
+impl Mul<f64> for &TreeplexVector {
+    type Output = TreeplexVector;
+    fn mul(self, mul: f64) -> TreeplexVector {
         let mut obj = self.clone();
         obj *= mul;
         obj
@@ -299,10 +301,11 @@ pub mod test_fixtures {
     use crate::treeplex::Treeplex;
     use crate::vector::TreeplexVector;
     use assert_approx_eq::assert_approx_eq;
+    use std::sync::Arc;
 
     use lazy_static::lazy_static;
     lazy_static! {
-        pub static ref CHAIN_TREEPLEX: Treeplex = Treeplex::new(
+        pub static ref CHAIN_TREEPLEX: Arc<Treeplex> = Arc::new(Treeplex::new(
             Player::Player1,
             4,
             vec![
@@ -311,7 +314,7 @@ pub mod test_fixtures {
                 Infoset::new(3, 2, 2),
             ]
             .into_boxed_slice()
-        );
+        ));
     }
 
 
@@ -0,0 +1,483 @@
+//! Bayesian Stackelberg games in normal form, e.g., security games with multiple attacker types.
+//! Chance draws the follower's type at the root, after which the leader and follower move
+//! simultaneously, with only the follower knowing its type. The normal-form game may be repeated
+//! over several rounds, with the actions of each round revealed to both players, in which case
+//! the last rounds may be solved as subgames.
+
+extern crate efg_lite;
+extern crate env_logger;
+extern crate libgt;
+extern crate structopt;
+
+use efg_lite::game::{ExtensiveFormGame, Player};
+use efg_lite::sse::FollowerTypes;
+use libgt::{ChanceOrPlayer, ExtensiveFormGameBuilder, GameTreeVertex, Leaf, VertexOrLeaf};
+use log::{error, info};
+use structopt::StructOpt;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::str::{FromStr, SplitWhitespace};
+
+/// Payoffs of each follower type, indexed by [type][leader action][follower action].
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub struct BayesianGame {
+    pub priors: Vec<f64>,
+    pub payoffs_pl1: Vec<Vec<Vec<f64>>>,
+    pub payoffs_pl2: Vec<Vec<Vec<f64>>>,
+}
+
+/// Reads a game given by whitespace separated numbers: the number of types, leader actions and
+/// follower actions, followed by the prior of each type, and then the leader's and the follower's
+/// payoff matrices of each type in turn, row by row (one row per leader action).
+impl FromStr for BayesianGame {
+    type Err = String;
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        let mut numbers = contents.split_whitespace();
+        let num_types = parse_next::<usize>(&mut numbers)?;
+        let num_actions_pl1 = parse_next::<usize>(&mut numbers)?;
+        let num_actions_pl2 = parse_next::<usize>(&mut numbers)?;
+        if num_types == 0 || num_actions_pl1 == 0 || num_actions_pl2 == 0 {
+            return Err("Numbers of types and actions must be positive".to_string());
+        }
+
+        let priors = (0..num_types)
+            .map(|_| parse_next::<f64>(&mut numbers))
+            .collect::<Result<Vec<f64>, String>>()?;
+        let mut payoffs_pl1 = vec![];
+        let mut payoffs_pl2 = vec![];
+        for _ in 0..num_types {
+            for payoffs in [&mut payoffs_pl1, &mut payoffs_pl2].iter_mut() {
+                payoffs.push(
+                    (0..num_actions_pl1)
+                        .map(|_| {
+                            (0..num_actions_pl2)
+                                .map(|_| parse_next::<f64>(&mut numbers))
+                                .collect::<Result<Vec<f64>, String>>()
+                        })
+                        .collect::<Result<Vec<Vec<f64>>, String>>()?,
+                );
+            }
+        }
+        if numbers.next().is_some() {
+            return Err("Unexpected trailing numbers in game file".to_string());
+        }
+
+        Ok(BayesianGame {
+            priors,
+            payoffs_pl1,
+            payoffs_pl2,
+        })
+    }
+}
+
+/// Parses the next whitespace separated number of a game file.
+fn parse_next<T>(numbers: &mut SplitWhitespace) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: ToString,
+{
+    numbers
+        .next()
+        .ok_or_else(|| "Unexpected end of game file".to_string())?
+        .parse::<T>()
+        .map_err(|err| err.to_string())
+}
+
+impl BayesianGame {
+    /// Game with uniformly random priors and payoffs in [0, 1].
+    pub fn random(
+        num_types: usize,
+        num_actions_pl1: usize,
+        num_actions_pl2: usize,
+        random_seed: usize,
+    ) -> BayesianGame {
+        let mut rng: StdRng = SeedableRng::seed_from_u64(random_seed as u64);
+        let priors = (0..num_types)
+            .map(|_| rng.gen::<f64>())
+            .collect::<Vec<f64>>();
+        let normalizer: f64 = priors.iter().sum();
+        let priors = priors.iter().map(|x| x / normalizer).collect::<Vec<f64>>();
+
+        let mut random_payoffs = || {
+            (0..num_types)
+                .map(|_| {
+                    (0..num_actions_pl1)
+                        .map(|_| (0..num_actions_pl2).map(|_| rng.gen::<f64>()).collect())
+                        .collect()
+                })
+                .collect::<Vec<Vec<Vec<f64>>>>()
+        };
+        let payoffs_pl1 = random_payoffs();
+        let payoffs_pl2 = random_payoffs();
+
+        BayesianGame {
+            priors,
+            payoffs_pl1,
+            payoffs_pl2,
+        }
+    }
+
+    pub fn num_types(&self) -> usize {
+        self.priors.len()
+    }
+
+    pub fn num_actions(&self, player: Player) -> usize {
+        match player {
+            Player::Player1 => self.payoffs_pl1[0].len(),
+            Player::Player2 => self.payoffs_pl1[0][0].len(),
+        }
+    }
+}
+
+/// How often the normal-form game is played, and how many of the last rounds are solved as
+/// subgames, one for each history of actions played before them.
+#[derive(Debug, Clone, Copy)]
+pub struct Config {
+    pub num_rounds: usize,
+    pub subgame_rounds: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct State {
+    game: Rc<BayesianGame>,
+    config: Config,
+    follower_type: Option<usize>,
+    // Actions of both players in the rounds played so far.
+    history: Vec<(usize, usize)>,
+    action_pl1: Option<usize>,
+}
+
+/// The follower's infoset is identified by its type and the actions played so far, while the
+/// leader only observes the latter.
+#[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq)]
+pub enum Info {
+    Leader(Vec<(usize, usize)>),
+    Follower(usize, Vec<(usize, usize)>),
+}
+
+/// Actions played before the rounds solved as subgames.
+#[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq)]
+pub struct Subgame {
+    history: Vec<(usize, usize)>,
+}
+
+impl State {
+    pub fn initial_state(game: Rc<BayesianGame>, config: Config) -> State {
+        assert!(
+            config.num_rounds > 0,
+            "The game must be played at least once"
+        );
+        assert!(
+            config.subgame_rounds < config.num_rounds,
+            "The first round cannot be solved as a subgame"
+        );
+        State {
+            game,
+            config,
+            follower_type: None,
+            history: vec![],
+            action_pl1: None,
+        }
+    }
+}
+
+impl GameTreeVertex for State {
+    type Action = usize;
+    type PlayerInfo = Info;
+    type Subgame = Subgame;
+
+    fn next_player(&self) -> ChanceOrPlayer {
+        match (self.follower_type, self.action_pl1) {
+            (None, _) => ChanceOrPlayer::Chance,
+            (Some(_), None) => ChanceOrPlayer::Player(Player::Player1),
+            (Some(_), Some(_)) => ChanceOrPlayer::Player(Player::Player2),
+        }
+    }
+
+    fn player_information(&self) -> Self::PlayerInfo {
+        match self.next_player() {
+            ChanceOrPlayer::Player(Player::Player2) => {
+                Info::Follower(self.follower_type.unwrap(), self.history.clone())
+            }
+            _ => Info::Leader(self.history.clone()),
+        }
+    }
+
+    fn available_actions(&self) -> Box<[(Self::Action, f64)]> {
+        match self.next_player() {
+            ChanceOrPlayer::Chance => self.game.priors.iter().cloned().enumerate().collect(),
+            ChanceOrPlayer::Player(player) => (0..self.game.num_actions(player))
+                .map(|action| (action, 1.0f64))
+                .collect(),
+        }
+    }
+
+    fn next_state(&self, action: &Self::Action) -> VertexOrLeaf<Self> {
+        match self.next_player() {
+            ChanceOrPlayer::Chance => VertexOrLeaf::Vertex(State {
+                follower_type: Some(*action),
+                ..self.clone()
+            }),
+            ChanceOrPlayer::Player(Player::Player1) => VertexOrLeaf::Vertex(State {
+                action_pl1: Some(*action),
+                ..self.clone()
+            }),
+            ChanceOrPlayer::Player(Player::Player2) => {
+                let mut history = self.history.clone();
+                history.push((self.action_pl1.unwrap(), *action));
+                if history.len() < self.config.num_rounds {
+                    return VertexOrLeaf::Vertex(State {
+                        history,
+                        action_pl1: None,
+                        ..self.clone()
+                    });
+                }
+
+                // Payoffs are summed over all rounds.
+                let follower_type = self.follower_type.unwrap();
+                let (payoff_pl1, payoff_pl2) = history.iter().fold(
+                    (0f64, 0f64),
+                    |(payoff_pl1, payoff_pl2), (action_pl1, action_pl2)| {
+                        (
+                            payoff_pl1
+                                + self.game.payoffs_pl1[follower_type][*action_pl1][*action_pl2],
+                            payoff_pl2
+                                + self.game.payoffs_pl2[follower_type][*action_pl1][*action_pl2],
+                        )
+                    },
+                );
+                VertexOrLeaf::Leaf(Leaf {
+                    payoff_pl1,
+                    payoff_pl2,
+                })
+            }
+        }
+    }
+
+    fn subgame(&self) -> Option<Self::Subgame> {
+        let trunk_rounds = self.config.num_rounds - self.config.subgame_rounds;
+        match self.follower_type {
+            Some(_) if self.history.len() >= trunk_rounds => Some(Subgame {
+                history: self.history[..trunk_rounds].to_vec(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Converts the game into an extensive-form game, along with the type of each follower infoset.
+pub fn make_game(
+    bayesian_game: &BayesianGame,
+    config: Config,
+) -> (ExtensiveFormGame, FollowerTypes) {
+    let mut builder = ExtensiveFormGameBuilder::<State>::new();
+    let initial_state = State::initial_state(Rc::new(bayesian_game.clone()), config);
+    let (efg, annotations) = builder.make_game_and_annotations(&initial_state, true);
+    let annotations = annotations.unwrap();
+
+    let infoset_types = annotations
+        .treeplex_annotations_pl2
+        .infoset_annotations
+        .iter()
+        .map(|info| match info {
+            Some(Info::Follower(follower_type, _)) => *follower_type,
+            _ => panic!("Follower infoset without a type"),
+        })
+        .collect::<Vec<usize>>();
+    let follower_types = FollowerTypes::new(
+        efg.treeplex(Player::Player2),
+        bayesian_game.priors.clone(),
+        infoset_types,
+    );
+    (efg, follower_types)
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "bayesian")]
+struct Opt {
+    #[structopt(short = "o", long = "output_file")]
+    output_file: PathBuf,
+
+    // Follower type of each follower infoset, to be passed on to the solver.
+    #[structopt(long = "types_file")]
+    types_file: PathBuf,
+
+    // Normal-form Bayesian game to convert, see `BayesianGame`'s `FromStr` for the format.
+    // If not given, a random game is generated instead.
+    #[structopt(short = "i", long = "input_file")]
+    input_file: Option<PathBuf>,
+
+    // Number of times the game is played, with the actions of each round revealed.
+    #[structopt(long = "num_rounds", default_value = "1")]
+    num_rounds: usize,
+
+    // Number of last rounds solved as subgames.
+    #[structopt(long = "subgame_rounds", default_value = "0")]
+    subgame_rounds: usize,
+
+    #[structopt(long = "num_types", default_value = "2")]
+    num_types: usize,
+
+    #[structopt(long = "num_actions_leader", default_value = "3")]
+    num_actions_pl1: usize,
+
+    #[structopt(long = "num_actions_follower", default_value = "3")]
+    num_actions_pl2: usize,
+
+    #[structopt(long = "seed", default_value = "0")]
+    seed: usize,
+}
+
+fn main() {
+    env_logger::init();
+
+    let opt = Opt::from_args();
+
+    let bayesian_game = match &opt.input_file {
+        Some(input_file) => {
+            let mut contents = String::new();
+            File::open(input_file)
+                .unwrap()
+                .read_to_string(&mut contents)
+                .unwrap();
+            match contents.parse::<BayesianGame>() {
+                Ok(bayesian_game) => bayesian_game,
+                Err(err) => {
+                    error!("Invalid game file {:?}: {}", input_file, err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => BayesianGame::random(
+            opt.num_types,
+            opt.num_actions_pl1,
+            opt.num_actions_pl2,
+            opt.seed,
+        ),
+    };
+    info!("Priors {:?}", bayesian_game.priors);
+
+    let config = Config {
+        num_rounds: opt.num_rounds,
+        subgame_rounds: opt.subgame_rounds,
+    };
+    let (efg, follower_types) = make_game(&bayesian_game, config);
+
+    let mut file_writer = File::create(&opt.output_file).unwrap();
+    efg.persist(&mut file_writer).unwrap();
+
+    let mut file_writer = File::create(&opt.types_file).unwrap();
+    follower_types.persist(&mut file_writer).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{make_game, BayesianGame, Config};
+    use efg_lite::game::{ExtensiveFormGame, Player};
+    use efg_lite::schema::game_capnp;
+    use efg_lite::sse::{BlueprintBr, FollowerTypes};
+    use efg_lite::strategy::SequenceFormStrategy;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    // Type 0 plays the commitment game, where the leader gets 3.5 if it commits to Up with
+    // probability 0.5, while type 1 prefers Right against the same commitment.
+    const GAME: &str = "2 2 2
+        0.25 0.75
+        2 4 1 3
+        1 0 0 1
+        1 0 3 0
+        0 2 1 0";
+
+    /// Persists the game and its follower types, and reads them back.
+    fn round_trip(
+        game: &ExtensiveFormGame,
+        follower_types: &FollowerTypes,
+    ) -> (ExtensiveFormGame, FollowerTypes) {
+        let mut buffer = Vec::<u8>::new();
+        game.persist(&mut buffer).unwrap();
+        let message_reader =
+            capnp::serialize::read_message(&mut &buffer[..], capnp::message::ReaderOptions::new())
+                .unwrap();
+        let game_reader = message_reader
+            .get_root::<game_capnp::game::Reader>()
+            .unwrap();
+        let game = ExtensiveFormGame::deserialize(&game_reader).unwrap();
+
+        let mut buffer = Vec::<u8>::new();
+        follower_types.persist(&mut buffer).unwrap();
+        let follower_types =
+            FollowerTypes::load(game.treeplex(Player::Player2), &mut &buffer[..]).unwrap();
+        (game, follower_types)
+    }
+
+    #[test]
+    fn generated_game_round_trip() {
+        let bayesian_game = GAME.parse::<BayesianGame>().unwrap();
+        assert_eq!(bayesian_game.priors, vec![0.25, 0.75]);
+        assert_eq!(
+            bayesian_game.payoffs_pl2[1],
+            vec![vec![0.0, 2.0], vec![1.0, 0.0]]
+        );
+
+        let config = Config {
+            num_rounds: 1,
+            subgame_rounds: 0,
+        };
+        let (game, follower_types) = make_game(&bayesian_game, config);
+        let (game, follower_types) = round_trip(&game, &follower_types);
+        assert_eq!(game.treeplex(Player::Player1).num_infosets(), 1);
+        assert_eq!(game.treeplex(Player::Player2).num_infosets(), 2);
+        assert_eq!(game.num_subgames(), 0);
+        assert_approx_eq!(follower_types.prior(1), 0.75);
+
+        let blueprint = SequenceFormStrategy::from_uniform_strategy(game.treeplex(Player::Player1));
+        let blueprint_br = BlueprintBr::new(&game, &blueprint);
+        let leader_values = follower_types.conditional_values(|x| {
+            blueprint_br.leader_seq_value(blueprint_br.follower_behavioral_index(x))
+        });
+        assert_approx_eq!(leader_values[0], 3.5);
+        assert_approx_eq!(leader_values[1], 0.0);
+
+        // Repeated twice, the second round is solved in a subgame for each of the four action
+        // profiles of the first round. The leader has an infoset in each, and each follower
+        // type has an infoset in each.
+        let config = Config {
+            num_rounds: 2,
+            subgame_rounds: 1,
+        };
+        let (game, follower_types) = make_game(&bayesian_game, config);
+        let (game, follower_types) = round_trip(&game, &follower_types);
+        assert_eq!(game.num_subgames(), 4);
+        assert_eq!(game.top_level_subgames().len(), 4);
+        assert_eq!(game.treeplex(Player::Player1).num_infosets(), 5);
+        assert_eq!(game.treeplex(Player::Player2).num_infosets(), 10);
+        for follower_type in 0..2 {
+            assert_eq!(follower_types.infosets_of_type(follower_type).len(), 5);
+        }
+        for subgame_id in 0..4 {
+            assert_eq!(game.subgame_infosets(Player::Player1, subgame_id).len(), 1);
+            assert_eq!(game.subgame_infosets(Player::Player2, subgame_id).len(), 2);
+        }
+    }
+
+    #[test]
+    fn invalid_game_files() {
+        for contents in [
+            "2 2 2 0.25 0.75 2 4 1",
+            "0 2 2",
+            "2 2 x",
+            &format!("{} 1", GAME),
+        ]
+        .iter()
+        {
+            assert!(contents.parse::<BayesianGame>().is_err());
+        }
+    }
+}
@@ -1,3 +1,4 @@
+#[cfg(feature = "gurobi")]
 extern crate bindgen;
 use std::env;
 use std::fs::File;
@@ -5,6 +6,7 @@ use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 
+#[cfg(feature = "gurobi")]
 fn make_gurobi_bindings() {
     if let Ok(gurobi_lib_path) = env::var("GUROBI_LIB") {
         println!("cargo:rustc-link-search=all={}", gurobi_lib_path);
@@ -33,5 +35,6 @@ fn make_gurobi_bindings() {
 
 fn main() {
     // make_cbc_bindings();
+    #[cfg(feature = "gurobi")]
     make_gurobi_bindings();
 }
\ No newline at end of file
@@ -15,7 +15,9 @@ use std::path::PathBuf;
 use structopt::StructOpt;
 
 mod solve_games;
-use solve_games::{MatGameSolution, GurobiSolver};
+#[cfg(feature = "gurobi")]
+use solve_games::GurobiSolver;
+use solve_games::{MatGameSolution, SimplexSolver};
 
 use libgt::GameAnnotations;
 
@@ -486,21 +488,41 @@ struct Opt {
 
     #[structopt(short = "r", long = "random_seed")]
     random_seed: usize,
+
+    // Solve the main game using the embedded LP solver instead of Gurobi.
+    #[structopt(long = "embedded_lp")]
+    embedded_lp: bool,
+}
+
+#[cfg(feature = "gurobi")]
+fn solve_main_game(synth: &Synth, embedded_lp: bool) -> MatGameSolution {
+    match embedded_lp {
+        true => SimplexSolver::new(&synth.main_game_payoffs_pl1,
+                                   &synth.main_game_payoffs_pl2).solve(),
+        false => GurobiSolver::new(&synth.main_game_payoffs_pl1,
+                                   &synth.main_game_payoffs_pl2).solve(),
+    }
+}
+
+// Without Gurobi, the embedded LP solver is the only option.
+#[cfg(not(feature = "gurobi"))]
+fn solve_main_game(synth: &Synth, _embedded_lp: bool) -> MatGameSolution {
+    SimplexSolver::new(&synth.main_game_payoffs_pl1,
+                       &synth.main_game_payoffs_pl2).solve()
 }
 
 
 
-fn blueprint<'a>(synth: &Synth,
-                 efg: &'a ExtensiveFormGame, 
-                 annotations: &GameAnnotations<State>,
-                 ) -> SequenceFormStrategy<'a> {
+fn blueprint(synth: &Synth,
+             efg: &ExtensiveFormGame, 
+             annotations: &GameAnnotations<State>,
+             embedded_lp: bool,
+             ) -> SequenceFormStrategy {
 
     // println!("{:?}", annotations);
     
     // Solve normal form representation of the main game.
-    let solver = GurobiSolver::new(&synth.main_game_payoffs_pl1,
-                               &synth.main_game_payoffs_pl2);
-    let sol = solver.solve();
+    let sol = solve_main_game(synth, embedded_lp);
 
     let mut beh = BehavioralStrategy::from_uniform_strategy(efg.treeplex(Player::Player1));
     let mut embedded = beh.inner().clone();
@@ -584,7 +606,7 @@ fn main() {
     println!("num infosets {:?}", efg.treeplex(Player::Player1).num_infosets());
 
     println!("Compute blueprints for main game and embed it into the extensive form game");
-    let blueprint = blueprint(&synth, &efg, &annotations);
+    let blueprint = blueprint(&synth, &efg, &annotations, opt.embedded_lp);
 
     let mut game_file_writer = File::create(&opt.output_game_file).unwrap();       
     efg.persist(&mut game_file_writer).unwrap();
@@ -11,11 +11,15 @@
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(dead_code)]
+#[cfg(feature = "gurobi")]
 include!(concat!(env!("OUT_DIR"), "/gurobi_bindings.rs"));
 
+#[cfg(feature = "gurobi")]
 use std::ffi::{CString, CStr};
 use noisy_float::prelude::*;
 
+use embedded_solvers::{ConstraintSense, LpStatus, Model, ObjectiveSense, VariableType};
+
 #[derive(Debug, Clone, PartialOrd, PartialEq)]
 pub struct MatGameSolution {
     pub leader_payoff: f64,
@@ -25,11 +29,13 @@ pub struct MatGameSolution {
     pub follower_br: usize,
 }
 
+#[cfg(feature = "gurobi")]
 pub struct GurobiSolver<'a> {
     payoffs_pl1: &'a Vec::<Vec<f64>>,
     payoffs_pl2: &'a Vec::<Vec<f64>>,
 }
 
+#[cfg(feature = "gurobi")]
 pub struct SingleLPSolver<'a> {
     env: *mut GRBenv,
     model: *mut GRBmodel,
@@ -40,16 +46,119 @@ pub struct SingleLPSolver<'a> {
     payoffs_pl2: &'a Vec::<Vec<f64>>,
 }
 
+/// Multiple LP method using the embedded simplex solver, which does not require Gurobi.
+/// Follower actions which cannot be induced by any leader strategy give infeasible LPs,
+/// and are skipped.
+pub struct SimplexSolver<'a> {
+    payoffs_pl1: &'a Vec::<Vec<f64>>,
+    payoffs_pl2: &'a Vec::<Vec<f64>>,
+}
+
+fn payoff_size_check(payoffs_pl1: &Vec::<Vec<f64>>, payoffs_pl2: &Vec::<Vec<f64>>) {
+    assert!(payoffs_pl1.len() > 0);
+    assert!(payoffs_pl2.len() > 0);
+    assert_eq!(payoffs_pl1.len(), payoffs_pl2.len());
+    assert_eq!(payoffs_pl1[0].len(), payoffs_pl2[0].len());
+
+    // Ensure payoffs are indeed a retangular matrix.
+    assert_eq!(payoffs_pl1.iter().all(|x| x.len() == payoffs_pl1[0].len()), true);
+    assert_eq!(payoffs_pl2.iter().all(|x| x.len() == payoffs_pl2[0].len()), true);
+}
+
+/// LP maximizing the leader's payoff subject to `best_follower_pure_strategy` being a
+/// best response for the follower. Variables are the probabilities of each leader action.
+fn single_lp_model(payoffs_pl1: &Vec::<Vec<f64>>,
+                   payoffs_pl2: &Vec::<Vec<f64>>,
+                   best_follower_pure_strategy: usize) -> Model {
+    let num_follower_actions = payoffs_pl1.last().unwrap().len();
+    let num_leader_actions = payoffs_pl1.len();
+
+    let mut model = Model::new(&format!("Follower BR: {}", best_follower_pure_strategy),
+                               ObjectiveSense::Maximize);
+
+    for leader_strategy in 0..num_leader_actions {
+        model.add_variable(&format!("leader strategy {}", leader_strategy),
+                           VariableType::Continuous,
+                           payoffs_pl1[leader_strategy][best_follower_pure_strategy], // Leader payoffs for their action.
+                           0f64,
+                           1f64);
+    }
+
+    // Best follower strategy has to be `best_follower_pure_strategy`
+    for follower_strategy in 0..num_follower_actions {
+        if follower_strategy == best_follower_pure_strategy {
+            continue;
+        }
+        let coeffs = (0..num_leader_actions)
+            .map(|leader_action_id| {
+                (leader_action_id,
+                 payoffs_pl2[leader_action_id][best_follower_pure_strategy] -
+                 payoffs_pl2[leader_action_id][follower_strategy])
+            })
+            .collect::<Vec<(usize, f64)>>();
+        model.add_constraint(&format!("BR constr, best follower strategy: {:?}, follower strategy {:?}", best_follower_pure_strategy, follower_strategy),
+                             coeffs,
+                             ConstraintSense::GreaterEqual,
+                             0f64);
+    }
+
+    let sum_to_one = (0..num_leader_actions).map(|x| (x, 1f64)).collect::<Vec<(usize, f64)>>();
+    model.add_constraint("Sum-to-one constraint", sum_to_one, ConstraintSense::Equal, 1f64);
+
+    model
+}
+
+impl<'a> SimplexSolver<'a> {
+    pub fn new(payoffs_pl1: &'a Vec::<Vec<f64>>,
+           payoffs_pl2: &'a Vec::<Vec<f64>>) -> SimplexSolver<'a> {
+        payoff_size_check(payoffs_pl1, payoffs_pl2);
+        SimplexSolver {
+            payoffs_pl1,
+            payoffs_pl2,
+        }
+    }
+
+    /// Solves `single_lp_model`, or returns None if `best_follower_pure_strategy` cannot
+    /// be induced.
+    fn solve_single_lp(&self, best_follower_pure_strategy: usize) -> Option<MatGameSolution> {
+        let lp_solution = single_lp_model(self.payoffs_pl1,
+                                          self.payoffs_pl2,
+                                          best_follower_pure_strategy).solve();
+        if lp_solution.status != LpStatus::Optimal {
+            return None;
+        }
+
+        let (leader_payoff, follower_payoff) =
+            get_player_payoffs(self.payoffs_pl1,
+                               self.payoffs_pl2,
+                               best_follower_pure_strategy,
+                               &lp_solution.values);
+
+        Some(MatGameSolution {
+            leader_payoff,
+            follower_payoff,
+
+            leader_strategy: lp_solution.values,
+            follower_br: best_follower_pure_strategy,
+        })
+    }
+
+    pub fn solve(&self) -> MatGameSolution {
+        let num_follower_actions = self.payoffs_pl1.last().unwrap().len();
+
+        let solutions = (0..num_follower_actions)
+            .filter_map(|best_follower_pure_strategy| self.solve_single_lp(best_follower_pure_strategy))
+            .collect::<Vec<MatGameSolution>>();
+
+        // At least one follower action is a best response to any leader strategy.
+        (*solutions.iter().max_by_key(|x| r64(x.leader_payoff)).unwrap()).clone()
+    }
+}
+
+#[cfg(feature = "gurobi")]
 impl<'a> GurobiSolver<'a> {
     fn payoff_size_check(&self) {
-        assert!(self.payoffs_pl1.len() > 0);
-        assert!(self.payoffs_pl2.len() > 0);
-        assert_eq!(self.payoffs_pl1.len(), self.payoffs_pl2.len());
-        assert_eq!(self.payoffs_pl1[0].len(), self.payoffs_pl2[0].len());
-        
-        // Ensure payoffs are indeed a retangular matrix.
-        assert_eq!(self.payoffs_pl1.iter().all(|x| x.len() == self.payoffs_pl1[0].len()), true);
-        assert_eq!(self.payoffs_pl2.iter().all(|x| x.len() == self.payoffs_pl2[0].len()), true);
+        payoff_size_check(self.payoffs_pl1, self.payoffs_pl2);
     }
 
     pub fn new(payoffs_pl1: &'a Vec::<Vec<f64>>, 
@@ -123,6 +232,7 @@ fn get_player_payoffs(
     (accum_payoff_pl1, accum_payoff_pl2)
 }
 
+#[cfg(feature = "gurobi")]
 impl<'a> SingleLPSolver<'a> {
 
     pub fn new(
@@ -130,6 +240,8 @@ impl<'a> SingleLPSolver<'a> {
             payoffs_pl2: &'a std::vec::Vec<std::vec::Vec<f64>>, 
             best_follower_pure_strategy: usize) -> SingleLPSolver<'a> {
         
+        let model = single_lp_model(payoffs_pl1, payoffs_pl2, best_follower_pure_strategy);
+
         unsafe {
             let mut envP: *mut GRBenv = std::ptr::null_mut();
             GRBloadenv(&mut envP, CString::new("zero-sum-log").unwrap().as_ptr());
@@ -138,7 +250,7 @@ impl<'a> SingleLPSolver<'a> {
             GRBnewmodel(
                 envP,
                 &mut modelP,
-                CString::new(format!("Follower BR: {}", best_follower_pure_strategy)).unwrap().as_ptr(),
+                CString::new(model.name()).unwrap().as_ptr(),
                 0,
                 std::ptr::null_mut(),
                 std::ptr::null_mut(),
@@ -147,17 +259,8 @@ impl<'a> SingleLPSolver<'a> {
                 std::ptr::null_mut(),
             );
 
-            GRBsetintattr(
-                modelP,
-                CString::new("ModelSense").unwrap().as_ptr(),
-                -1, // Maximization problem.
-            );
-
-            println!("Making variables");
-            Self::make_variables(modelP, payoffs_pl1, payoffs_pl2, best_follower_pure_strategy);
-            
-            println!("Making constraints");
-            Self::make_constraints(modelP, payoffs_pl1, payoffs_pl2, best_follower_pure_strategy);
+            println!("Loading model");
+            Self::load_model(modelP, &model);
 
             SingleLPSolver {
                 env: envP,
@@ -170,70 +273,51 @@ impl<'a> SingleLPSolver<'a> {
         
     }
 
-    fn make_variables(model: *mut GRBmodel, 
-                    payoffs_pl1: &Vec::<Vec::<f64>>,
-                    payoffs_pl2: &Vec::<Vec::<f64>>,
-                    best_follower_pure_strategy: usize) {
-        let num_follower_actions = payoffs_pl1.last().unwrap().len();
-        let num_leader_actions = payoffs_pl1.len();
+    fn load_model(grb_model: *mut GRBmodel, model: &Model) {
+        let mut status : i32 = 0;
 
-        let mut status:i32 = 0;
-        
-        for leader_strategy in 0..num_leader_actions {
+        for variable in model.variables().iter() {
+            let vtype = match variable.variable_type {
+                VariableType::Continuous => 'C',
+                VariableType::Binary => 'B',
+                VariableType::Integer => 'I',
+            };
             unsafe {
                 status = GRBaddvar(
-                    model,
+                    grb_model,
                     0,                    // Will add constraints later on.
                     std::ptr::null_mut(), // Will add constraints later on.
                     std::ptr::null_mut(), // Will add constraints later on.
-                    payoffs_pl1[leader_strategy][best_follower_pure_strategy], // Leader payoffs for their action.
-                    0f64,                 // Sequence probability must be >= 0
-                    1f64, // Sequence probability may not be more than 1 (not really required in theory).
-                    'C' as i8, // GRB_CONTINUOUS,
-                    CString::new(format!("leader strategy {}", leader_strategy))
+                    variable.objective_coeff,
+                    variable.lower_bound.max(-GRB_INFINITY),
+                    variable.upper_bound.min(GRB_INFINITY),
+                    vtype as i8,
+                    CString::new(variable.name.as_str())
                         .unwrap()
                         .as_ptr(),
                 );
             }
             assert_eq!(status, 0);
         }
-    }
-
-    fn make_constraints(model: *mut GRBmodel, 
-                    payoffs_pl1: &Vec::<Vec::<f64>>,
-                    payoffs_pl2: &Vec::<Vec::<f64>>,
-                    best_follower_pure_strategy: usize) {
-        let num_follower_actions = payoffs_pl1.last().unwrap().len();
-        let num_leader_actions = payoffs_pl1.len();
-
-        let mut status : i32 = 0;
-        
-        // Best follower strategy has to be `best_follower_pure_strategy`
-        for follower_strategy in 0..num_follower_actions {
-            if follower_strategy == best_follower_pure_strategy {
-                continue;
-            }
-
 
+        for constraint in model.constraints().iter() {
             // Note that `col_indices` refers to variable index, not column in the game matrix
-            let mut col_indices = Vec::<i32>::new();         
-            let mut col_coeffs = Vec::<f64>::new();
-            for leader_action_id in 0..num_leader_actions {
-                let col_coeff = payoffs_pl2[leader_action_id][best_follower_pure_strategy] - 
-                                payoffs_pl2[leader_action_id][follower_strategy];
-                col_coeffs.push(col_coeff);
-                col_indices.push(leader_action_id as i32);
-            }
-
-            unsafe{
+            let mut col_indices = constraint.coeffs.iter().map(|x| x.0 as i32).collect::<Vec<i32>>();
+            let mut col_coeffs = constraint.coeffs.iter().map(|x| x.1).collect::<Vec<f64>>();
+            let sense = match constraint.sense {
+                ConstraintSense::LessEqual => GRB_LESS_EQUAL,
+                ConstraintSense::GreaterEqual => GRB_GREATER_EQUAL,
+                ConstraintSense::Equal => GRB_EQUAL,
+            };
+            unsafe {
                 status = GRBaddconstr(
-                    model,
+                    grb_model,
                     col_indices.len() as i32,
                     col_indices.as_mut_ptr(),
                     col_coeffs.as_mut_ptr(),
-                    GRB_GREATER_EQUAL as i8,
-                    0f64,
-                    CString::new(format!("BR constr, best follower strategy: {:?}, follower strategy {:?}", best_follower_pure_strategy, follower_strategy))
+                    sense as i8,
+                    constraint.rhs,
+                    CString::new(constraint.name.as_str())
                         .unwrap()
                         .as_ptr(),
                 );
@@ -241,26 +325,16 @@ impl<'a> SingleLPSolver<'a> {
             }
         }
 
-        // Add sum-to-one constraints. TODO: make idiomatic.
-        let mut col_indices = Vec::<i32>::new();
-        let mut col_coeffs = Vec::<f64>::new();
-        for leader_action_id in 0..num_leader_actions {
-            col_coeffs.push(1f64);
-            col_indices.push(leader_action_id as i32);
-        }
+        let model_sense = match model.objective_sense() {
+            ObjectiveSense::Minimize => 1,
+            ObjectiveSense::Maximize => -1,
+        };
         unsafe {
-            status = GRBaddconstr(
-                model,
-                col_indices.len() as i32,
-                col_indices.as_mut_ptr(),
-                col_coeffs.as_mut_ptr(),
-                GRB_EQUAL as i8,
-                1f64,
-                CString::new(format!("Sum-to-one constraint"))
-                    .unwrap()
-                    .as_ptr(),
+            GRBsetintattr(
+                grb_model,
+                CString::new("ModelSense").unwrap().as_ptr(),
+                model_sense,
             );
-            assert_eq!(status , 0);
         }
     }
 
@@ -32,7 +32,9 @@ where
 
     /// Return subgame that the vertex belongs to, and None if the vertex is not
     /// in a subgame. Returns `None` by default, meaning that the calling vertex is
-    /// not in any subgame.
+    /// not in any subgame. For nested subgames, this should be the innermost subgame.
+    /// The parent of a subgame is taken to be the subgame that its first vertex
+    /// was reached from.
     fn subgame(&self) -> Option<Self::Subgame> {
         None
     }
@@ -13,7 +13,7 @@ use crate::treeplex::{LeafInfo, SequenceOrEmpty, TreeplexInformation};
 use assert_approx_eq::assert_approx_eq;
 use itertools::sorted;
 use std::collections::BTreeMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 /// Builder for an extensive form game. The primary purpose of this
 /// class is to traverse a game tree, store relevant information regarding treeplexes
@@ -29,6 +29,10 @@ pub struct ExtensiveFormGameBuilder<T: GameTreeVertex> {
 
     // Mapping from subgames to subgame indices.
     subgames: BTreeMap<T::Subgame, usize>,
+
+    // Parent of each subgame (indexed by subgame index), or `Free` for top-level subgames.
+    // The parent of a subgame is the subgame from which it was first entered.
+    subgame_parents: Vec<SubgameOrFree>,
 }
 
 impl<'a, T> ExtensiveFormGameBuilder<T>
@@ -42,6 +46,7 @@ where
             treeplex_info_pl2: TreeplexInformation::new(),
             leaves_information: Vec::<_>::new(),
             subgames: BTreeMap::<_, _>::new(),
+            subgame_parents: Vec::<_>::new(),
         };
 
         // Initialize by adding in empty sequence to each treeplex_info object.
@@ -160,12 +165,12 @@ where
     ) -> ExtensiveFormGame {
         let num_sequences_pl1 = sequence_mapper_pl1.len();
         let num_sequences_pl2 = sequence_mapper_pl2.len();
-        let treeplex_pl1 = Rc::<Treeplex>::new(Treeplex::new(
+        let treeplex_pl1 = Arc::<Treeplex>::new(Treeplex::new(
             Player::Player1,
             num_sequences_pl1,
             infoset_list_pl1.into_boxed_slice(),
         ));
-        let treeplex_pl2 = Rc::<Treeplex>::new(Treeplex::new(
+        let treeplex_pl2 = Arc::<Treeplex>::new(Treeplex::new(
             Player::Player2,
             num_sequences_pl2,
             infoset_list_pl2.into_boxed_slice(),
@@ -194,6 +199,7 @@ where
             payoff_matrix,
             subgame_list_pl1.clone(),
             subgame_list_pl2.clone(),
+            self.subgame_parents.clone(),
         )
     }
 
@@ -294,15 +300,16 @@ where
             // another vertex or a leaf state.
             match next_vertex_or_leaf {
                 VertexOrLeaf::Vertex(vertex) => {
-                    let new_subgame_description = vertex.subgame();
-                    Self::check_subgame_consistency(
+                    let new_subgame = Self::add_subgame_if_needed_and_get(
                         &mut self.subgames,
-                        &new_subgame_description,
+                        &mut self.subgame_parents,
+                        vertex.subgame(),
                         &aux_state.prev_subgame,
                     );
-                    let new_subgame = Self::add_subgame_if_needed_and_get(
-                        &mut self.subgames,
-                        new_subgame_description,
+                    Self::check_subgame_consistency(
+                        &self.subgame_parents,
+                        &new_subgame,
+                        &aux_state.prev_subgame,
                     );
                     let new_aux_state: AuxState =
                         aux_state.new_with_updated_chance(prob, new_subgame);
@@ -372,15 +379,16 @@ where
             // Handle accordingly depending on whether the next vertex is another vertex or terminal.
             match next_vertex_or_leaf {
                 VertexOrLeaf::Vertex(vertex) => {
-                    let new_subgame_description = vertex.subgame();
-                    Self::check_subgame_consistency(
+                    let new_subgame = Self::add_subgame_if_needed_and_get(
                         &mut self.subgames,
-                        &new_subgame_description,
+                        &mut self.subgame_parents,
+                        vertex.subgame(),
                         &aux_state.prev_subgame,
                     );
-                    let new_subgame = Self::add_subgame_if_needed_and_get(
-                        &mut self.subgames,
-                        new_subgame_description,
+                    Self::check_subgame_consistency(
+                        &self.subgame_parents,
+                        &new_subgame,
+                        &aux_state.prev_subgame,
                     );
                     let new_aux_state: AuxState =
                         aux_state.new_with_updated_sequence(player, new_sequence, new_subgame);
@@ -397,41 +405,170 @@ where
     }
 
     /// Test if subgame is consistent with parent---if new subgame is free, then
-    /// parent must be none too. If both subgames are not free, then they must be
-    /// equal.
+    /// parent must be none too. If both subgames are not free, then either they are
+    /// equal, or the new subgame is nested directly within the previous one.
+    /// Subgames entered from outside of any subgame must be top-level.
     fn check_subgame_consistency(
-        subgames: &mut BTreeMap<T::Subgame, usize>,
-        subgame_description: &Option<T::Subgame>,
+        subgame_parents: &Vec<SubgameOrFree>,
+        new_subgame: &SubgameOrFree,
         prev_subgame: &SubgameOrFree,
     ) {
-        match (subgame_description, prev_subgame) {
-            (None, SubgameOrFree::Free) => {} // Both ancestor and descendent do not belong to any subgame---possible.
-            (None, SubgameOrFree::Subgame(_)) => {
+        match (new_subgame, prev_subgame) {
+            (SubgameOrFree::Free, SubgameOrFree::Free) => {} // Both ancestor and descendent do not belong to any subgame---possible.
+            (SubgameOrFree::Free, SubgameOrFree::Subgame(_)) => {
                 // Descendent does not belong to subgame, but ancestor does---impossible.
                 panic!("Parent was in a subgame but children was not");
             }
-            // Descendent is in some subgame, but ancestor was not---possible.
-            (Some(_), SubgameOrFree::Free) => {}
-            // Descendent and ancestor belong to some subgame each---possible, but have
-            // to check if they belong to the same subgame. To do that, we make sure
-            // that (1) new_subgame_desc belongs in the mapping of subgames and
-            // (2) what it maps to is equal to what the vertex's ancestor is mapped to.
-            (Some(new_subgame_desc), SubgameOrFree::Subgame(idx)) => {
-                assert_eq!(subgames.get(&new_subgame_desc).unwrap(), idx);
+            // Descendent is in some subgame, but ancestor was not---possible, so long as
+            // the descendent's subgame is not nested in some other subgame.
+            (SubgameOrFree::Subgame(new_idx), SubgameOrFree::Free) => {
+                assert_eq!(
+                    subgame_parents[*new_idx],
+                    SubgameOrFree::Free,
+                    "Nested subgame {:?} was entered from outside of its parent",
+                    new_idx
+                );
+            }
+            // Descendent and ancestor belong to some subgame each---possible, but the
+            // descendent either has to remain in the same subgame, or enter a subgame
+            // nested directly within the ancestor's.
+            (SubgameOrFree::Subgame(new_idx), SubgameOrFree::Subgame(idx)) => {
+                assert!(
+                    new_idx == idx || subgame_parents[*new_idx] == *prev_subgame,
+                    "Subgame {:?} was entered from subgame {:?}, which is not its parent {:?}",
+                    new_idx,
+                    idx,
+                    subgame_parents[*new_idx]
+                );
             }
         }
     }
 
+    /// Adds in the subgame if it has not already been added, recording the subgame
+    /// it was entered from as its parent.
     fn add_subgame_if_needed_and_get(
         subgames: &mut BTreeMap<T::Subgame, usize>,
+        subgame_parents: &mut Vec<SubgameOrFree>,
         subgame_description: Option<T::Subgame>,
+        prev_subgame: &SubgameOrFree,
     ) -> SubgameOrFree {
-        // Add in subgame if it has not already been added.
-        if subgame_description.is_some() {
-            let l = subgames.len();
-            SubgameOrFree::Subgame(*subgames.entry(subgame_description.unwrap()).or_insert(l))
-        } else {
-            SubgameOrFree::Free
+        match subgame_description {
+            Some(subgame_description) => {
+                let l = subgames.len();
+                let idx = *subgames.entry(subgame_description).or_insert(l);
+                if idx == subgame_parents.len() {
+                    subgame_parents.push(*prev_subgame);
+                }
+                SubgameOrFree::Subgame(idx)
+            }
+            None => SubgameOrFree::Free,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExtensiveFormGameBuilder;
+    use crate::efg_lite::game::{Player, SubgameOrFree};
+    use crate::game_tree::{ChanceOrPlayer, GameTreeVertex, Leaf, VertexOrLeaf};
+
+    /// The follower chooses between Out and In, which enters subgame A. There, the leader
+    /// chooses between Up and Down, which enters subgame B nested within A, where the follower
+    /// chooses between Left and Right. If `skip` is set, the follower may also enter B directly
+    /// from the root.
+    #[derive(Debug, Clone)]
+    struct NestedVertex {
+        node: &'static str,
+        skip: bool,
+    }
+
+    impl NestedVertex {
+        fn next(&self, node: &'static str) -> VertexOrLeaf<NestedVertex> {
+            VertexOrLeaf::Vertex(NestedVertex {
+                node,
+                skip: self.skip,
+            })
         }
     }
-}
\ No newline at end of file
+
+    fn leaf(payoff_pl1: f64, payoff_pl2: f64) -> VertexOrLeaf<NestedVertex> {
+        VertexOrLeaf::Leaf(Leaf {
+            payoff_pl1,
+            payoff_pl2,
+        })
+    }
+
+    impl GameTreeVertex for NestedVertex {
+        type PlayerInfo = &'static str;
+        type Action = &'static str;
+        type Subgame = char;
+
+        fn next_player(&self) -> ChanceOrPlayer {
+            match self.node {
+                "outer" => ChanceOrPlayer::Player(Player::Player1),
+                _ => ChanceOrPlayer::Player(Player::Player2),
+            }
+        }
+
+        fn player_information(&self) -> Self::PlayerInfo {
+            self.node
+        }
+
+        fn available_actions(&self) -> Box<[(Self::Action, f64)]> {
+            let actions = match self.node {
+                "root" if self.skip => vec!["in", "out", "skip"],
+                "root" => vec!["in", "out"],
+                "outer" => vec!["down", "up"],
+                _ => vec!["left", "right"],
+            };
+            actions.into_iter().map(|action| (action, 1.0)).collect()
+        }
+
+        fn next_state(&self, action: &Self::Action) -> VertexOrLeaf<NestedVertex> {
+            match (self.node, *action) {
+                ("root", "in") => self.next("outer"),
+                ("root", "out") => leaf(0.0, 0.25),
+                ("root", "skip") => self.next("skipped_inner"),
+                ("outer", "up") => leaf(2.0, 1.0),
+                ("outer", "down") => self.next("inner"),
+                (_, "left") => leaf(1.0, 0.0),
+                _ => leaf(3.0, 1.0),
+            }
+        }
+
+        fn subgame(&self) -> Option<Self::Subgame> {
+            match self.node {
+                "root" => None,
+                "outer" => Some('A'),
+                _ => Some('B'),
+            }
+        }
+    }
+
+    #[test]
+    fn nested_subgame_parents() {
+        let root = NestedVertex {
+            node: "root",
+            skip: false,
+        };
+        let (game, _) = ExtensiveFormGameBuilder::new().make_game_and_annotations(&root, false);
+        assert_eq!(game.num_subgames(), 2);
+        assert_eq!(game.subgame_parent(0), SubgameOrFree::Free);
+        assert_eq!(game.subgame_parent(1), SubgameOrFree::Subgame(0));
+        assert_eq!(game.top_level_subgames(), vec![0]);
+        assert_eq!(game.subgame_children(0), &vec![1]);
+        assert_eq!(game.subgame_depth(1), 1);
+        assert!(game.validate().is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "entered from")]
+    fn inconsistent_nesting() {
+        // B is entered both from the root and from within A.
+        let root = NestedVertex {
+            node: "root",
+            skip: true,
+        };
+        ExtensiveFormGameBuilder::new().make_game_and_annotations(&root, false);
+    }
+}
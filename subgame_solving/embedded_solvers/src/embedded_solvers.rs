@@ -0,0 +1,9 @@
+mod model;
+mod simplex;
+mod writers;
+
+pub use self::model::{
+    Constraint, ConstraintSense, IndicatorConstraint, LpSolution, LpStatus, Model, ObjectiveSense,
+    Sos1Constraint, Variable, VariableType, INFINITY,
+};
+pub use self::simplex::SimplexOptions;
@@ -0,0 +1,524 @@
+use crate::simplex::{SimplexOptions, SimplexTableau};
+
+/// Used for unbounded variables, in the same way as `GRB_INFINITY`.
+pub const INFINITY: f64 = f64::INFINITY;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ObjectiveSense {
+    Minimize,
+    Maximize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ConstraintSense {
+    LessEqual,
+    GreaterEqual,
+    Equal,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum VariableType {
+    Continuous,
+    Binary,
+    Integer,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LpStatus {
+    Optimal,
+    Infeasible,
+    Unbounded,
+    IterationLimit,
+    TimeLimit,
+}
+
+#[derive(Debug, Clone)]
+pub struct Variable {
+    pub name: String,
+    pub variable_type: VariableType,
+    pub objective_coeff: f64,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    pub name: String,
+    pub coeffs: Vec<(usize, f64)>,
+    pub sense: ConstraintSense,
+    pub rhs: f64,
+}
+
+/// The constraint sum_j coeff_j x_j (sense) rhs, which is only enforced when the binary
+/// variable `binary_variable` takes the value `binary_value`.
+#[derive(Debug, Clone)]
+pub struct IndicatorConstraint {
+    pub name: String,
+    pub binary_variable: usize,
+    pub binary_value: bool,
+    pub coeffs: Vec<(usize, f64)>,
+    pub sense: ConstraintSense,
+    pub rhs: f64,
+}
+
+/// Special ordered set of type 1: at most one of the variables may be nonzero. Variables
+/// are given as pairs of (variable index, weight), where weights order the variables
+/// for branching.
+#[derive(Debug, Clone)]
+pub struct Sos1Constraint {
+    pub name: String,
+    pub variables: Vec<(usize, f64)>,
+}
+
+/// Solution of the LP (relaxation) of a `Model`. Values and the objective are only
+/// meaningful if the status is `LpStatus::Optimal`.
+#[derive(Debug, Clone)]
+pub struct LpSolution {
+    pub status: LpStatus,
+    pub objective_value: f64,
+    pub values: Vec<f64>,
+}
+
+/// A mixed integer linear program,
+///     min/max c'x
+///     a_i'x (<=, >=, =) b_i for each constraint i,
+///     l <= x <= u,
+///     x_j integral for integer and binary variables j,
+/// built up incrementally in the same way as a Gurobi or CBC model. Bounds may be
+/// infinite. Variables and constraints are indexed in the order they were added.
+/// Models may also contain indicator and SOS1 constraints, which are not linear and are
+/// handled natively by solvers which support them.
+///
+/// Models are solver-agnostic: they may be solved (or their relaxations solved) by the
+/// embedded simplex method, or loaded into native solvers by the adapters in `optimizers`.
+#[derive(Debug, Clone)]
+pub struct Model {
+    name: String,
+    objective_sense: ObjectiveSense,
+    variables: Vec<Variable>,
+    constraints: Vec<Constraint>,
+    indicator_constraints: Vec<IndicatorConstraint>,
+    sos1_constraints: Vec<Sos1Constraint>,
+}
+
+impl Model {
+    pub fn new(name: &str, objective_sense: ObjectiveSense) -> Model {
+        Model {
+            name: name.to_string(),
+            objective_sense,
+            variables: Vec::<Variable>::new(),
+            constraints: Vec::<Constraint>::new(),
+            indicator_constraints: Vec::<IndicatorConstraint>::new(),
+            sos1_constraints: Vec::<Sos1Constraint>::new(),
+        }
+    }
+
+    /// Adds a variable and returns its index. Binary variables are restricted to [0, 1].
+    pub fn add_variable(
+        &mut self,
+        name: &str,
+        variable_type: VariableType,
+        objective_coeff: f64,
+        lower_bound: f64,
+        upper_bound: f64,
+    ) -> usize {
+        assert!(lower_bound <= upper_bound, "Lower bound exceeds upper bound.");
+        assert!(lower_bound < INFINITY && upper_bound > -INFINITY);
+        let (lower_bound, upper_bound) = match variable_type {
+            VariableType::Binary => (lower_bound.max(0.0), upper_bound.min(1.0)),
+            _ => (lower_bound, upper_bound),
+        };
+        self.variables.push(Variable {
+            name: name.to_string(),
+            variable_type,
+            objective_coeff,
+            lower_bound,
+            upper_bound,
+        });
+        self.variables.len() - 1
+    }
+
+    /// Adds the constraint sum_j coeff_j x_j (sense) rhs, where `coeffs` contains pairs
+    /// of (variable index, coefficient). Returns the index of the constraint.
+    pub fn add_constraint(
+        &mut self,
+        name: &str,
+        coeffs: Vec<(usize, f64)>,
+        sense: ConstraintSense,
+        rhs: f64,
+    ) -> usize {
+        assert!(
+            coeffs.iter().all(|(index, _)| *index < self.variables.len()),
+            "Constraint refers to a variable which does not exist."
+        );
+        self.constraints.push(Constraint {
+            name: name.to_string(),
+            coeffs,
+            sense,
+            rhs,
+        });
+        self.constraints.len() - 1
+    }
+
+    /// Adds the constraint sum_j coeff_j x_j (sense) rhs, which only has to hold if the
+    /// binary variable `binary_variable` equals `binary_value`. Returns the index of the
+    /// indicator constraint.
+    pub fn add_indicator_constraint(
+        &mut self,
+        name: &str,
+        binary_variable: usize,
+        binary_value: bool,
+        coeffs: Vec<(usize, f64)>,
+        sense: ConstraintSense,
+        rhs: f64,
+    ) -> usize {
+        assert_eq!(
+            self.variables[binary_variable].variable_type,
+            VariableType::Binary,
+            "Indicator constraints must be triggered by a binary variable."
+        );
+        assert!(
+            coeffs.iter().all(|(index, _)| *index < self.variables.len()),
+            "Constraint refers to a variable which does not exist."
+        );
+        self.indicator_constraints.push(IndicatorConstraint {
+            name: name.to_string(),
+            binary_variable,
+            binary_value,
+            coeffs,
+            sense,
+            rhs,
+        });
+        self.indicator_constraints.len() - 1
+    }
+
+    /// Adds an SOS1 constraint over pairs of (variable index, weight). Weights must be
+    /// distinct. Returns the index of the SOS1 constraint.
+    pub fn add_sos1_constraint(&mut self, name: &str, variables: Vec<(usize, f64)>) -> usize {
+        assert!(
+            variables.iter().all(|(index, _)| *index < self.variables.len()),
+            "Constraint refers to a variable which does not exist."
+        );
+        self.sos1_constraints.push(Sos1Constraint {
+            name: name.to_string(),
+            variables,
+        });
+        self.sos1_constraints.len() - 1
+    }
+
+    pub fn set_objective_sense(&mut self, objective_sense: ObjectiveSense) {
+        self.objective_sense = objective_sense;
+    }
+
+    pub fn set_objective_coeff(&mut self, variable: usize, objective_coeff: f64) {
+        self.variables[variable].objective_coeff = objective_coeff;
+    }
+
+    pub fn set_bounds(&mut self, variable: usize, lower_bound: f64, upper_bound: f64) {
+        assert!(lower_bound <= upper_bound, "Lower bound exceeds upper bound.");
+        self.variables[variable].lower_bound = lower_bound;
+        self.variables[variable].upper_bound = upper_bound;
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn objective_sense(&self) -> ObjectiveSense {
+        self.objective_sense
+    }
+
+    pub fn variables(&self) -> &Vec<Variable> {
+        &self.variables
+    }
+
+    pub fn constraints(&self) -> &Vec<Constraint> {
+        &self.constraints
+    }
+
+    pub fn indicator_constraints(&self) -> &Vec<IndicatorConstraint> {
+        &self.indicator_constraints
+    }
+
+    pub fn sos1_constraints(&self) -> &Vec<Sos1Constraint> {
+        &self.sos1_constraints
+    }
+
+    pub fn num_variables(&self) -> usize {
+        self.variables.len()
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    /// True if there are no integer or binary variables, nor indicator or SOS1 constraints.
+    pub fn is_linear(&self) -> bool {
+        self.variables
+            .iter()
+            .all(|x| x.variable_type == VariableType::Continuous)
+            && !self.has_logical_constraints()
+    }
+
+    /// True if there are indicator or SOS1 constraints.
+    pub fn has_logical_constraints(&self) -> bool {
+        !self.indicator_constraints.is_empty() || !self.sos1_constraints.is_empty()
+    }
+
+    /// Objective value of `values`, which need not be feasible.
+    pub fn objective_value(&self, values: &[f64]) -> f64 {
+        self.variables
+            .iter()
+            .zip(values.iter())
+            .map(|(variable, value)| variable.objective_coeff * value)
+            .sum()
+    }
+
+    /// Solves an LP with the default `SimplexOptions`. Panics if there are integer variables.
+    pub fn solve(&self) -> LpSolution {
+        self.solve_with_options(&SimplexOptions::default())
+    }
+
+    pub fn solve_with_options(&self, options: &SimplexOptions) -> LpSolution {
+        assert!(self.is_linear(), "Model has integer variables, solve the relaxation instead.");
+        self.solve_relaxation_with_options(options)
+    }
+
+    /// Solves the LP relaxation, i.e., ignoring integrality of variables. Indicator and
+    /// SOS1 constraints are relaxed as described in `linear_relaxation`.
+    pub fn solve_relaxation(&self) -> LpSolution {
+        self.solve_relaxation_with_options(&SimplexOptions::default())
+    }
+
+    pub fn solve_relaxation_with_options(&self, options: &SimplexOptions) -> LpSolution {
+        match self.has_logical_constraints() {
+            true => SimplexTableau::new(&self.linear_relaxation()).solve(options),
+            false => SimplexTableau::new(self).solve(options),
+        }
+    }
+
+    /// A copy of the model with indicator and SOS1 constraints replaced by the linear
+    /// constraints implied by the current bounds. An indicator constraint is added as
+    /// a linear constraint if its binary variable is fixed to `binary_value`, and is
+    /// dropped otherwise. If a variable in an SOS1 constraint is bounded away from zero,
+    /// all other variables in it are constrained to be zero. Hence, once all binary
+    /// variables are fixed, the relaxation is exact.
+    pub fn linear_relaxation(&self) -> Model {
+        let mut model = Model {
+            name: self.name.clone(),
+            objective_sense: self.objective_sense,
+            variables: self.variables.clone(),
+            constraints: self.constraints.clone(),
+            indicator_constraints: vec![],
+            sos1_constraints: vec![],
+        };
+
+        for constraint in self.indicator_constraints.iter() {
+            let variable = &self.variables[constraint.binary_variable];
+            let value = if constraint.binary_value { 1.0 } else { 0.0 };
+            if variable.lower_bound == value && variable.upper_bound == value {
+                model.add_constraint(
+                    &constraint.name,
+                    constraint.coeffs.clone(),
+                    constraint.sense,
+                    constraint.rhs,
+                );
+            }
+        }
+
+        for constraint in self.sos1_constraints.iter() {
+            let is_nonzero = |index: usize| {
+                self.variables[index].lower_bound > 0.0 || self.variables[index].upper_bound < 0.0
+            };
+            if let Some((nonzero, _)) = constraint.variables.iter().find(|(x, _)| is_nonzero(*x)) {
+                for (index, _) in constraint.variables.iter().filter(|(x, _)| x != nonzero) {
+                    model.add_constraint(
+                        &constraint.name,
+                        vec![(*index, 1.0)],
+                        ConstraintSense::Equal,
+                        0.0,
+                    );
+                }
+            }
+        }
+
+        model
+    }
+}
+
+#[cfg(test)]
+pub mod test_fixtures {
+    use super::{ConstraintSense, LpStatus, Model, ObjectiveSense, VariableType, INFINITY};
+    use crate::simplex::SimplexOptions;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn textbook_maximization() {
+        // max 3x + 5y s.t. x <= 4, 2y <= 12, 3x + 2y <= 18, x, y >= 0.
+        let mut lp = Model::new("test", ObjectiveSense::Maximize);
+        let x = lp.add_variable("x", VariableType::Continuous, 3.0, 0.0, INFINITY);
+        let y = lp.add_variable("y", VariableType::Continuous, 5.0, 0.0, INFINITY);
+        lp.add_constraint("c", vec![(x, 1.0)], ConstraintSense::LessEqual, 4.0);
+        lp.add_constraint("c", vec![(y, 2.0)], ConstraintSense::LessEqual, 12.0);
+        lp.add_constraint("c", vec![(x, 3.0), (y, 2.0)], ConstraintSense::LessEqual, 18.0);
+
+        let solution = lp.solve();
+        assert_eq!(solution.status, LpStatus::Optimal);
+        assert_approx_eq!(solution.objective_value, 36.0);
+        assert_approx_eq!(solution.values[x], 2.0);
+        assert_approx_eq!(solution.values[y], 6.0);
+    }
+
+    #[test]
+    fn free_variables_and_bounds() {
+        // min z s.t. z >= x - 1, z >= 1 - x, 2 <= x <= 5, with z free.
+        let mut lp = Model::new("test", ObjectiveSense::Minimize);
+        let x = lp.add_variable("x", VariableType::Continuous, 0.0, 2.0, 5.0);
+        let z = lp.add_variable("z", VariableType::Continuous, 1.0, -INFINITY, INFINITY);
+        lp.add_constraint("c0", vec![(z, 1.0), (x, -1.0)], ConstraintSense::GreaterEqual, -1.0);
+        lp.add_constraint("c1", vec![(z, 1.0), (x, 1.0)], ConstraintSense::GreaterEqual, 1.0);
+
+        let solution = lp.solve();
+        assert_eq!(solution.status, LpStatus::Optimal);
+        assert_approx_eq!(solution.objective_value, 1.0);
+        assert_approx_eq!(solution.values[x], 2.0);
+
+        // Variables bounded only from above.
+        let mut lp = Model::new("test", ObjectiveSense::Maximize);
+        let x = lp.add_variable("x", VariableType::Continuous, 1.0, -INFINITY, 3.0);
+        let y = lp.add_variable("y", VariableType::Continuous, -1.0, -INFINITY, INFINITY);
+        lp.add_constraint("c", vec![(x, 1.0), (y, -1.0)], ConstraintSense::Equal, 1.0);
+        let solution = lp.solve();
+        assert_eq!(solution.status, LpStatus::Optimal);
+        assert_approx_eq!(solution.objective_value, 1.0);
+        assert_approx_eq!(solution.values[y], solution.values[x] - 1.0);
+    }
+
+    #[test]
+    fn homogeneous_greater_equal_rows() {
+        // max x s.t. z >= x, y >= z, 0 >= y - 0.5, with z free and 0 <= x, y <= 1.
+        let mut lp = Model::new("test", ObjectiveSense::Maximize);
+        let x = lp.add_variable("x", VariableType::Continuous, 1.0, 0.0, 1.0);
+        let y = lp.add_variable("y", VariableType::Continuous, 0.0, 0.0, 1.0);
+        let z = lp.add_variable("z", VariableType::Continuous, 0.0, -INFINITY, INFINITY);
+        lp.add_constraint("c0", vec![(z, 1.0), (x, -1.0)], ConstraintSense::GreaterEqual, 0.0);
+        lp.add_constraint("c1", vec![(y, 1.0), (z, -1.0)], ConstraintSense::GreaterEqual, 0.0);
+        lp.add_constraint("c2", vec![(y, -1.0)], ConstraintSense::GreaterEqual, -0.5);
+
+        let solution = lp.solve();
+        assert_eq!(solution.status, LpStatus::Optimal);
+        assert_approx_eq!(solution.objective_value, 0.5);
+        assert!(solution.values[z] >= solution.values[x] - 1e-9);
+        assert!(solution.values[y] >= solution.values[z] - 1e-9);
+    }
+
+    #[test]
+    fn infeasible_and_unbounded() {
+        let mut lp = Model::new("test", ObjectiveSense::Minimize);
+        let x = lp.add_variable("x", VariableType::Continuous, 1.0, 0.0, INFINITY);
+        let y = lp.add_variable("y", VariableType::Continuous, 1.0, 0.0, INFINITY);
+        lp.add_constraint("c", vec![(x, 1.0), (y, 1.0)], ConstraintSense::LessEqual, 1.0);
+        lp.add_constraint("c", vec![(x, 1.0), (y, 1.0)], ConstraintSense::GreaterEqual, 2.0);
+        assert_eq!(lp.solve().status, LpStatus::Infeasible);
+
+        let mut lp = Model::new("test", ObjectiveSense::Maximize);
+        let x = lp.add_variable("x", VariableType::Continuous, 1.0, 0.0, INFINITY);
+        let y = lp.add_variable("y", VariableType::Continuous, 0.0, 0.0, INFINITY);
+        lp.add_constraint("c", vec![(x, 1.0), (y, -1.0)], ConstraintSense::LessEqual, 1.0);
+        assert_eq!(lp.solve().status, LpStatus::Unbounded);
+    }
+
+    #[test]
+    fn time_limit() {
+        let mut lp = Model::new("test", ObjectiveSense::Maximize);
+        let x = lp.add_variable("x", VariableType::Continuous, 1.0, 0.0, INFINITY);
+        lp.add_constraint("c", vec![(x, 1.0)], ConstraintSense::LessEqual, 1.0);
+        let options = SimplexOptions {
+            time_limit: 0.0,
+            ..SimplexOptions::default()
+        };
+        assert_eq!(lp.solve_with_options(&options).status, LpStatus::TimeLimit);
+        assert_eq!(lp.solve().status, LpStatus::Optimal);
+    }
+
+    #[test]
+    fn degenerate_and_redundant() {
+        // Beale's example, which cycles under the textbook pivoting rule.
+        let mut lp = Model::new("test", ObjectiveSense::Minimize);
+        let x = (0..4)
+            .map(|i| {
+                lp.add_variable(&format!("x{}", i), VariableType::Continuous, 0.0, 0.0, INFINITY)
+            })
+            .collect::<Vec<usize>>();
+        lp.set_objective_coeff(x[0], -0.75);
+        lp.set_objective_coeff(x[1], 150.0);
+        lp.set_objective_coeff(x[2], -0.02);
+        lp.set_objective_coeff(x[3], 6.0);
+        lp.add_constraint(
+            "c",
+            vec![(x[0], 0.25), (x[1], -60.0), (x[2], -0.04), (x[3], 9.0)],
+            ConstraintSense::LessEqual,
+            0.0,
+        );
+        lp.add_constraint(
+            "c",
+            vec![(x[0], 0.5), (x[1], -90.0), (x[2], -0.02), (x[3], 3.0)],
+            ConstraintSense::LessEqual,
+            0.0,
+        );
+        lp.add_constraint("c", vec![(x[2], 1.0)], ConstraintSense::LessEqual, 1.0);
+        let solution = lp.solve();
+        assert_eq!(solution.status, LpStatus::Optimal);
+        assert_approx_eq!(solution.objective_value, -0.05);
+
+        // Duplicated equality constraints.
+        let mut lp = Model::new("test", ObjectiveSense::Maximize);
+        let x = lp.add_variable("x", VariableType::Continuous, 1.0, 0.0, INFINITY);
+        let y = lp.add_variable("y", VariableType::Continuous, 2.0, 0.0, INFINITY);
+        lp.add_constraint("c", vec![(x, 1.0), (y, 1.0)], ConstraintSense::Equal, 1.0);
+        lp.add_constraint("c", vec![(x, 2.0), (y, 2.0)], ConstraintSense::Equal, 2.0);
+        let solution = lp.solve();
+        assert_eq!(solution.status, LpStatus::Optimal);
+        assert_approx_eq!(solution.objective_value, 2.0);
+        assert_approx_eq!(solution.values[y], 1.0);
+    }
+
+    #[test]
+    fn binary_relaxation() {
+        let mut model = Model::new("test", ObjectiveSense::Maximize);
+        let x = model.add_variable("x", VariableType::Binary, 1.0, -INFINITY, INFINITY);
+        model.add_constraint("c", vec![(x, 2.0)], ConstraintSense::LessEqual, 1.0);
+        assert!(!model.is_linear());
+        assert_eq!(model.variables()[x].lower_bound, 0.0);
+        assert_eq!(model.variables()[x].upper_bound, 1.0);
+
+        let solution = model.solve_relaxation();
+        assert_eq!(solution.status, LpStatus::Optimal);
+        assert_approx_eq!(solution.values[x], 0.5);
+    }
+
+    #[test]
+    fn logical_constraint_relaxation() {
+        // max y s.t. z = 1 -> y <= 1, SOS1 {w, z}, y <= 2, w <= 3.
+        let mut model = Model::new("test", ObjectiveSense::Maximize);
+        let y = model.add_variable("y", VariableType::Continuous, 1.0, 0.0, 2.0);
+        let z = model.add_variable("z", VariableType::Binary, 0.0, 0.0, 1.0);
+        let w = model.add_variable("w", VariableType::Continuous, 0.0, 0.0, 3.0);
+        let coeffs = vec![(y, 1.0)];
+        model.add_indicator_constraint("ind", z, true, coeffs, ConstraintSense::LessEqual, 1.0);
+        model.add_sos1_constraint("sos", vec![(w, 1.0), (z, 2.0)]);
+        assert!(!model.is_linear());
+
+        // Neither is enforced while z is free.
+        assert_approx_eq!(model.solve_relaxation().objective_value, 2.0);
+
+        model.set_bounds(z, 1.0, 1.0);
+        let solution = model.solve_relaxation();
+        assert_eq!(solution.status, LpStatus::Optimal);
+        assert_approx_eq!(solution.objective_value, 1.0);
+        assert_approx_eq!(solution.values[w], 0.0);
+
+        model.set_bounds(w, 1.0, 3.0);
+        assert_eq!(model.solve_relaxation().status, LpStatus::Infeasible);
+    }
+}
@@ -0,0 +1,445 @@
+use crate::model::{ConstraintSense, LpSolution, LpStatus, Model, ObjectiveSense};
+
+use std::time::Instant;
+
+/// Parameters for the simplex method.
+#[derive(Debug, Clone)]
+pub struct SimplexOptions {
+    /// Maximum number of pivots over both phases.
+    pub max_iterations: usize,
+
+    /// Time limit in seconds over both phases.
+    pub time_limit: f64,
+
+    /// Entries of smaller magnitude are treated as zero when selecting pivots.
+    pub pivot_tolerance: f64,
+
+    /// Infeasibility (sum of artificial variables) tolerated at the end of phase 1.
+    pub feasibility_tolerance: f64,
+
+    /// Number of consecutive degenerate pivots after which we switch from Dantzig's
+    /// rule to Bland's rule, which guarantees termination.
+    pub max_degenerate_pivots: usize,
+}
+
+impl Default for SimplexOptions {
+    fn default() -> SimplexOptions {
+        SimplexOptions {
+            max_iterations: 1_000_000,
+            time_limit: f64::INFINITY,
+            pivot_tolerance: 1e-9,
+            feasibility_tolerance: 1e-7,
+            max_degenerate_pivots: 50,
+        }
+    }
+}
+
+/// A variable of the original LP in terms of nonnegative standard form columns, i.e.,
+/// x = offset + sum_k sign_k * y_{column_k}.
+#[derive(Debug, Clone)]
+struct VariableMapping {
+    offset: f64,
+    columns: Vec<(usize, f64)>,
+}
+
+/// Dense tableau for the two-phase (primal) simplex method on the standard form
+///     min c'y, Ay = b, y >= 0, b >= 0,
+/// obtained from the LP relaxation of a `Model` by shifting and splitting variables, and
+/// by adding slack and artificial variables. The tableau is kept in canonical form with respect
+/// to the current basis.
+///
+/// This is intended for small and medium sized problems (e.g., for tests and machines
+/// without commercial solvers); the tableau requires O(rows * columns) memory.
+pub struct SimplexTableau {
+    num_rows: usize,
+    num_columns: usize,
+
+    // Columns [0, num_structural) come from the original variables, followed by slacks,
+    // followed by artificial variables from `first_artificial` onwards.
+    first_artificial: usize,
+
+    // Row-major entries of size num_rows * (num_columns + 1), where the last
+    // entry of each row is the right hand side.
+    entries: Vec<f64>,
+    basis: Vec<usize>,
+
+    // Costs for phase 2, and the constant which is added to the objective.
+    costs: Vec<f64>,
+    objective_constant: f64,
+    objective_sign: f64,
+
+    variable_mappings: Vec<VariableMapping>,
+}
+
+impl SimplexTableau {
+    pub fn new(lp: &Model) -> SimplexTableau {
+        // Map original variables to nonnegative structural columns.
+        let mut variable_mappings = Vec::<VariableMapping>::new();
+        let mut num_structural = 0;
+        for variable in lp.variables().iter() {
+            let (lower, upper) = (variable.lower_bound, variable.upper_bound);
+            let mapping = match (lower.is_finite(), upper.is_finite()) {
+                (true, _) => VariableMapping {
+                    offset: lower,
+                    columns: vec![(num_structural, 1.0)],
+                },
+                (false, true) => VariableMapping {
+                    offset: upper,
+                    columns: vec![(num_structural, -1.0)],
+                },
+                (false, false) => VariableMapping {
+                    offset: 0.0,
+                    columns: vec![(num_structural, 1.0), (num_structural + 1, -1.0)],
+                },
+            };
+            num_structural += mapping.columns.len();
+            variable_mappings.push(mapping);
+        }
+
+        // Rows in terms of structural columns: (coeffs, sense, rhs). Finite upper bounds
+        // of variables which are also bounded from below become additional rows.
+        let mut rows = Vec::<(Vec<(usize, f64)>, ConstraintSense, f64)>::new();
+        for constraint in lp.constraints().iter() {
+            let mut coeffs = Vec::<(usize, f64)>::new();
+            let mut rhs = constraint.rhs;
+            for (variable_index, coeff) in constraint.coeffs.iter() {
+                let mapping = &variable_mappings[*variable_index];
+                rhs -= coeff * mapping.offset;
+                for (column, sign) in mapping.columns.iter() {
+                    coeffs.push((*column, coeff * sign));
+                }
+            }
+            rows.push((coeffs, constraint.sense, rhs));
+        }
+        for (variable, mapping) in lp.variables().iter().zip(variable_mappings.iter()) {
+            if variable.lower_bound.is_finite() && variable.upper_bound.is_finite() {
+                rows.push((
+                    vec![(mapping.columns[0].0, 1.0)],
+                    ConstraintSense::LessEqual,
+                    variable.upper_bound - variable.lower_bound,
+                ));
+            }
+        }
+
+        let num_rows = rows.len();
+        let num_slacks = rows
+            .iter()
+            .filter(|(_, sense, _)| *sense != ConstraintSense::Equal)
+            .count();
+
+        // Rows whose slack has a coefficient of +1 after making the right hand side
+        // nonnegative may use it as the initial basic variable. All others need an
+        // artificial variable.
+        let needs_artificial = rows
+            .iter()
+            .map(|(_, sense, rhs)| match sense {
+                ConstraintSense::LessEqual => *rhs < 0.0,
+                ConstraintSense::GreaterEqual => *rhs > 0.0,
+                ConstraintSense::Equal => true,
+            })
+            .collect::<Vec<bool>>();
+        let num_artificials = needs_artificial.iter().filter(|x| **x).count();
+
+        let first_artificial = num_structural + num_slacks;
+        let num_columns = first_artificial + num_artificials;
+        let width = num_columns + 1;
+        let mut entries = vec![0f64; num_rows * width];
+        let mut basis = vec![0usize; num_rows];
+
+        let mut next_slack = num_structural;
+        let mut next_artificial = first_artificial;
+        for (row_index, (coeffs, sense, rhs)) in rows.iter().enumerate() {
+            let row = &mut entries[row_index * width..(row_index + 1) * width];
+            for (column, coeff) in coeffs.iter() {
+                row[*column] += coeff;
+            }
+            let slack_column = match sense {
+                ConstraintSense::LessEqual => Some((next_slack, 1.0)),
+                ConstraintSense::GreaterEqual => Some((next_slack, -1.0)),
+                ConstraintSense::Equal => None,
+            };
+            if let Some((column, coeff)) = slack_column {
+                row[column] = coeff;
+                next_slack += 1;
+            }
+            row[num_columns] = *rhs;
+
+            // Flip rows so that the right hand side is nonnegative. Rows of the form
+            // a'y >= 0 are flipped too, so that their slack enters the basis with a +1.
+            if *rhs < 0.0 || (*rhs == 0.0 && *sense == ConstraintSense::GreaterEqual) {
+                for entry in row.iter_mut() {
+                    *entry = -*entry;
+                }
+            }
+
+            basis[row_index] = match needs_artificial[row_index] {
+                true => {
+                    row[next_artificial] = 1.0;
+                    next_artificial += 1;
+                    next_artificial - 1
+                }
+                false => slack_column.unwrap().0,
+            };
+        }
+
+        // Phase 2 costs, expressed as a minimization problem.
+        let objective_sign = match lp.objective_sense() {
+            ObjectiveSense::Minimize => 1.0,
+            ObjectiveSense::Maximize => -1.0,
+        };
+        let mut costs = vec![0f64; num_columns];
+        let mut objective_constant = 0f64;
+        for (variable, mapping) in lp.variables().iter().zip(variable_mappings.iter()) {
+            objective_constant += variable.objective_coeff * mapping.offset;
+            for (column, sign) in mapping.columns.iter() {
+                costs[*column] = objective_sign * variable.objective_coeff * sign;
+            }
+        }
+
+        SimplexTableau {
+            num_rows,
+            num_columns,
+            first_artificial,
+            entries,
+            basis,
+            costs,
+            objective_constant,
+            objective_sign,
+            variable_mappings,
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.num_columns + 1
+    }
+
+    fn entry(&self, row: usize, column: usize) -> f64 {
+        self.entries[row * self.width() + column]
+    }
+
+    fn rhs(&self, row: usize) -> f64 {
+        self.entries[row * self.width() + self.num_columns]
+    }
+
+    /// Reduced costs c_j - c_B' B^{-1} A_j of all columns, given costs `costs`.
+    fn reduced_costs(&self, costs: &[f64]) -> Vec<f64> {
+        let mut reduced_costs = costs.to_vec();
+        for row in 0..self.num_rows {
+            let basic_cost = costs[self.basis[row]];
+            if basic_cost == 0.0 {
+                continue;
+            }
+            for (column, reduced_cost) in reduced_costs.iter_mut().enumerate() {
+                *reduced_cost -= basic_cost * self.entry(row, column);
+            }
+        }
+        reduced_costs
+    }
+
+    fn pivot(&mut self, pivot_row: usize, pivot_column: usize, reduced_costs: &mut [f64]) {
+        let width = self.width();
+        let pivot_value = self.entry(pivot_row, pivot_column);
+        for entry in self.entries[pivot_row * width..(pivot_row + 1) * width].iter_mut() {
+            *entry /= pivot_value;
+        }
+        let pivot_entries = self.entries[pivot_row * width..(pivot_row + 1) * width].to_vec();
+
+        for row in 0..self.num_rows {
+            if row == pivot_row {
+                continue;
+            }
+            let factor = self.entry(row, pivot_column);
+            if factor == 0.0 {
+                continue;
+            }
+            let row_entries = &mut self.entries[row * width..(row + 1) * width];
+            for (entry, pivot_entry) in row_entries.iter_mut().zip(pivot_entries.iter()) {
+                *entry -= factor * pivot_entry;
+            }
+            // Remove numerical noise in the pivot column.
+            row_entries[pivot_column] = 0.0;
+        }
+
+        let factor = reduced_costs[pivot_column];
+        if factor != 0.0 {
+            for column in 0..self.num_columns {
+                reduced_costs[column] -= factor * pivot_entries[column];
+            }
+        }
+        self.basis[pivot_row] = pivot_column;
+    }
+
+    /// Runs simplex iterations minimizing `costs`, only allowing columns in
+    /// [0, num_allowed_columns) to enter the basis.
+    fn optimize(
+        &mut self,
+        costs: &[f64],
+        num_allowed_columns: usize,
+        options: &SimplexOptions,
+        start_time: Instant,
+        num_iterations: &mut usize,
+    ) -> LpStatus {
+        let mut reduced_costs = self.reduced_costs(costs);
+        let mut num_degenerate_pivots = 0;
+        loop {
+            if *num_iterations >= options.max_iterations {
+                return LpStatus::IterationLimit;
+            }
+            if start_time.elapsed().as_secs_f64() > options.time_limit {
+                return LpStatus::TimeLimit;
+            }
+
+            // Entering column: most negative reduced cost (Dantzig's rule), or the
+            // first column with negative reduced cost (Bland's rule) when stalling.
+            let use_bland = num_degenerate_pivots >= options.max_degenerate_pivots;
+            let mut entering = None;
+            let mut best_reduced_cost = -options.pivot_tolerance;
+            for (column, reduced_cost) in reduced_costs[..num_allowed_columns].iter().enumerate() {
+                if *reduced_cost < best_reduced_cost {
+                    entering = Some(column);
+                    best_reduced_cost = *reduced_cost;
+                    if use_bland {
+                        break;
+                    }
+                }
+            }
+            let entering = match entering {
+                Some(column) => column,
+                None => return LpStatus::Optimal,
+            };
+
+            // Leaving row: minimum ratio test, breaking ties by the smallest basic column.
+            let mut leaving = None;
+            let mut best_ratio = f64::INFINITY;
+            for row in 0..self.num_rows {
+                let coeff = self.entry(row, entering);
+                if coeff <= options.pivot_tolerance {
+                    continue;
+                }
+                let ratio = self.rhs(row).max(0.0) / coeff;
+                let is_better = match leaving {
+                    None => true,
+                    Some(best_row) => {
+                        ratio < best_ratio - options.pivot_tolerance
+                            || (ratio <= best_ratio + options.pivot_tolerance
+                                && self.basis[row] < self.basis[best_row])
+                    }
+                };
+                if is_better {
+                    leaving = Some(row);
+                    best_ratio = ratio;
+                }
+            }
+            let leaving = match leaving {
+                Some(row) => row,
+                None => return LpStatus::Unbounded,
+            };
+
+            match best_ratio <= options.pivot_tolerance {
+                true => num_degenerate_pivots += 1,
+                false => num_degenerate_pivots = 0,
+            }
+            self.pivot(leaving, entering, &mut reduced_costs);
+            *num_iterations += 1;
+        }
+    }
+
+    /// Pivots artificial variables which remain basic (at level zero) after phase 1
+    /// out of the basis. Rows where this is impossible are redundant, and their
+    /// artificial variables remain basic at zero throughout phase 2.
+    fn drive_out_artificials(&mut self, options: &SimplexOptions) {
+        let mut dummy_costs = vec![0f64; self.num_columns];
+        for row in 0..self.num_rows {
+            if self.basis[row] < self.first_artificial {
+                continue;
+            }
+            let replacement = (0..self.first_artificial)
+                .find(|column| self.entry(row, *column).abs() > options.pivot_tolerance);
+            if let Some(column) = replacement {
+                self.pivot(row, column, &mut dummy_costs);
+            }
+        }
+    }
+
+    pub fn solve(mut self, options: &SimplexOptions) -> LpSolution {
+        let start_time = Instant::now();
+        let mut num_iterations = 0;
+
+        // Phase 1: minimize the sum of artificial variables.
+        if self.first_artificial < self.num_columns {
+            let mut phase_one_costs = vec![0f64; self.num_columns];
+            for cost in phase_one_costs[self.first_artificial..].iter_mut() {
+                *cost = 1.0;
+            }
+            let status = self.optimize(
+                &phase_one_costs,
+                self.num_columns,
+                options,
+                start_time,
+                &mut num_iterations,
+            );
+            if matches!(status, LpStatus::IterationLimit | LpStatus::TimeLimit) {
+                return self.empty_solution(status);
+            }
+            let infeasibility: f64 = (0..self.num_rows)
+                .filter(|row| self.basis[*row] >= self.first_artificial)
+                .map(|row| self.rhs(row))
+                .sum();
+            if infeasibility > options.feasibility_tolerance {
+                return self.empty_solution(LpStatus::Infeasible);
+            }
+            self.drive_out_artificials(options);
+        }
+
+        // Phase 2: optimize the original objective without artificial variables.
+        let costs = self.costs.clone();
+        let status = self.optimize(
+            &costs,
+            self.first_artificial,
+            options,
+            start_time,
+            &mut num_iterations,
+        );
+        match status {
+            LpStatus::Optimal => {
+                let objective_value = self.objective_sign
+                    * (0..self.num_rows)
+                        .map(|row| costs[self.basis[row]] * self.rhs(row))
+                        .sum::<f64>()
+                    + self.objective_constant;
+                LpSolution {
+                    status,
+                    objective_value,
+                    values: self.column_values_to_variables(),
+                }
+            }
+            _ => self.empty_solution(status),
+        }
+    }
+
+    fn column_values_to_variables(&self) -> Vec<f64> {
+        let mut column_values = vec![0f64; self.num_columns];
+        for row in 0..self.num_rows {
+            column_values[self.basis[row]] = self.rhs(row);
+        }
+        self.variable_mappings
+            .iter()
+            .map(|mapping| {
+                mapping.offset
+                    + mapping
+                        .columns
+                        .iter()
+                        .map(|(column, sign)| sign * column_values[*column])
+                        .sum::<f64>()
+            })
+            .collect::<Vec<f64>>()
+    }
+
+    fn empty_solution(&self, status: LpStatus) -> LpSolution {
+        LpSolution {
+            status,
+            objective_value: f64::NAN,
+            values: vec![f64::NAN; self.variable_mappings.len()],
+        }
+    }
+}
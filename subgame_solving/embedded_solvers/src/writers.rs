@@ -0,0 +1,439 @@
+use crate::model::{ConstraintSense, Model, ObjectiveSense, Variable, VariableType};
+
+use std::fs::File;
+use std::io::{BufWriter, Error, ErrorKind, Result, Write};
+use std::path::Path;
+
+/// Maximum number of terms written on a single line of an LP file. CPLEX limits lines
+/// to 510 characters.
+const LP_TERMS_PER_LINE: usize = 8;
+
+/// Row of an MPS file, as (name, coeffs, sense, rhs).
+type MpsRow<'a> = (String, &'a Vec<(usize, f64)>, ConstraintSense, f64);
+
+/// Names in both formats may not contain whitespace, and LP files additionally
+/// reserve most operators. Offending characters are replaced by underscores, and
+/// unnamed variables and constraints are given their index as a name.
+fn sanitize_name(name: &str, default_prefix: &str, index: usize) -> String {
+    if name.is_empty() {
+        return format!("{}{}", default_prefix, index);
+    }
+    let sanitized = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '.' | '(' | ')' | '[' | ']' | '#' => c,
+            _ => '_',
+        })
+        .collect::<String>();
+    // Names in LP files may not start with a digit or period.
+    match sanitized.chars().next() {
+        Some('0'..='9') | Some('.') => format!("{}{}", default_prefix, sanitized),
+        _ => sanitized,
+    }
+}
+
+impl Model {
+    fn variable_names(&self) -> Vec<String> {
+        self.variables()
+            .iter()
+            .enumerate()
+            .map(|(index, variable)| sanitize_name(&variable.name, "x", index))
+            .collect()
+    }
+
+    fn constraint_names(&self) -> Vec<String> {
+        self.constraints()
+            .iter()
+            .enumerate()
+            .map(|(index, constraint)| sanitize_name(&constraint.name, "c", index))
+            .collect()
+    }
+
+    fn indicator_constraint_names(&self) -> Vec<String> {
+        self.indicator_constraints()
+            .iter()
+            .enumerate()
+            .map(|(index, constraint)| sanitize_name(&constraint.name, "ic", index))
+            .collect()
+    }
+
+    fn sos1_constraint_names(&self) -> Vec<String> {
+        self.sos1_constraints()
+            .iter()
+            .enumerate()
+            .map(|(index, constraint)| sanitize_name(&constraint.name, "s", index))
+            .collect()
+    }
+
+    /// Linear parts of all constraints and indicator constraints. Indicator constraints are
+    /// written as ordinary rows in MPS files, which are then marked as such in the
+    /// INDICATORS section.
+    fn mps_rows(&self) -> Vec<MpsRow<'_>> {
+        let constraints = self
+            .constraints()
+            .iter()
+            .zip(self.constraint_names())
+            .map(|(c, name)| (name, &c.coeffs, c.sense, c.rhs));
+        let indicator_constraints = self
+            .indicator_constraints()
+            .iter()
+            .zip(self.indicator_constraint_names())
+            .map(|(c, name)| (name, &c.coeffs, c.sense, c.rhs));
+        constraints.chain(indicator_constraints).collect()
+    }
+
+    /// Writes the model in free MPS format. Indicator and SOS1 constraints are written in
+    /// the INDICATORS and SOS sections understood by Gurobi and CPLEX.
+    pub fn write_mps<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let variable_names = self.variable_names();
+        let rows = self.mps_rows();
+
+        writeln!(writer, "NAME {}", sanitize_name(self.name(), "model", 0))?;
+        writeln!(writer, "OBJSENSE")?;
+        match self.objective_sense() {
+            ObjectiveSense::Minimize => writeln!(writer, "    MIN")?,
+            ObjectiveSense::Maximize => writeln!(writer, "    MAX")?,
+        }
+
+        writeln!(writer, "ROWS")?;
+        writeln!(writer, " N  obj")?;
+        for (name, _, sense, _) in rows.iter() {
+            let sense = match sense {
+                ConstraintSense::LessEqual => "L",
+                ConstraintSense::GreaterEqual => "G",
+                ConstraintSense::Equal => "E",
+            };
+            writeln!(writer, " {}  {}", sense, name)?;
+        }
+
+        // MPS files are written column by column.
+        let mut columns = vec![Vec::<(usize, f64)>::new(); self.num_variables()];
+        for (row_index, (_, coeffs, _, _)) in rows.iter().enumerate() {
+            for (variable, coeff) in coeffs.iter() {
+                columns[*variable].push((row_index, *coeff));
+            }
+        }
+
+        writeln!(writer, "COLUMNS")?;
+        let mut is_integer_block = false;
+        for (variable_index, variable) in self.variables().iter().enumerate() {
+            let is_integer = variable.variable_type != VariableType::Continuous;
+            if is_integer != is_integer_block {
+                let marker = if is_integer { "INTORG" } else { "INTEND" };
+                writeln!(writer, "    MARKER  'MARKER'  '{}'", marker)?;
+                is_integer_block = is_integer;
+            }
+
+            let name = &variable_names[variable_index];
+            if variable.objective_coeff != 0.0 || columns[variable_index].is_empty() {
+                writeln!(writer, "    {}  obj  {}", name, variable.objective_coeff)?;
+            }
+            for (row_index, coeff) in columns[variable_index].iter() {
+                writeln!(writer, "    {}  {}  {}", name, rows[*row_index].0, coeff)?;
+            }
+        }
+        if is_integer_block {
+            writeln!(writer, "    MARKER  'MARKER'  'INTEND'")?;
+        }
+
+        writeln!(writer, "RHS")?;
+        for (name, _, _, rhs) in rows.iter() {
+            if *rhs != 0.0 {
+                writeln!(writer, "    RHS  {}  {}", name, rhs)?;
+            }
+        }
+
+        writeln!(writer, "BOUNDS")?;
+        for (variable, name) in self.variables().iter().zip(variable_names.iter()) {
+            write_mps_bounds(writer, variable, name)?;
+        }
+
+        if !self.sos1_constraints().is_empty() {
+            writeln!(writer, "SOS")?;
+            let names = self.sos1_constraint_names();
+            for (constraint, name) in self.sos1_constraints().iter().zip(names.iter()) {
+                writeln!(writer, " S1 SOS  {}", name)?;
+                for (variable, weight) in constraint.variables.iter() {
+                    writeln!(writer, "    {}:{}", variable_names[*variable], weight)?;
+                }
+            }
+        }
+
+        if !self.indicator_constraints().is_empty() {
+            writeln!(writer, "INDICATORS")?;
+            let num_constraints = self.num_constraints();
+            for (index, constraint) in self.indicator_constraints().iter().enumerate() {
+                writeln!(
+                    writer,
+                    " IF  {}  {}  {}",
+                    rows[num_constraints + index].0,
+                    variable_names[constraint.binary_variable],
+                    constraint.binary_value as u8
+                )?;
+            }
+        }
+
+        writeln!(writer, "ENDATA")
+    }
+
+    /// Writes the model in CPLEX LP format.
+    pub fn write_lp<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let variable_names = self.variable_names();
+        let constraint_names = self.constraint_names();
+
+        writeln!(writer, "\\ Model {}", self.name())?;
+        match self.objective_sense() {
+            ObjectiveSense::Minimize => writeln!(writer, "Minimize")?,
+            ObjectiveSense::Maximize => writeln!(writer, "Maximize")?,
+        }
+        let objective = self
+            .variables()
+            .iter()
+            .enumerate()
+            .filter(|(_, variable)| variable.objective_coeff != 0.0)
+            .map(|(index, variable)| (index, variable.objective_coeff))
+            .collect::<Vec<(usize, f64)>>();
+        write!(writer, " obj:")?;
+        write_lp_expression(writer, &objective, &variable_names)?;
+        writeln!(writer)?;
+
+        writeln!(writer, "Subject To")?;
+        for (constraint, name) in self.constraints().iter().zip(constraint_names.iter()) {
+            write!(writer, " {}:", name)?;
+            write_lp_expression(writer, &constraint.coeffs, &variable_names)?;
+            writeln!(writer, " {} {}", format_lp_sense(constraint.sense), constraint.rhs)?;
+        }
+        let names = self.indicator_constraint_names();
+        for (constraint, name) in self.indicator_constraints().iter().zip(names.iter()) {
+            write!(
+                writer,
+                " {}: {} = {} ->",
+                name,
+                variable_names[constraint.binary_variable],
+                constraint.binary_value as u8
+            )?;
+            write_lp_expression(writer, &constraint.coeffs, &variable_names)?;
+            writeln!(writer, " {} {}", format_lp_sense(constraint.sense), constraint.rhs)?;
+        }
+
+        writeln!(writer, "Bounds")?;
+        for (variable, name) in self.variables().iter().zip(variable_names.iter()) {
+            let is_default = match variable.variable_type {
+                VariableType::Binary => variable.lower_bound == 0.0 && variable.upper_bound == 1.0,
+                _ => variable.lower_bound == 0.0 && variable.upper_bound == f64::INFINITY,
+            };
+            if is_default {
+                continue;
+            }
+            if variable.lower_bound == variable.upper_bound {
+                writeln!(writer, " {} = {}", name, variable.lower_bound)?;
+            } else if variable.lower_bound == -f64::INFINITY
+                && variable.upper_bound == f64::INFINITY
+            {
+                writeln!(writer, " {} free", name)?;
+            } else {
+                writeln!(
+                    writer,
+                    " {} <= {} <= {}",
+                    format_lp_bound(variable.lower_bound),
+                    name,
+                    format_lp_bound(variable.upper_bound)
+                )?;
+            }
+        }
+
+        for (section, variable_type) in [
+            ("Generals", VariableType::Integer),
+            ("Binaries", VariableType::Binary),
+        ]
+        .iter()
+        {
+            let names = self
+                .variables()
+                .iter()
+                .zip(variable_names.iter())
+                .filter(|(variable, _)| variable.variable_type == *variable_type)
+                .map(|(_, name)| name.as_str())
+                .collect::<Vec<&str>>();
+            if !names.is_empty() {
+                writeln!(writer, "{}", section)?;
+                for chunk in names.chunks(LP_TERMS_PER_LINE) {
+                    writeln!(writer, " {}", chunk.join(" "))?;
+                }
+            }
+        }
+
+        if !self.sos1_constraints().is_empty() {
+            writeln!(writer, "SOS")?;
+            let names = self.sos1_constraint_names();
+            for (constraint, name) in self.sos1_constraints().iter().zip(names.iter()) {
+                let members = constraint
+                    .variables
+                    .iter()
+                    .map(|(variable, weight)| format!("{}:{}", variable_names[*variable], weight))
+                    .collect::<Vec<String>>();
+                writeln!(writer, " {}: S1:: {}", name, members.join(" "))?;
+            }
+        }
+
+        writeln!(writer, "End")
+    }
+
+    /// Writes the model to `path`, in MPS format if the extension is `.mps` and in LP
+    /// format if the extension is `.lp`.
+    pub fn write_to_file(&self, path: &Path) -> Result<()> {
+        let extension = path.extension().and_then(|x| x.to_str());
+        let mut writer = BufWriter::new(File::create(path)?);
+        match extension {
+            Some("mps") => self.write_mps(&mut writer),
+            Some("lp") => self.write_lp(&mut writer),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown model file format: {:?}", path),
+            )),
+        }
+    }
+}
+
+fn write_mps_bounds<W: Write>(writer: &mut W, variable: &Variable, name: &str) -> Result<()> {
+    let (lb, ub) = (variable.lower_bound, variable.upper_bound);
+    if variable.variable_type == VariableType::Binary && lb == 0.0 && ub == 1.0 {
+        return writeln!(writer, " BV BND  {}", name);
+    }
+    if lb == ub {
+        return writeln!(writer, " FX BND  {}  {}", name, lb);
+    }
+    if lb == -f64::INFINITY && ub == f64::INFINITY {
+        return writeln!(writer, " FR BND  {}", name);
+    }
+
+    // Integer variables are written out in full, since some readers default their
+    // upper bound to 1.
+    let is_integer = variable.variable_type != VariableType::Continuous;
+    if lb == -f64::INFINITY {
+        writeln!(writer, " MI BND  {}", name)?;
+    } else if lb != 0.0 || is_integer {
+        writeln!(writer, " LO BND  {}  {}", name, lb)?;
+    }
+    if ub != f64::INFINITY {
+        writeln!(writer, " UP BND  {}  {}", name, ub)?;
+    } else if is_integer {
+        writeln!(writer, " PL BND  {}", name)?;
+    }
+    Ok(())
+}
+
+fn format_lp_sense(sense: ConstraintSense) -> &'static str {
+    match sense {
+        ConstraintSense::LessEqual => "<=",
+        ConstraintSense::GreaterEqual => ">=",
+        ConstraintSense::Equal => "=",
+    }
+}
+
+fn format_lp_bound(bound: f64) -> String {
+    if bound == f64::INFINITY {
+        "+inf".to_string()
+    } else if bound == -f64::INFINITY {
+        "-inf".to_string()
+    } else {
+        format!("{}", bound)
+    }
+}
+
+/// Writes a linear expression, breaking long expressions over multiple lines. Empty
+/// expressions are written as `0 x` for the first variable, since LP files do not
+/// allow them.
+fn write_lp_expression<W: Write>(
+    writer: &mut W,
+    coeffs: &[(usize, f64)],
+    variable_names: &[String],
+) -> Result<()> {
+    if coeffs.is_empty() {
+        return match variable_names.first() {
+            Some(name) => write!(writer, " 0 {}", name),
+            None => Ok(()),
+        };
+    }
+    for (term, (variable, coeff)) in coeffs.iter().enumerate() {
+        if term > 0 && term % LP_TERMS_PER_LINE == 0 {
+            write!(writer, "\n  ")?;
+        }
+        let sign = if *coeff < 0.0 { '-' } else { '+' };
+        write!(writer, " {} {} {}", sign, coeff.abs(), variable_names[*variable])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod test_fixtures {
+    use crate::model::{ConstraintSense, Model, ObjectiveSense, VariableType, INFINITY};
+
+    fn small_model() -> Model {
+        let mut model = Model::new("small model", ObjectiveSense::Maximize);
+        let x = model.add_variable("x", VariableType::Continuous, 3.0, 0.0, INFINITY);
+        let y = model.add_variable("y", VariableType::Continuous, 0.0, -INFINITY, INFINITY);
+        let z = model.add_variable("z 0", VariableType::Binary, -1.5, 0.0, 1.0);
+        let n = model.add_variable("n", VariableType::Integer, 0.0, 0.0, 10.0);
+        model.add_constraint(
+            "c0",
+            vec![(x, 1.0), (y, -2.0), (z, 4.0)],
+            ConstraintSense::LessEqual,
+            4.0,
+        );
+        model.add_constraint("c1", vec![(y, 1.0), (n, 1.0)], ConstraintSense::Equal, 0.0);
+        let coeffs = vec![(x, 1.0)];
+        model.add_indicator_constraint("i0", z, true, coeffs, ConstraintSense::LessEqual, 0.0);
+        model.add_sos1_constraint("s0", vec![(x, 1.0), (z, 2.0)]);
+        model
+    }
+
+    #[test]
+    fn writes_mps() {
+        let mut buffer = Vec::<u8>::new();
+        small_model().write_mps(&mut buffer).unwrap();
+        let mps = String::from_utf8(buffer).unwrap();
+        let lines = mps.lines().collect::<Vec<&str>>();
+
+        assert_eq!(lines[0], "NAME small_model");
+        assert!(lines.contains(&"    MAX"));
+        assert!(lines.contains(&" L  c0"));
+        assert!(lines.contains(&" E  c1"));
+        assert!(lines.contains(&"    x  obj  3"));
+        assert!(lines.contains(&"    y  c0  -2"));
+        assert!(lines.contains(&"    MARKER  'MARKER'  'INTORG'"));
+        assert!(lines.contains(&"    z_0  obj  -1.5"));
+        assert!(lines.contains(&"    RHS  c0  4"));
+        assert!(lines.contains(&" FR BND  y"));
+        assert!(lines.contains(&" BV BND  z_0"));
+        assert!(lines.contains(&" UP BND  n  10"));
+        assert!(lines.contains(&" L  i0"));
+        assert!(lines.contains(&"    x  i0  1"));
+        assert!(lines.contains(&" IF  i0  z_0  1"));
+        assert!(lines.contains(&" S1 SOS  s0"));
+        assert!(lines.contains(&"    z_0:2"));
+        assert_eq!(*lines.last().unwrap(), "ENDATA");
+    }
+
+    #[test]
+    fn writes_lp() {
+        let mut buffer = Vec::<u8>::new();
+        small_model().write_lp(&mut buffer).unwrap();
+        let lp = String::from_utf8(buffer).unwrap();
+        let lines = lp.lines().collect::<Vec<&str>>();
+
+        assert!(lines.contains(&"Maximize"));
+        assert!(lines.contains(&" obj: + 3 x - 1.5 z_0"));
+        assert!(lines.contains(&" c0: + 1 x - 2 y + 4 z_0 <= 4"));
+        assert!(lines.contains(&" c1: + 1 y + 1 n = 0"));
+        assert!(lines.contains(&" y free"));
+        assert!(lines.contains(&" 0 <= n <= 10"));
+        assert!(lines.contains(&"Generals"));
+        assert!(lines.contains(&" n"));
+        assert!(lines.contains(&"Binaries"));
+        assert!(lines.contains(&" z_0"));
+        assert!(lines.contains(&" i0: z_0 = 1 -> + 1 x <= 0"));
+        assert!(lines.contains(&" s0: S1:: x:1 z_0:2"));
+        assert_eq!(*lines.last().unwrap(), "End");
+    }
+}
@@ -0,0 +1,207 @@
+use crate::cbc_bindings::bindings::*;
+
+use embedded_solvers::{ConstraintSense, Model, ObjectiveSense, VariableType};
+
+use std::ffi::CString;
+use std::slice;
+
+/// A `Model` loaded into CBC.
+pub struct CbcModel {
+    model: *mut core::ffi::c_void,
+    num_variables: usize,
+}
+
+impl CbcModel {
+    pub fn new(model: &Model) -> CbcModel {
+        unsafe {
+            let cbc_model = Cbc_newModel();
+            Cbc_setProblemName(cbc_model, CString::new(model.name()).unwrap().as_ptr());
+            let cbc_model = CbcModel {
+                model: cbc_model,
+                num_variables: model.num_variables(),
+            };
+            cbc_model.add_variables(model);
+            cbc_model.add_constraints(model);
+            cbc_model.add_indicator_constraints(model);
+            cbc_model.add_sos1_constraints(model);
+
+            let objective_sense = match model.objective_sense() {
+                ObjectiveSense::Minimize => 1f64,
+                ObjectiveSense::Maximize => -1f64,
+            };
+            Cbc_setObjSense(cbc_model.model, objective_sense);
+            cbc_model
+        }
+    }
+
+    fn add_variables(&self, model: &Model) {
+        for variable in model.variables().iter() {
+            let is_integer = match variable.variable_type {
+                VariableType::Continuous => 0,
+                VariableType::Binary | VariableType::Integer => 1,
+            };
+            unsafe {
+                Cbc_addCol(
+                    self.model,
+                    CString::new(variable.name.as_str()).unwrap().as_ptr(),
+                    variable.lower_bound,
+                    variable.upper_bound,
+                    variable.objective_coeff,
+                    is_integer,
+                    0,                    // Constraints are added later on.
+                    std::ptr::null_mut(), // Constraints are added later on.
+                    std::ptr::null_mut(), // Constraints are added later on.
+                );
+            }
+        }
+        unsafe {
+            assert_eq!(Cbc_getNumCols(self.model), self.num_variables as i32);
+        }
+    }
+
+    fn add_row(&self, name: &str, coeffs: &[(usize, f64)], sense: ConstraintSense, rhs: f64) {
+        let col_indices = coeffs
+            .iter()
+            .map(|(index, _)| *index as i32)
+            .collect::<Vec<i32>>();
+        let col_coeffs = coeffs.iter().map(|(_, coeff)| *coeff).collect::<Vec<f64>>();
+        let sense = match sense {
+            ConstraintSense::LessEqual => 'L',
+            ConstraintSense::GreaterEqual => 'G',
+            ConstraintSense::Equal => 'E',
+        };
+        unsafe {
+            Cbc_addRow(
+                self.model,
+                CString::new(name).unwrap().as_ptr(),
+                col_indices.len() as i32,
+                col_indices.as_slice().as_ptr(),
+                col_coeffs.as_slice().as_ptr(),
+                sense as i8,
+                rhs,
+            );
+        }
+    }
+
+    fn add_constraints(&self, model: &Model) {
+        for constraint in model.constraints().iter() {
+            self.add_row(
+                &constraint.name,
+                &constraint.coeffs,
+                constraint.sense,
+                constraint.rhs,
+            );
+        }
+    }
+
+    /// The C interface of CBC has no indicator constraints, so they are linearized using
+    /// the bounds of the variables involved. For example, z = 1 -> a'x <= b becomes
+    /// a'x + Mz <= b + M, where M is the largest violation a'x - b within the bounds.
+    /// Panics if the violation is unbounded.
+    fn add_indicator_constraints(&self, model: &Model) {
+        for constraint in model.indicator_constraints().iter() {
+            let senses = match constraint.sense {
+                ConstraintSense::Equal => {
+                    vec![ConstraintSense::LessEqual, ConstraintSense::GreaterEqual]
+                }
+                sense => vec![sense],
+            };
+            for sense in senses.into_iter() {
+                let (min_lhs, max_lhs) = constraint.coeffs.iter().fold(
+                    (0f64, 0f64),
+                    |(min_lhs, max_lhs), (index, coeff)| {
+                        let variable = &model.variables()[*index];
+                        let (a, b) = (coeff * variable.lower_bound, coeff * variable.upper_bound);
+                        (min_lhs + a.min(b), max_lhs + a.max(b))
+                    },
+                );
+                // Coefficient of the binary variable and rhs of the linearized constraint.
+                let (big_m, binary_coeff, rhs) = match (sense, constraint.binary_value) {
+                    (ConstraintSense::LessEqual, value) => {
+                        let big_m = (max_lhs - constraint.rhs).max(0.0);
+                        match value {
+                            true => (big_m, big_m, constraint.rhs + big_m),
+                            false => (big_m, -big_m, constraint.rhs),
+                        }
+                    }
+                    (_, value) => {
+                        let big_m = (constraint.rhs - min_lhs).max(0.0);
+                        match value {
+                            true => (big_m, -big_m, constraint.rhs - big_m),
+                            false => (big_m, big_m, constraint.rhs),
+                        }
+                    }
+                };
+                assert!(
+                    big_m.is_finite(),
+                    "Indicator constraint {} has unbounded variables.",
+                    constraint.name
+                );
+                let mut coeffs = constraint.coeffs.clone();
+                coeffs.push((constraint.binary_variable, binary_coeff));
+                self.add_row(&constraint.name, &coeffs, sense, rhs);
+            }
+        }
+    }
+
+    fn add_sos1_constraints(&self, model: &Model) {
+        if model.sos1_constraints().is_empty() {
+            return;
+        }
+        let mut row_starts = vec![0i32];
+        let mut col_indices = Vec::<i32>::new();
+        let mut weights = Vec::<f64>::new();
+        for constraint in model.sos1_constraints().iter() {
+            for (index, weight) in constraint.variables.iter() {
+                col_indices.push(*index as i32);
+                weights.push(*weight);
+            }
+            row_starts.push(col_indices.len() as i32);
+        }
+        unsafe {
+            Cbc_addSOS(
+                self.model,
+                model.sos1_constraints().len() as i32,
+                row_starts.as_ptr(),
+                col_indices.as_ptr(),
+                weights.as_ptr(),
+                1,
+            );
+        }
+    }
+
+    pub fn solve(&self) {
+        unsafe {
+            Cbc_solve(self.model);
+        }
+    }
+
+    pub fn objective_value(&self) -> f64 {
+        unsafe { Cbc_getObjValue(self.model) }
+    }
+
+    pub fn is_proven_optimal(&self) -> bool {
+        unsafe { Cbc_isProvenOptimal(self.model) != 0 }
+    }
+
+    pub fn is_proven_infeasible(&self) -> bool {
+        unsafe { Cbc_isProvenInfeasible(self.model) != 0 }
+    }
+
+    pub fn is_seconds_limit_reached(&self) -> bool {
+        unsafe { Cbc_isSecondsLimitReached(self.model) != 0 }
+    }
+
+    /// Whether an integer feasible solution was found.
+    pub fn has_solution(&self) -> bool {
+        unsafe { !Cbc_bestSolution(self.model).is_null() }
+    }
+
+    /// Values of all variables in the best solution found.
+    pub fn values(&self) -> Vec<f64> {
+        unsafe {
+            let ptr = Cbc_getColSolution(self.model);
+            slice::from_raw_parts(ptr, self.num_variables).to_vec()
+        }
+    }
+}
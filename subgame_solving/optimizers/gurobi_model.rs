@@ -0,0 +1,318 @@
+use crate::gurobi_bindings::bindings::*;
+
+use embedded_solvers::{ConstraintSense, Model, ObjectiveSense, VariableType};
+
+use std::ffi::CString;
+
+/// A `Model` loaded into Gurobi. The environment and model are freed when dropped.
+pub struct GurobiModel {
+    env: *mut GRBenv,
+    model: *mut GRBmodel,
+    num_variables: usize,
+}
+
+fn to_gurobi_bound(bound: f64) -> f64 {
+    bound.clamp(-GRB_INFINITY, GRB_INFINITY)
+}
+
+fn to_gurobi_sense(sense: ConstraintSense) -> u8 {
+    match sense {
+        ConstraintSense::LessEqual => GRB_LESS_EQUAL,
+        ConstraintSense::GreaterEqual => GRB_GREATER_EQUAL,
+        ConstraintSense::Equal => GRB_EQUAL,
+    }
+}
+
+impl GurobiModel {
+    /// Creates a new environment logging to `log_file`, and loads `model` into it.
+    /// Parameters should be set using `set_double_param` before calling `optimize`.
+    pub fn new(model: &Model, log_file: &str) -> GurobiModel {
+        unsafe {
+            let mut env: *mut GRBenv = std::ptr::null_mut();
+            let err = GRBloadenv(&mut env, CString::new(log_file).unwrap().as_ptr());
+            assert_eq!(err, 0);
+
+            let mut grb_model: *mut GRBmodel = std::ptr::null_mut();
+            let err = GRBnewmodel(
+                env,
+                &mut grb_model,
+                CString::new(model.name()).unwrap().as_ptr(),
+                0,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            );
+            assert_eq!(err, 0);
+
+            let gurobi_model = GurobiModel {
+                env,
+                model: grb_model,
+                num_variables: model.num_variables(),
+            };
+            gurobi_model.add_variables(model);
+            gurobi_model.add_constraints(model);
+            gurobi_model.add_indicator_constraints(model);
+            gurobi_model.add_sos1_constraints(model);
+            gurobi_model.set_model_sense(model.objective_sense());
+            gurobi_model
+        }
+    }
+
+    fn add_variables(&self, model: &Model) {
+        for variable in model.variables().iter() {
+            let vtype = match variable.variable_type {
+                VariableType::Continuous => GRB_CONTINUOUS,
+                VariableType::Binary => GRB_BINARY,
+                VariableType::Integer => GRB_INTEGER,
+            };
+            unsafe {
+                let err = GRBaddvar(
+                    self.model,
+                    0,                    // Constraints are added later on.
+                    std::ptr::null_mut(), // Constraints are added later on.
+                    std::ptr::null_mut(), // Constraints are added later on.
+                    variable.objective_coeff,
+                    to_gurobi_bound(variable.lower_bound),
+                    to_gurobi_bound(variable.upper_bound),
+                    vtype as i8,
+                    CString::new(variable.name.as_str()).unwrap().as_ptr(),
+                );
+                assert_eq!(err, 0);
+            }
+        }
+    }
+
+    fn add_constraints(&self, model: &Model) {
+        for constraint in model.constraints().iter() {
+            let mut col_indices = constraint
+                .coeffs
+                .iter()
+                .map(|(index, _)| *index as i32)
+                .collect::<Vec<i32>>();
+            let mut col_coeffs = constraint
+                .coeffs
+                .iter()
+                .map(|(_, coeff)| *coeff)
+                .collect::<Vec<f64>>();
+            unsafe {
+                let err = GRBaddconstr(
+                    self.model,
+                    col_indices.len() as i32,
+                    col_indices.as_mut_ptr(),
+                    col_coeffs.as_mut_ptr(),
+                    to_gurobi_sense(constraint.sense) as i8,
+                    constraint.rhs,
+                    CString::new(constraint.name.as_str()).unwrap().as_ptr(),
+                );
+                assert_eq!(err, 0);
+            }
+        }
+    }
+
+    fn add_indicator_constraints(&self, model: &Model) {
+        for constraint in model.indicator_constraints().iter() {
+            let col_indices = constraint
+                .coeffs
+                .iter()
+                .map(|(index, _)| *index as i32)
+                .collect::<Vec<i32>>();
+            let col_coeffs = constraint
+                .coeffs
+                .iter()
+                .map(|(_, coeff)| *coeff)
+                .collect::<Vec<f64>>();
+            unsafe {
+                let err = GRBaddgenconstrIndicator(
+                    self.model,
+                    CString::new(constraint.name.as_str()).unwrap().as_ptr(),
+                    constraint.binary_variable as i32,
+                    constraint.binary_value as i32,
+                    col_indices.len() as i32,
+                    col_indices.as_ptr(),
+                    col_coeffs.as_ptr(),
+                    to_gurobi_sense(constraint.sense) as i8,
+                    constraint.rhs,
+                );
+                assert_eq!(err, 0);
+            }
+        }
+    }
+
+    fn add_sos1_constraints(&self, model: &Model) {
+        for constraint in model.sos1_constraints().iter() {
+            let mut types = vec![GRB_SOS_TYPE1 as i32];
+            let mut beg = vec![0i32];
+            let mut indices = constraint
+                .variables
+                .iter()
+                .map(|(index, _)| *index as i32)
+                .collect::<Vec<i32>>();
+            let mut weights = constraint
+                .variables
+                .iter()
+                .map(|(_, weight)| *weight)
+                .collect::<Vec<f64>>();
+            unsafe {
+                let err = GRBaddsos(
+                    self.model,
+                    1,
+                    indices.len() as i32,
+                    types.as_mut_ptr(),
+                    beg.as_mut_ptr(),
+                    indices.as_mut_ptr(),
+                    weights.as_mut_ptr(),
+                );
+                assert_eq!(err, 0);
+            }
+        }
+    }
+
+    fn set_model_sense(&self, objective_sense: ObjectiveSense) {
+        let model_sense = match objective_sense {
+            ObjectiveSense::Minimize => 1,
+            ObjectiveSense::Maximize => -1,
+        };
+        unsafe {
+            let err = GRBsetintattr(
+                self.model,
+                CString::new("ModelSense").unwrap().as_ptr(),
+                model_sense,
+            );
+            assert_eq!(err, 0);
+        }
+    }
+
+    /// Sets a parameter, e.g., "TimeLimit" or "FeasibilityTol", of the model's environment.
+    pub fn set_double_param(&self, name: &str, value: f64) {
+        unsafe {
+            let err = GRBsetdblparam(
+                GRBgetenv(self.model),
+                CString::new(name).unwrap().as_ptr(),
+                value,
+            );
+            assert_eq!(err, 0);
+        }
+    }
+
+    /// Sets an integer parameter, e.g., "Threads", of the model's environment.
+    pub fn set_int_param(&self, name: &str, value: i32) {
+        unsafe {
+            let err = GRBsetintparam(
+                GRBgetenv(self.model),
+                CString::new(name).unwrap().as_ptr(),
+                value,
+            );
+            assert_eq!(err, 0);
+        }
+    }
+
+    /// Sets the MIP start of a variable.
+    pub fn set_start(&self, variable: usize, value: f64) {
+        unsafe {
+            let err = GRBsetdblattrelement(
+                self.model,
+                CString::new("Start").unwrap().as_ptr(),
+                variable as i32,
+                value,
+            );
+            assert_eq!(err, 0);
+        }
+    }
+
+    pub fn optimize(&self) {
+        let result = self.try_optimize();
+        assert_eq!(result, Ok(()));
+    }
+
+    /// Optimizes the model, returning Gurobi's error code if it fails, e.g., when out of
+    /// memory. Whether a solution was found is given by `status` and `solution_count`.
+    pub fn try_optimize(&self) -> Result<(), i32> {
+        let err = unsafe { GRBoptimize(self.model) };
+        if err == 0 {
+            Ok(())
+        } else {
+            Err(err)
+        }
+    }
+
+    /// Status of the optimized model, given by one of the `GRB_*` status codes,
+    /// e.g., `GRB_OPTIMAL` or `GRB_TIME_LIMIT`.
+    pub fn status(&self) -> u32 {
+        let mut status: i32 = 0;
+        unsafe {
+            let err = GRBgetintattr(
+                self.model,
+                CString::new("Status").unwrap().as_ptr(),
+                &mut status,
+            );
+            assert_eq!(err, 0);
+        }
+        status as u32
+    }
+
+    /// Number of feasible solutions found.
+    pub fn solution_count(&self) -> usize {
+        let mut solution_count: i32 = 0;
+        unsafe {
+            GRBgetintattr(
+                self.model,
+                CString::new("SolCount").unwrap().as_ptr(),
+                &mut solution_count,
+            );
+        }
+        solution_count as usize
+    }
+
+    pub fn objective_value(&self) -> f64 {
+        let mut objective_value: f64 = 0f64;
+        unsafe {
+            GRBgetdblattr(
+                self.model,
+                CString::new("ObjVal").unwrap().as_ptr(),
+                &mut objective_value,
+            );
+        }
+        objective_value
+    }
+
+    /// Relative gap between the incumbent and the best bound.
+    pub fn mip_gap(&self) -> f64 {
+        let mut mip_gap: f64 = 0f64;
+        unsafe {
+            GRBgetdblattr(
+                self.model,
+                CString::new("MIPGap").unwrap().as_ptr(),
+                &mut mip_gap,
+            );
+        }
+        mip_gap
+    }
+
+    /// Values of all variables in the incumbent solution.
+    pub fn values(&self) -> Vec<f64> {
+        let mut dst = vec![0f64; self.num_variables];
+        unsafe {
+            GRBgetdblattrarray(
+                self.model,
+                CString::new("X").unwrap().as_ptr(),
+                0,
+                self.num_variables as i32,
+                dst.as_mut_ptr(),
+            );
+        }
+        dst
+    }
+}
+
+impl Drop for GurobiModel {
+    // Free model and environment. If omitted, Gurobi will complain with
+    // error 10009 (License not found).
+    fn drop(&mut self) {
+        unsafe {
+            GRBfreemodel(self.model);
+            GRBfreeenv(self.env);
+        }
+    }
+}
@@ -1,2 +1,7 @@
 pub mod cbc_bindings;
-pub mod gurobi_bindings;
\ No newline at end of file
+pub mod cbc_model;
+pub mod gurobi_bindings;
+pub mod gurobi_model;
+
+pub use self::cbc_model::CbcModel;
+pub use self::gurobi_model::GurobiModel;
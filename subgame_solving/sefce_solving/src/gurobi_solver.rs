@@ -0,0 +1,39 @@
+use crate::sefce_model::SefceModel;
+use crate::sefce_solution::SefceSolution;
+use crate::solver::{Solver, SolverConfig};
+use efg_lite::game::ExtensiveFormGame;
+
+use optimizers::GurobiModel;
+
+/// Solves the LP built by `SefceModel` using Gurobi.
+pub struct GurobiSolver<'a> {
+    sefce_model: SefceModel<'a>,
+    gurobi_model: GurobiModel,
+}
+
+impl<'a> Solver<'a> for GurobiSolver<'a> {
+    fn new(game: &'a ExtensiveFormGame, solver_config: &'a SolverConfig) -> GurobiSolver<'a> {
+        let sefce_model = SefceModel::new(game);
+        let gurobi_model = GurobiModel::new(sefce_model.model(), "sefce-log");
+
+        println!("Setting time limit");
+        println!("{:?}", solver_config.time_limit);
+        gurobi_model.set_double_param("TimeLimit", solver_config.time_limit);
+
+        GurobiSolver {
+            sefce_model,
+            gurobi_model,
+        }
+    }
+
+    fn solve(&self) {
+        self.gurobi_model.optimize();
+    }
+
+    fn get_solution(&self) -> SefceSolution {
+        self.sefce_model.solution(
+            &self.gurobi_model.values(),
+            self.gurobi_model.objective_value(),
+        )
+    }
+}
@@ -0,0 +1,156 @@
+#[cfg(feature = "gurobi")]
+mod gurobi_solver;
+mod sefce_model;
+mod sefce_solution;
+mod simplex_solver;
+mod solver;
+
+use std::fs::File;
+use std::io::{BufReader, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use structopt::StructOpt;
+
+use efg_lite::game::{ExtensiveFormGame, Player};
+use efg_lite::schema::game_capnp;
+
+use log::{info, warn};
+
+#[cfg(feature = "gurobi")]
+use crate::gurobi_solver::GurobiSolver;
+use crate::sefce_model::SefceModel;
+use crate::sefce_solution::SefceSolution;
+use crate::simplex_solver::SimplexSolver;
+use crate::solver::{Solver, SolverConfig};
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "SefceSolver")]
+struct Opt {
+    // Input game file
+    #[structopt(short = "g", long = "input_game_file")]
+    input_file: PathBuf,
+
+    // Time limit
+    #[structopt(short = "t", long = "time_limit")]
+    time_limit: f64,
+
+    // Solver to use: gurobi or simplex.
+    #[structopt(short = "s", long = "solver", default_value = "gurobi")]
+    solver: SolverType,
+
+    // Write the LP to this file before solving, in MPS (.mps) or LP (.lp) format.
+    #[structopt(long = "model_file")]
+    model_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SolverType {
+    Gurobi,
+    Simplex,
+}
+
+impl FromStr for SolverType {
+    type Err = String;
+    fn from_str(solver_type: &str) -> Result<Self, Self::Err> {
+        match solver_type {
+            "gurobi" | "Gurobi" => Ok(SolverType::Gurobi),
+            "simplex" | "Simplex" => Ok(SolverType::Simplex),
+            s => Err(format!("Unknown solver type: {}", s)),
+        }
+    }
+}
+
+fn run_solver<'a, S: Solver<'a>>(
+    game: &'a ExtensiveFormGame,
+    solver_config: &'a SolverConfig,
+) -> SefceSolution {
+    let solver = S::new(game, solver_config);
+    solver.solve();
+    solver.get_solution()
+}
+
+/// Solves the LP using Gurobi, or using the embedded simplex solver if we were built
+/// without Gurobi.
+#[cfg(feature = "gurobi")]
+fn run_lp_solver(
+    game: &ExtensiveFormGame,
+    solver_config: &SolverConfig,
+) -> SefceSolution {
+    run_solver::<GurobiSolver>(game, solver_config)
+}
+
+#[cfg(not(feature = "gurobi"))]
+fn run_lp_solver(
+    game: &ExtensiveFormGame,
+    solver_config: &SolverConfig,
+) -> SefceSolution {
+    warn!("Built without Gurobi, using the embedded simplex solver instead");
+    run_solver::<SimplexSolver>(game, solver_config)
+}
+
+fn main() {
+    env_logger::init();
+
+    let opt = Opt::from_args();
+
+    let game_file = File::open(&opt.input_file).unwrap();
+    let mut game_file_reader = BufReader::new(game_file);
+    let message_reader = capnp::serialize::read_message(
+        &mut game_file_reader,
+        capnp::message::ReaderOptions {
+            traversal_limit_in_words: 8 * 1024 * 1024 * 1024,
+            nesting_limit: 64,
+        },
+    )
+    .unwrap();
+
+    let game_reader = message_reader
+        .get_root::<game_capnp::game::Reader>()
+        .unwrap();
+
+    match ExtensiveFormGame::deserialize(&game_reader) {
+        Ok(ref game) => {
+            if game.is_zero_sum() {
+                warn!("Game is zero-sum. The SEFCE coincides with the Nash equilibrium");
+            }
+
+            println!("Num infosets: {:?}, {:?}", game.treeplex(Player::Player1).num_infosets(), game.treeplex(Player::Player2).num_infosets());
+            println!("Num sequences: {:?}, {:?}", game.treeplex(Player::Player1).num_sequences(), game.treeplex(Player::Player2).num_sequences());
+
+            if let Some(model_file) = &opt.model_file {
+                info!("Writing model to {:?}", model_file);
+                SefceModel::new(game)
+                    .model()
+                    .write_to_file(model_file)
+                    .unwrap();
+            }
+
+            let solver_config = SolverConfig {
+                time_limit: opt.time_limit,
+            };
+
+            let sol = match opt.solver {
+                SolverType::Gurobi => run_lp_solver(game, &solver_config),
+                SolverType::Simplex => run_solver::<SimplexSolver>(game, &solver_config),
+            };
+
+            println!("SEFCE leader value: {:?}", sol.leader_value);
+            println!("SEFCE follower value: {:?}", sol.follower_value);
+
+            println!("Saving SEFCE marginal strategies");
+            let mut file_writer = File::create("sefce-leader-marginal.vec").unwrap();
+            sol.leader_marginal.inner().persist(&mut file_writer).unwrap();
+            let mut file_writer = File::create("sefce-follower-marginal.vec").unwrap();
+            sol.follower_marginal.inner().persist(&mut file_writer).unwrap();
+
+            println!("Saving SEFCE correlation plan");
+            let mut file_writer = File::create("sefce-correlation-plan.txt").unwrap();
+            for ((seq_pl1, seq_pl2), prob) in sol.correlation_plan.iter() {
+                writeln!(file_writer, "{} {} {}", seq_pl1, seq_pl2, prob).unwrap();
+            }
+        }
+        Err(err) => {
+            println!("{}", err);
+        }
+    }
+}
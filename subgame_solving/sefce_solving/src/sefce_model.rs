@@ -0,0 +1,512 @@
+use crate::sefce_solution::SefceSolution;
+
+use efg_lite::game::{EFGTools, ExtensiveFormGame, Player};
+use efg_lite::strategy::SequenceFormStrategy;
+use efg_lite::treeplex::{SequenceId, TreeplexTools};
+use efg_lite::vector::TreeplexVector;
+
+use embedded_solvers::{ConstraintSense, Model, ObjectiveSense, VariableType, INFINITY};
+
+use std::collections::{BTreeSet, HashMap};
+
+/// ===============================================================================================
+/// LP for the Stackelberg Extensive-Form Correlated Equilibrium (SEFCE), i.e., the extensive-form
+/// correlated equilibrium of von Stengel and Forges maximizing the leader's payoff, where only the
+/// follower is subject to incentive constraints. Every SSE is feasible, so the leader's value is
+/// an upper bound on the leader's SSE value.
+///
+/// Variables are the correlation plan p(s1, s2) over *relevant* pairs of sequences, i.e., pairs
+/// where either sequence is empty, or the infosets the sequences belong to are connected (some
+/// leaf lies below both). Connected infosets are found from the sequences of each leaf.
+///
+/// max  sum_z u1(z) c(z) p(s1(z), s2(z))
+/// p(empty, empty) = 1, -------------------------------------------------------------(A)
+/// p(parent(I1), s2) = sum_{a in I1} p(a, s2) for relevant (I1, s2), ------------------(B)
+/// p(s1, parent(I2)) = sum_{b in I2} p(s1, b) for relevant (s1, I2), ------------------(C)
+/// v(J, s2) >= sum_{z at t} u2(z) c(z) p(s1(z), s2) + sum_{K after t} v(K, s2)
+///     for all follower sequences s2, infosets J at or below I2 = infoset(s2), t in J, ------(D)
+/// sum_{z at or below s2} u2(z) c(z) p(s1(z), s2(z)) >= v(I2, s2) for all s2 != empty, ----(E)
+/// p >= 0,
+///
+/// where v(J, s2) is the value of the follower's best response at J, had it been recommended
+/// s2 and deviated at I2, in which case it receives no further recommendations. (D) makes v an
+/// upper bound on the value of deviating, and (E) says that following recommendations is at
+/// least as good as deviating.
+/// ===============================================================================================
+/// The numbering we will use for variables is
+///
+/// Correlation plan over relevant pairs: [0,..., |P|)
+/// Deviation values, for each follower sequence s2 and infoset J at or below infoset(s2):
+///     [|P|,..., |P| + |V|)
+/// ================================================================================================
+pub struct SefceModel<'a> {
+    game: &'a ExtensiveFormGame,
+    efg_tools: EFGTools,
+    treeplex_tools_pl1: TreeplexTools,
+    treeplex_tools_pl2: TreeplexTools,
+
+    // Pairs of infosets (leader, follower) which are connected.
+    connected_infosets: Vec<(usize, usize)>,
+
+    // Relevant pairs of sequences, in the order of their variables.
+    pairs: Vec<(SequenceId, SequenceId)>,
+    pair_indices: HashMap<(SequenceId, SequenceId), usize>,
+
+    // Deviation value variables, keyed by (follower infoset, recommended follower sequence).
+    deviation_indices: HashMap<(usize, SequenceId), usize>,
+
+    model: Model,
+}
+
+impl<'a> SefceModel<'a> {
+    pub fn new(game: &'a ExtensiveFormGame) -> SefceModel<'a> {
+        let mut sefce_model = SefceModel {
+            game,
+            efg_tools: EFGTools::new(game),
+            treeplex_tools_pl1: TreeplexTools::new(game.treeplex(Player::Player1)),
+            treeplex_tools_pl2: TreeplexTools::new(game.treeplex(Player::Player2)),
+            connected_infosets: vec![],
+            pairs: vec![],
+            pair_indices: HashMap::new(),
+            deviation_indices: HashMap::new(),
+            model: Model::new("sefce-model", ObjectiveSense::Maximize),
+        };
+
+        println!("Finding relevant sequence pairs");
+        sefce_model.connected_infosets = sefce_model.find_connected_infosets();
+        sefce_model.make_correlation_plan_variables();
+        println!("Num relevant sequence pairs: {:?}", sefce_model.pairs.len());
+
+        println!("Making deviation value variables");
+        sefce_model.make_deviation_variables();
+
+        println!("Making consistency constraints (A, B, C)");
+        sefce_model.make_consistency_constraints();
+
+        println!("Making deviation constraints (D)");
+        sefce_model.make_deviation_constraints();
+
+        println!("Making incentive constraints (E)");
+        sefce_model.make_incentive_constraints();
+
+        sefce_model
+    }
+
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+
+    /// Index of the correlation plan variable of a relevant pair of sequences.
+    pub fn get_pair_index(&self, sequence_pl1: SequenceId, sequence_pl2: SequenceId) -> usize {
+        self.pair_indices[&(sequence_pl1, sequence_pl2)]
+    }
+
+    pub fn get_deviation_index(&self, infoset_id: usize, sequence_pl2: SequenceId) -> usize {
+        self.deviation_indices[&(infoset_id, sequence_pl2)]
+    }
+
+    /// Extracts the correlation plan, the marginal strategies of both players and the payoffs
+    /// of both players from the values of all variables in a solution.
+    pub fn solution(&self, values: &[f64], objective_value: f64) -> SefceSolution {
+        let marginal = |player: Player| {
+            let treeplex = self.game.treeplex(player);
+            let marginal = (0..treeplex.num_sequences())
+                .map(|sequence_id| match player {
+                    Player::Player1 => {
+                        let empty_sequence_pl2 = self.empty_sequence_id(Player::Player2);
+                        values[self.get_pair_index(sequence_id, empty_sequence_pl2)]
+                    }
+                    Player::Player2 => {
+                        let empty_sequence_pl1 = self.empty_sequence_id(Player::Player1);
+                        values[self.get_pair_index(empty_sequence_pl1, sequence_id)]
+                    }
+                })
+                .collect::<Vec<f64>>();
+            SequenceFormStrategy::from_treeplex_vector(TreeplexVector::from_vec(treeplex, marginal))
+        };
+
+        let follower_value = self
+            .game
+            .payoff_matrix()
+            .entries
+            .iter()
+            .map(|leaf| {
+                leaf.payoff_pl2
+                    * leaf.chance_factor
+                    * values[self.get_pair_index(leaf.seq_pl1, leaf.seq_pl2)]
+            })
+            .sum();
+
+        SefceSolution::new(
+            self.pairs
+                .iter()
+                .zip(values.iter())
+                .map(|(pair, value)| (*pair, *value))
+                .collect(),
+            marginal(Player::Player1),
+            marginal(Player::Player2),
+            objective_value,
+            follower_value,
+        )
+    }
+
+    fn empty_sequence_id(&self, player: Player) -> SequenceId {
+        self.game.treeplex(player).empty_sequence_id()
+    }
+
+    fn treeplex_tools(&self, player: Player) -> &TreeplexTools {
+        match player {
+            Player::Player1 => &self.treeplex_tools_pl1,
+            Player::Player2 => &self.treeplex_tools_pl2,
+        }
+    }
+
+    /// Infosets on the path from the root to a sequence, starting from the sequence's infoset.
+    fn infoset_path(&self, player: Player, mut sequence_id: SequenceId) -> Vec<usize> {
+        let mut path = vec![];
+        while let Some(infoset_id) = self
+            .treeplex_tools(player)
+            .parent_infoset_of_seq(sequence_id)
+        {
+            path.push(infoset_id);
+            sequence_id = self.game.treeplex(player).infosets()[infoset_id].parent_sequence;
+        }
+        path
+    }
+
+    /// Pairs of (leader, follower) infosets which both lie on the path to some leaf, sorted.
+    fn find_connected_infosets(&self) -> Vec<(usize, usize)> {
+        let leaf_sequences = self
+            .game
+            .payoff_matrix()
+            .entries
+            .iter()
+            .map(|leaf| (leaf.seq_pl1, leaf.seq_pl2))
+            .collect::<BTreeSet<(SequenceId, SequenceId)>>();
+
+        let mut connected_infosets = BTreeSet::<(usize, usize)>::new();
+        for (sequence_pl1, sequence_pl2) in leaf_sequences.into_iter() {
+            let path_pl2 = self.infoset_path(Player::Player2, sequence_pl2);
+            for infoset_pl1 in self.infoset_path(Player::Player1, sequence_pl1) {
+                for infoset_pl2 in path_pl2.iter() {
+                    connected_infosets.insert((infoset_pl1, *infoset_pl2));
+                }
+            }
+        }
+        connected_infosets.into_iter().collect()
+    }
+
+    fn add_pair(&mut self, sequence_pl1: SequenceId, sequence_pl2: SequenceId) {
+        self.model.add_variable(
+            &format!("correlation_plan_{}_{}", sequence_pl1, sequence_pl2),
+            VariableType::Continuous,
+            0f64, // Objective is filled in once all pairs are known.
+            0f64, // Probabilities must be >= 0
+            1f64, // Probabilities may not be more than 1 (not really required in theory).
+        );
+        self.pair_indices
+            .insert((sequence_pl1, sequence_pl2), self.pairs.len());
+        self.pairs.push((sequence_pl1, sequence_pl2));
+    }
+
+    fn make_correlation_plan_variables(&mut self) {
+        let empty_sequence_pl1 = self.empty_sequence_id(Player::Player1);
+        let empty_sequence_pl2 = self.empty_sequence_id(Player::Player2);
+
+        // Pairs involving an empty sequence are always relevant.
+        for sequence_pl1 in 0..self.game.treeplex(Player::Player1).num_sequences() {
+            self.add_pair(sequence_pl1, empty_sequence_pl2);
+        }
+        for sequence_pl2 in 0..self.game.treeplex(Player::Player2).num_sequences() {
+            if sequence_pl2 != empty_sequence_pl2 {
+                self.add_pair(empty_sequence_pl1, sequence_pl2);
+            }
+        }
+
+        for (infoset_pl1, infoset_pl2) in self.connected_infosets.clone().into_iter() {
+            let infoset_pl1 = self.game.treeplex(Player::Player1).infosets()[infoset_pl1];
+            let infoset_pl2 = self.game.treeplex(Player::Player2).infosets()[infoset_pl2];
+            for sequence_pl1 in infoset_pl1.start_sequence..=infoset_pl1.end_sequence {
+                for sequence_pl2 in infoset_pl2.start_sequence..=infoset_pl2.end_sequence {
+                    self.add_pair(sequence_pl1, sequence_pl2);
+                }
+            }
+        }
+
+        // Objective, aggregating leaves with the same pair of sequences.
+        let mut objective_coeffs = vec![0f64; self.pairs.len()];
+        for leaf in self.game.payoff_matrix().entries.iter() {
+            objective_coeffs[self.get_pair_index(leaf.seq_pl1, leaf.seq_pl2)] +=
+                leaf.payoff_pl1 * leaf.chance_factor;
+        }
+        for (pair_index, objective_coeff) in objective_coeffs.into_iter().enumerate() {
+            self.model.set_objective_coeff(pair_index, objective_coeff);
+        }
+    }
+
+    /// Follower infosets at or below an infoset (inclusive).
+    fn infosets_at_or_below(&self, infoset_id: usize) -> Vec<usize> {
+        let treeplex = self.game.treeplex(Player::Player2);
+        let mut infosets = vec![];
+        let mut stack = vec![infoset_id];
+        while let Some(infoset_id) = stack.pop() {
+            infosets.push(infoset_id);
+            let infoset = treeplex.infosets()[infoset_id];
+            for sequence_id in infoset.start_sequence..=infoset.end_sequence {
+                stack.extend(self.treeplex_tools_pl2.seq_to_infoset_range(sequence_id));
+            }
+        }
+        infosets
+    }
+
+    fn make_deviation_variables(&mut self) {
+        for sequence_pl2 in 0..self.game.treeplex(Player::Player2).num_sequences() {
+            let infoset_pl2 = match self.treeplex_tools_pl2.parent_infoset_of_seq(sequence_pl2) {
+                Some(infoset_pl2) => infoset_pl2,
+                None => continue, // The empty sequence is never recommended.
+            };
+            for infoset_id in self.infosets_at_or_below(infoset_pl2) {
+                let index = self.model.add_variable(
+                    &format!("deviation_value_{}_{}", infoset_id, sequence_pl2),
+                    VariableType::Continuous,
+                    0f64,
+                    -INFINITY, // Values can range from -INF to INF
+                    INFINITY,
+                );
+                self.deviation_indices
+                    .insert((infoset_id, sequence_pl2), index);
+            }
+        }
+    }
+
+    /// Constraints (A), (B) and (C).
+    fn make_consistency_constraints(&mut self) {
+        let treeplex_pl1 = self.game.treeplex(Player::Player1);
+        let treeplex_pl2 = self.game.treeplex(Player::Player2);
+        let empty_sequence_pl1 = self.empty_sequence_id(Player::Player1);
+        let empty_sequence_pl2 = self.empty_sequence_id(Player::Player2);
+
+        self.model.add_constraint(
+            "correlation_plan_empty_pair",
+            vec![(
+                self.get_pair_index(empty_sequence_pl1, empty_sequence_pl2),
+                1.0,
+            )],
+            ConstraintSense::Equal,
+            1f64,
+        );
+
+        // Sequences of the other player relevant to each infoset.
+        let mut relevant_pl2 = vec![vec![empty_sequence_pl2]; treeplex_pl1.num_infosets()];
+        let mut relevant_pl1 = vec![vec![empty_sequence_pl1]; treeplex_pl2.num_infosets()];
+        for (infoset_pl1, infoset_pl2) in self.connected_infosets.iter() {
+            let infoset = treeplex_pl2.infosets()[*infoset_pl2];
+            relevant_pl2[*infoset_pl1].extend(infoset.start_sequence..=infoset.end_sequence);
+            let infoset = treeplex_pl1.infosets()[*infoset_pl1];
+            relevant_pl1[*infoset_pl2].extend(infoset.start_sequence..=infoset.end_sequence);
+        }
+
+        // (B)
+        for (infoset_id, infoset) in treeplex_pl1.infosets().iter().enumerate() {
+            for sequence_pl2 in relevant_pl2[infoset_id].iter() {
+                let mut coeffs = vec![(
+                    self.get_pair_index(infoset.parent_sequence, *sequence_pl2),
+                    1.0,
+                )];
+                for sequence_pl1 in infoset.start_sequence..=infoset.end_sequence {
+                    coeffs.push((self.get_pair_index(sequence_pl1, *sequence_pl2), -1.0));
+                }
+                self.model.add_constraint(
+                    &format!("leader_consistency_{}_{}", infoset_id, sequence_pl2),
+                    coeffs,
+                    ConstraintSense::Equal,
+                    0f64,
+                );
+            }
+        }
+
+        // (C)
+        for (infoset_id, infoset) in treeplex_pl2.infosets().iter().enumerate() {
+            for sequence_pl1 in relevant_pl1[infoset_id].iter() {
+                let mut coeffs = vec![(
+                    self.get_pair_index(*sequence_pl1, infoset.parent_sequence),
+                    1.0,
+                )];
+                for sequence_pl2 in infoset.start_sequence..=infoset.end_sequence {
+                    coeffs.push((self.get_pair_index(*sequence_pl1, sequence_pl2), -1.0));
+                }
+                self.model.add_constraint(
+                    &format!("follower_consistency_{}_{}", sequence_pl1, infoset_id),
+                    coeffs,
+                    ConstraintSense::Equal,
+                    0f64,
+                );
+            }
+        }
+    }
+
+    /// Terms u2(z) c(z) p(s1(z), s2) for leaves z at a follower sequence, where the follower
+    /// sequence of the pair is `recommended` instead of that of the leaf.
+    fn follower_payoff_terms(
+        &self,
+        sequence_pl2: SequenceId,
+        recommended: SequenceId,
+        coeff: f64,
+    ) -> Vec<(usize, f64)> {
+        self.efg_tools
+            .leaf_indices_at_sequence(Player::Player2, sequence_pl2)
+            .into_iter()
+            .map(|leaf_index| {
+                let leaf = self.game.payoff_matrix().entries[leaf_index];
+                (
+                    self.get_pair_index(leaf.seq_pl1, recommended),
+                    coeff * leaf.payoff_pl2 * leaf.chance_factor,
+                )
+            })
+            .collect()
+    }
+
+    /// Constraints (D).
+    fn make_deviation_constraints(&mut self) {
+        let treeplex = self.game.treeplex(Player::Player2);
+        for sequence_pl2 in 0..treeplex.num_sequences() {
+            let infoset_pl2 = match self.treeplex_tools_pl2.parent_infoset_of_seq(sequence_pl2) {
+                Some(infoset_pl2) => infoset_pl2,
+                None => continue,
+            };
+            for infoset_id in self.infosets_at_or_below(infoset_pl2) {
+                let infoset = treeplex.infosets()[infoset_id];
+                for sequence_id in infoset.start_sequence..=infoset.end_sequence {
+                    let mut coeffs =
+                        vec![(self.get_deviation_index(infoset_id, sequence_pl2), 1.0)];
+                    for child_infoset in self.treeplex_tools_pl2.seq_to_infoset_range(sequence_id) {
+                        coeffs.push((self.get_deviation_index(child_infoset, sequence_pl2), -1.0));
+                    }
+                    coeffs.extend(self.follower_payoff_terms(sequence_id, sequence_pl2, -1.0));
+                    self.model.add_constraint(
+                        &format!("deviation_constraints_{}_{}", sequence_id, sequence_pl2),
+                        merge_coeffs(coeffs),
+                        ConstraintSense::GreaterEqual,
+                        0f64,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Constraints (E).
+    fn make_incentive_constraints(&mut self) {
+        for sequence_pl2 in 0..self.game.treeplex(Player::Player2).num_sequences() {
+            let infoset_pl2 = match self.treeplex_tools_pl2.parent_infoset_of_seq(sequence_pl2) {
+                Some(infoset_pl2) => infoset_pl2,
+                None => continue,
+            };
+            let mut coeffs = vec![(self.get_deviation_index(infoset_pl2, sequence_pl2), -1.0)];
+            let (start_sequence, end_sequence) =
+                self.treeplex_tools_pl2.seqs_under_seq(sequence_pl2);
+            for sequence_id in start_sequence..=end_sequence {
+                coeffs.extend(self.follower_payoff_terms(sequence_id, sequence_id, 1.0));
+            }
+            self.model.add_constraint(
+                &format!("incentive_constraints_{}", sequence_pl2),
+                merge_coeffs(coeffs),
+                ConstraintSense::GreaterEqual,
+                0f64,
+            );
+        }
+    }
+}
+
+/// Sums up coefficients of the same variable, since several leaves may share a pair of sequences.
+fn merge_coeffs(mut coeffs: Vec<(usize, f64)>) -> Vec<(usize, f64)> {
+    coeffs.sort_by_key(|(index, _)| *index);
+    let mut merged = Vec::<(usize, f64)>::with_capacity(coeffs.len());
+    for (index, coeff) in coeffs.into_iter() {
+        match merged.last_mut() {
+            Some((last_index, last_coeff)) if *last_index == index => *last_coeff += coeff,
+            _ => merged.push((index, coeff)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+pub mod test_fixtures {
+    use super::SefceModel;
+    use efg_lite::fixtures::commitment_game;
+    use efg_lite::game::{ExtensiveFormGame, Infoset, PayoffMatrix, PayoffMatrixEntry, Player};
+    use efg_lite::treeplex::Treeplex;
+
+    use assert_approx_eq::assert_approx_eq;
+    use std::sync::Arc;
+
+    /// The follower first chooses between L and R, which the leader observes. After L, the
+    /// commitment game is played. After R, only the leader moves, choosing between U, worth
+    /// (1, 1), and D, worth (0, 0.6).
+    /// Leader sequences are Up and Down (0, 1) and U and D (2, 3).
+    /// Follower sequences are Left and Right (0, 1) and L and R (2, 3).
+    fn two_branch_game() -> ExtensiveFormGame {
+        let treeplex_pl1 = Treeplex::new(
+            Player::Player1,
+            5,
+            vec![Infoset::new(4, 0, 1), Infoset::new(4, 2, 3)].into_boxed_slice(),
+        );
+        let treeplex_pl2 = Treeplex::new(
+            Player::Player2,
+            5,
+            vec![Infoset::new(2, 0, 1), Infoset::new(4, 2, 3)].into_boxed_slice(),
+        );
+        let payoff_matrix = PayoffMatrix::new(vec![
+            PayoffMatrixEntry::new(0, 0, 1.0, 2.0, 1.0),
+            PayoffMatrixEntry::new(0, 1, 1.0, 4.0, 0.0),
+            PayoffMatrixEntry::new(1, 0, 1.0, 1.0, 0.0),
+            PayoffMatrixEntry::new(1, 1, 1.0, 3.0, 1.0),
+            PayoffMatrixEntry::new(2, 3, 1.0, 1.0, 1.0),
+            PayoffMatrixEntry::new(3, 3, 1.0, 0.0, 0.6),
+        ]);
+        ExtensiveFormGame::new(
+            Arc::new(treeplex_pl1),
+            Arc::new(treeplex_pl2),
+            payoff_matrix,
+            vec![],
+            vec![],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn sefce_of_commitment_game() {
+        // In normal form games, correlation does not help the leader, so the SEFCE coincides
+        // with the SSE.
+        let game = commitment_game();
+        let sefce_model = SefceModel::new(&game);
+        assert_eq!(sefce_model.pairs.len(), 9);
+
+        let lp_solution = sefce_model.model().solve();
+        let solution = sefce_model.solution(&lp_solution.values, lp_solution.objective_value);
+        assert_approx_eq!(solution.leader_value, 3.5);
+        assert_approx_eq!(solution.follower_value, 0.5);
+        assert_approx_eq!(solution.leader_marginal.inner()[0], 0.5);
+        assert_approx_eq!(solution.follower_marginal.inner()[1], 1.0);
+    }
+
+    #[test]
+    fn sefce_of_two_branch_game() {
+        let game = two_branch_game();
+        let sefce_model = SefceModel::new(&game);
+        // U and D are not relevant to Left and Right, since the leader only moves after R.
+        assert_eq!(sefce_model.pairs.len(), 21);
+        assert!(!sefce_model.pair_indices.contains_key(&(2, 0)));
+
+        // If recommended L, the follower may deviate to R, where the leader is then recommended
+        // D. The follower must still get at least 0.6 after L, so that the leader can only get
+        // 3.4 in the commitment game instead of 3.5.
+        let lp_solution = sefce_model.model().solve();
+        let solution = sefce_model.solution(&lp_solution.values, lp_solution.objective_value);
+        assert_approx_eq!(solution.leader_value, 3.4);
+        assert_approx_eq!(solution.follower_value, 0.6);
+        assert_approx_eq!(solution.follower_marginal.inner()[2], 1.0);
+        assert_approx_eq!(lp_solution.values[sefce_model.get_pair_index(3, 2)], 1.0);
+        assert_approx_eq!(lp_solution.values[sefce_model.get_pair_index(0, 1)], 0.4);
+    }
+}
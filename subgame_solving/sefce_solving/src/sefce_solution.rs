@@ -0,0 +1,29 @@
+use efg_lite::strategy::SequenceFormStrategy;
+use efg_lite::treeplex::SequenceId;
+
+pub struct SefceSolution {
+    // Probability of each relevant pair of sequences (pl1, pl2) being recommended.
+    pub correlation_plan: Vec<((SequenceId, SequenceId), f64)>,
+    pub leader_marginal: SequenceFormStrategy,
+    pub follower_marginal: SequenceFormStrategy,
+    pub leader_value: f64,
+    pub follower_value: f64,
+}
+
+impl SefceSolution {
+    pub fn new(
+        correlation_plan: Vec<((SequenceId, SequenceId), f64)>,
+        leader_marginal: SequenceFormStrategy,
+        follower_marginal: SequenceFormStrategy,
+        leader_value: f64,
+        follower_value: f64,
+    ) -> SefceSolution {
+        SefceSolution {
+            correlation_plan,
+            leader_marginal,
+            follower_marginal,
+            leader_value,
+            follower_value,
+        }
+    }
+}
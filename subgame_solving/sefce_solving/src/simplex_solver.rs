@@ -0,0 +1,52 @@
+use crate::sefce_model::SefceModel;
+use crate::sefce_solution::SefceSolution;
+use crate::solver::{Solver, SolverConfig};
+use efg_lite::game::ExtensiveFormGame;
+
+use embedded_solvers::{LpSolution, LpStatus, SimplexOptions};
+
+use std::cell::RefCell;
+
+/// Solves the LP built by `SefceModel` using the embedded simplex solver.
+pub struct SimplexSolver<'a> {
+    sefce_model: SefceModel<'a>,
+    time_limit: f64,
+    lp_solution: RefCell<Option<LpSolution>>,
+}
+
+impl<'a> Solver<'a> for SimplexSolver<'a> {
+    fn new(game: &'a ExtensiveFormGame, solver_config: &'a SolverConfig) -> SimplexSolver<'a> {
+        SimplexSolver {
+            sefce_model: SefceModel::new(game),
+            time_limit: solver_config.time_limit,
+            lp_solution: RefCell::new(None),
+        }
+    }
+
+    fn solve(&self) {
+        let options = SimplexOptions {
+            time_limit: self.time_limit,
+            ..SimplexOptions::default()
+        };
+        let lp_solution = self.sefce_model.model().solve_with_options(&options);
+        // Any SSE gives a feasible correlation plan, and all variables in the objective are
+        // bounded, so the LP is always solved to optimality unless we run out of time.
+        assert_ne!(
+            lp_solution.status,
+            LpStatus::TimeLimit,
+            "Time limit reached before the SEFCE LP was solved"
+        );
+        assert_eq!(lp_solution.status, LpStatus::Optimal);
+        *self.lp_solution.borrow_mut() = Some(lp_solution);
+    }
+
+    fn get_solution(&self) -> SefceSolution {
+        let lp_solution = self.lp_solution.borrow();
+        let lp_solution = lp_solution
+            .as_ref()
+            .expect("get_solution() called before solve()");
+
+        self.sefce_model
+            .solution(&lp_solution.values, lp_solution.objective_value)
+    }
+}
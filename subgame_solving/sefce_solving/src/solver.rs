@@ -0,0 +1,12 @@
+use crate::sefce_solution::SefceSolution;
+use efg_lite::game::ExtensiveFormGame;
+
+pub trait Solver<'a> {
+    fn new(game: &'a ExtensiveFormGame, solver_config: &'a SolverConfig) -> Self;
+    fn solve(&self);
+    fn get_solution(&self) -> SefceSolution;
+}
+
+pub struct SolverConfig {
+    pub time_limit: f64,
+}
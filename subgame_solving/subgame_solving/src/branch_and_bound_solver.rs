@@ -0,0 +1,486 @@
+use crate::mip_solution::MIPSolution;
+use crate::solver::{SolveStatus, Solver, SolverConfig};
+use crate::sse_model::SseModel;
+
+use efg_lite::game::Player;
+use efg_lite::sse::BoundedProblem;
+use efg_lite::treeplex::SequenceId;
+
+use embedded_solvers::{LpSolution, LpStatus};
+
+use log::{debug, info, warn};
+
+use std::cell::{Cell, RefCell};
+use std::time::Instant;
+
+/// Follower sequence variables within this distance of 0 or 1 are considered integral.
+const INTEGRALITY_TOLERANCE: f64 = 1e-6;
+
+/// Nodes whose LP bound does not exceed the incumbent by more than this are pruned.
+const PRUNING_TOLERANCE: f64 = 1e-9;
+
+/// How to pick the variables to branch on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BranchingRule {
+    /// Branch on the topmost follower infoset with a fractional strategy, creating one
+    /// child per action.
+    InfosetTopDown,
+
+    /// Branch on the follower sequence whose value is closest to 0.5.
+    MostFractional,
+}
+
+/// A node in the branch-and-bound tree, given by the bounds it imposes on the
+/// follower's sequence form variables on top of the LP relaxation.
+#[derive(Debug, Clone)]
+struct Node {
+    fixings: Vec<(usize, f64, f64)>,
+}
+
+/// Feasible solution found so far.
+struct Incumbent {
+    objective_value: f64,
+    values: Vec<f64>,
+}
+
+/// Branch-and-bound solver for the MILP built by `SseModel`, requiring no
+/// native libraries.
+///
+/// The only integer variables are the follower's sequence form variables, so
+/// each node solves the LP relaxation with the embedded simplex solver. With
+/// `BranchingRule::InfosetTopDown`, we pick the topmost follower infoset which is
+/// reached with a fractional strategy and create one child per action, in which
+/// all other actions are forbidden (in the style of SOS1 branching). Since the
+/// follower's strategy is pure, this covers all feasible solutions, while fixing
+/// entire subtrees of the follower's treeplex at once. `BranchingRule::MostFractional`
+/// is the usual 0/1 branching on a single variable.
+///
+/// Indicator and SOS1 encodings of the on-off constraints are dropped from the
+/// relaxation until the follower's variables are fixed, giving weaker bounds than big M.
+/// Nodes with a pure follower strategy are then branched on further by fixing the
+/// sequences the follower plays, until the relaxation is exact.
+///
+/// Nodes are explored depth-first, which finds incumbents quickly and keeps
+/// memory usage low. Only suitable for small and medium sized subgames.
+pub struct BranchAndBoundSolver<'a> {
+    sse_model: SseModel<'a>,
+    time_limit: f64,
+    branching_rule: BranchingRule,
+    feasible_follower: RefCell<Option<Vec<f64>>>,
+    incumbent: RefCell<Option<Incumbent>>,
+
+    // Best bound on the objective of nodes left open, when the time limit was reached or their
+    // LP could not be solved.
+    open_bound: Cell<f64>,
+    status: Cell<SolveStatus>,
+}
+
+impl<'a> BranchAndBoundSolver<'a> {
+    /// Uses the given follower strategy to obtain an initial incumbent, by optimizing
+    /// the leader's strategy with the follower's pure strategy held fixed. The leader's
+    /// strategy is not required, since it is recovered by the LP.
+    pub fn set_feasible_strategies(
+        &self,
+        _feasible_leader: &[f64],
+        feasible_follower: &[f64],
+    ) {
+        *self.feasible_follower.borrow_mut() = Some(feasible_follower.to_vec());
+    }
+
+    /// Node in which the follower's sequence form variables are fixed to the given pure
+    /// strategy, after rounding.
+    fn pure_follower_node(&self, follower: &[f64]) -> Node {
+        let fixings = follower
+            .iter()
+            .enumerate()
+            .map(|(sequence_id, value)| {
+                let value = value.round();
+                (self.sse_model.get_seq_form_index_pl2(sequence_id), value, value)
+            })
+            .collect::<Vec<(usize, f64, f64)>>();
+        Node { fixings }
+    }
+
+    fn solve_node(&self, node: &Node) -> LpSolution {
+        let mut model = self.sse_model.model().clone();
+        for (variable, lower_bound, upper_bound) in node.fixings.iter() {
+            model.set_bounds(*variable, *lower_bound, *upper_bound);
+        }
+        model.solve_relaxation()
+    }
+
+    fn num_follower_sequences(&self) -> usize {
+        self.sse_model.problem().game.treeplex(Player::Player2).num_sequences()
+    }
+
+    fn is_fractional(value: f64) -> bool {
+        value > INTEGRALITY_TOLERANCE && value < 1.0 - INTEGRALITY_TOLERANCE
+    }
+
+    /// Children of a node whose LP solution has fractional follower variables, or
+    /// None if the follower's strategy is already pure. Children are returned in the
+    /// order in which they should be explored.
+    fn branch(&self, node: &Node, values: &[f64]) -> Option<Vec<Node>> {
+        let treeplex = self.sse_model.problem().game.treeplex(Player::Player2);
+        let follower_value =
+            |sequence_id: SequenceId| values[self.sse_model.get_seq_form_index_pl2(sequence_id)];
+
+        match self.branching_rule {
+            BranchingRule::InfosetTopDown => {
+                // Infosets are ordered bottom-up, so the last fractional infoset is topmost.
+                let infoset = treeplex.infosets().iter().rev().find(|infoset| {
+                    (infoset.start_sequence..=infoset.end_sequence)
+                        .any(|sequence_id| Self::is_fractional(follower_value(sequence_id)))
+                })?;
+
+                let mut actions = (infoset.start_sequence..=infoset.end_sequence)
+                    .collect::<Vec<SequenceId>>();
+                // Explore the most likely action first.
+                actions.sort_by(|a, b| {
+                    follower_value(*a)
+                        .partial_cmp(&follower_value(*b))
+                        .unwrap()
+                        .reverse()
+                });
+
+                let children = actions
+                    .iter()
+                    .map(|chosen_sequence| {
+                        let mut fixings = node.fixings.clone();
+                        for sequence_id in infoset.start_sequence..=infoset.end_sequence {
+                            if sequence_id != *chosen_sequence {
+                                fixings.push((self.sse_model.get_seq_form_index_pl2(sequence_id), 0.0, 0.0));
+                            }
+                        }
+                        Node { fixings }
+                    })
+                    .collect::<Vec<Node>>();
+                Some(children)
+            }
+            BranchingRule::MostFractional => {
+                let sequence_id = (0..treeplex.num_sequences())
+                    .filter(|sequence_id| Self::is_fractional(follower_value(*sequence_id)))
+                    .min_by(|a, b| {
+                        (follower_value(*a) - 0.5)
+                            .abs()
+                            .partial_cmp(&(follower_value(*b) - 0.5).abs())
+                            .unwrap()
+                    })?;
+
+                let variable = self.sse_model.get_seq_form_index_pl2(sequence_id);
+                let mut children = [(1.0, 1.0), (0.0, 0.0)]
+                    .iter()
+                    .map(|(lower_bound, upper_bound)| {
+                        let mut fixings = node.fixings.clone();
+                        fixings.push((variable, *lower_bound, *upper_bound));
+                        Node { fixings }
+                    })
+                    .collect::<Vec<Node>>();
+                if follower_value(sequence_id) < 0.5 {
+                    children.reverse();
+                }
+                Some(children)
+            }
+        }
+    }
+
+    /// Children of a node whose LP solution has a pure follower strategy, which fix a
+    /// sequence played by the follower (but not yet fixed) to 1 and 0 respectively. Once
+    /// all played sequences are fixed, the relaxation enforces all on-off constraints
+    /// which matter, and None is returned.
+    fn branch_on_played_sequence(&self, node: &Node, follower: &[f64]) -> Option<Vec<Node>> {
+        let variable = (0..follower.len())
+            .filter(|sequence_id| follower[*sequence_id] > 0.5)
+            .map(|sequence_id| self.sse_model.get_seq_form_index_pl2(sequence_id))
+            .find(|variable| {
+                !node
+                    .fixings
+                    .iter()
+                    .any(|(x, lower_bound, _)| x == variable && *lower_bound == 1.0)
+            })?;
+        let children = [(1.0, 1.0), (0.0, 0.0)]
+            .iter()
+            .map(|(lower_bound, upper_bound)| {
+                let mut fixings = node.fixings.clone();
+                fixings.push((variable, *lower_bound, *upper_bound));
+                Node { fixings }
+            })
+            .collect::<Vec<Node>>();
+        Some(children)
+    }
+
+    fn update_incumbent(&self, lp_solution: LpSolution) -> bool {
+        let mut incumbent = self.incumbent.borrow_mut();
+        let is_better = match incumbent.as_ref() {
+            None => true,
+            Some(x) => lp_solution.objective_value > x.objective_value + PRUNING_TOLERANCE,
+        };
+        if is_better {
+            *incumbent = Some(Incumbent {
+                objective_value: lp_solution.objective_value,
+                values: lp_solution.values,
+            });
+        }
+        is_better
+    }
+
+    fn incumbent_value(&self) -> f64 {
+        match self.incumbent.borrow().as_ref() {
+            None => f64::NEG_INFINITY,
+            Some(x) => x.objective_value,
+        }
+    }
+}
+
+impl<'a> Solver<'a> for BranchAndBoundSolver<'a> {
+    fn new(problem: &'a BoundedProblem, solver_config: &SolverConfig) -> BranchAndBoundSolver<'a> {
+        BranchAndBoundSolver {
+            sse_model: SseModel::new(problem, solver_config.on_off_encoding),
+            time_limit: solver_config.time_limit,
+            branching_rule: solver_config.branching_rule,
+            feasible_follower: RefCell::new(None),
+            incumbent: RefCell::new(None),
+            open_bound: Cell::new(f64::NEG_INFINITY),
+            status: Cell::new(SolveStatus::Error),
+        }
+    }
+
+    fn solve(&self) -> SolveStatus {
+        let start_time = Instant::now();
+
+        // Initial incumbent from the feasible follower strategy, if any.
+        if let Some(feasible_follower) = self.feasible_follower.borrow().as_ref() {
+            let lp_solution = self.solve_node(&self.pure_follower_node(feasible_follower));
+            match lp_solution.status {
+                LpStatus::Optimal => {
+                    self.update_incumbent(lp_solution);
+                }
+                _ => warn!("Feasible follower strategy does not give a feasible solution"),
+            }
+        }
+
+        // Nodes are stacked with the LP bound of their parent.
+        let mut stack = vec![(f64::INFINITY, Node { fixings: vec![] })];
+        let mut num_nodes = 0;
+        let mut num_failed_nodes = 0;
+        let mut open_bound = f64::NEG_INFINITY;
+        let mut time_limit_reached = false;
+        while let Some((parent_bound, node)) = stack.pop() {
+            if start_time.elapsed().as_secs_f64() > self.time_limit {
+                time_limit_reached = true;
+                warn!("Time limit reached, {} open nodes remaining", stack.len());
+                open_bound = stack
+                    .iter()
+                    .fold(open_bound.max(parent_bound), |accum, (bound, _)| {
+                        accum.max(*bound)
+                    });
+                break;
+            }
+            num_nodes += 1;
+
+            // Only nodes proven to be infeasible may be pruned. Any other failure leaves the
+            // node open, so the incumbent is no longer known to be optimal.
+            let lp_solution = self.solve_node(&node);
+            match lp_solution.status {
+                LpStatus::Optimal => {}
+                LpStatus::Infeasible => continue,
+                status => {
+                    warn!("LP relaxation of a node failed with status {:?}", status);
+                    num_failed_nodes += 1;
+                    open_bound = open_bound.max(parent_bound);
+                    continue;
+                }
+            }
+            if lp_solution.objective_value <= self.incumbent_value() + PRUNING_TOLERANCE {
+                continue;
+            }
+
+            match self.branch(&node, &lp_solution.values) {
+                Some(children) => {
+                    // The stack is last in, first out.
+                    let bound = lp_solution.objective_value;
+                    stack.extend(children.into_iter().rev().map(|child| (bound, child)));
+                }
+                None if !self.sse_model.model().has_logical_constraints() => {
+                    if self.update_incumbent(lp_solution) {
+                        debug!("New incumbent {:?}", self.incumbent_value());
+                    }
+                }
+                None => {
+                    // The relaxation only enforces indicator and SOS1 constraints once the
+                    // follower's variables are fixed, so the follower's strategy gives a
+                    // feasible solution, but not necessarily the best one in this node.
+                    let follower = (0..self.num_follower_sequences())
+                        .map(|sequence_id| self.sse_model.get_seq_form_index_pl2(sequence_id))
+                        .map(|index| lp_solution.values[index])
+                        .collect::<Vec<f64>>();
+                    let fixed_solution = self.solve_node(&self.pure_follower_node(&follower));
+                    let fixed_status = fixed_solution.status;
+                    let is_exact = fixed_status == LpStatus::Optimal
+                        && fixed_solution.objective_value
+                            >= lp_solution.objective_value - PRUNING_TOLERANCE;
+                    if fixed_status == LpStatus::Optimal && self.update_incumbent(fixed_solution) {
+                        debug!("New incumbent {:?}", self.incumbent_value());
+                    }
+                    if !is_exact {
+                        let bound = lp_solution.objective_value;
+                        match self.branch_on_played_sequence(&node, &follower) {
+                            Some(children) => {
+                                stack
+                                    .extend(children.into_iter().rev().map(|child| (bound, child)));
+                            }
+                            None if fixed_status != LpStatus::Optimal
+                                && fixed_status != LpStatus::Infeasible =>
+                            {
+                                warn!(
+                                    "LP of a pure follower node failed with status {:?}",
+                                    fixed_status
+                                );
+                                num_failed_nodes += 1;
+                                open_bound = open_bound.max(bound);
+                            }
+                            None => {}
+                        }
+                    }
+                }
+            }
+        }
+
+        info!(
+            "Branch and bound explored {} nodes ({} failed) in {:?}s, objective {:?}",
+            num_nodes,
+            num_failed_nodes,
+            start_time.elapsed().as_secs_f64(),
+            self.incumbent_value()
+        );
+        self.open_bound.set(open_bound);
+
+        // Unless the time limit was reached or some node failed, the whole tree was explored.
+        let status = if self.incumbent.borrow().is_none() {
+            if time_limit_reached || num_failed_nodes > 0 {
+                SolveStatus::Error
+            } else {
+                SolveStatus::Infeasible
+            }
+        } else if time_limit_reached {
+            SolveStatus::TimeLimit
+        } else if num_failed_nodes > 0 {
+            SolveStatus::Suboptimal
+        } else {
+            SolveStatus::Optimal
+        };
+        self.status.set(status);
+        status
+    }
+
+    fn get_solution(&self) -> Result<MIPSolution, SolveStatus> {
+        let incumbent = self.incumbent.borrow();
+        let incumbent = match incumbent.as_ref() {
+            Some(incumbent) => incumbent,
+            None => return Err(self.status.get()),
+        };
+        let mut solution = self
+            .sse_model
+            .solution(&incumbent.values, incumbent.objective_value);
+        // Same definition as Gurobi's, relative to the incumbent.
+        let gap = self.open_bound.get() - incumbent.objective_value;
+        if gap > PRUNING_TOLERANCE {
+            solution.mip_gap = gap / incumbent.objective_value.abs();
+        }
+        solution.status = self.status.get();
+        Ok(solution)
+    }
+}
+
+#[cfg(test)]
+pub mod test_fixtures {
+    use super::{BranchAndBoundSolver, BranchingRule};
+    use crate::solver::{SolveStatus, Solver, SolverConfig};
+    use crate::sse_model::OnOffEncoding;
+    use efg_lite::fixtures::commitment_game;
+    use efg_lite::sse::{BoundedProblem, ValueBound};
+
+    use assert_approx_eq::assert_approx_eq;
+
+    /// Bounded problem of the entire commitment game, without any bounds. Its SSE gives the
+    /// leader a payoff of 3.5.
+    pub fn commitment_problem() -> BoundedProblem {
+        BoundedProblem::new(commitment_game(), 1.0, vec![], vec![0, 1, 2, 3])
+    }
+
+    #[test]
+    fn branch_and_bound_solves_commitment_game() {
+        let problem = commitment_problem();
+        let on_off_encodings = [OnOffEncoding::BigM, OnOffEncoding::Indicator, OnOffEncoding::Sos1];
+        for branching_rule in [BranchingRule::InfosetTopDown, BranchingRule::MostFractional].iter() {
+            for on_off_encoding in on_off_encodings.iter() {
+                let solver_config = SolverConfig {
+                    time_limit: f64::INFINITY,
+                    on_off_encoding: *on_off_encoding,
+                    branching_rule: *branching_rule,
+                    log_file: String::from("TestLog"),
+                    threads: 0,
+                };
+                let solver = BranchAndBoundSolver::new(&problem, &solver_config);
+                // Inducing Left is feasible, but suboptimal.
+                solver.set_feasible_strategies(&[1.0, 0.0, 1.0], &[1.0, 0.0, 1.0]);
+                assert_eq!(solver.solve(), SolveStatus::Optimal);
+
+                let solution = solver.get_solution().unwrap();
+                assert_approx_eq!(solution.objective_value, 3.5);
+                assert_approx_eq!(solution.leader_strategy.inner()[0], 0.5);
+                assert_approx_eq!(solution.follower_strategy.inner()[1], 1.0);
+                assert_approx_eq!(solution.follower_slack[1], 0.0);
+                assert_eq!(solution.mip_gap, 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn time_limit_keeps_feasible_solution() {
+        let problem = commitment_problem();
+        let solver_config = SolverConfig {
+            time_limit: 0.0,
+            on_off_encoding: OnOffEncoding::BigM,
+            branching_rule: BranchingRule::InfosetTopDown,
+            log_file: String::from("TestLog"),
+            threads: 0,
+        };
+        let solver = BranchAndBoundSolver::new(&problem, &solver_config);
+        solver.set_feasible_strategies(&[1.0, 0.0, 1.0], &[1.0, 0.0, 1.0]);
+        assert_eq!(solver.solve(), SolveStatus::TimeLimit);
+
+        // The root is left open, so nothing is known about the optimal objective.
+        let solution = solver.get_solution().unwrap();
+        assert_eq!(solution.status, SolveStatus::TimeLimit);
+        assert_approx_eq!(solution.objective_value, 2.0);
+        assert_eq!(solution.mip_gap, f64::INFINITY);
+
+        // Without a feasible solution to start from, there is nothing to fall back on.
+        let solver = BranchAndBoundSolver::new(&problem, &solver_config);
+        assert_eq!(solver.solve(), SolveStatus::Error);
+        assert_eq!(solver.get_solution().err(), Some(SolveStatus::Error));
+    }
+
+    #[test]
+    fn infeasible_bounds() {
+        // The follower's payoff is at most 1, so it cannot be guaranteed a value of 2.
+        let problem = BoundedProblem::new(
+            commitment_game(),
+            1.0,
+            vec![(0, ValueBound::LowerBound(2.0))],
+            vec![0, 1, 2, 3],
+        );
+        let solver_config = SolverConfig {
+            time_limit: f64::INFINITY,
+            on_off_encoding: OnOffEncoding::BigM,
+            branching_rule: BranchingRule::InfosetTopDown,
+            log_file: String::from("TestLog"),
+            threads: 0,
+        };
+        let solver = BranchAndBoundSolver::new(&problem, &solver_config);
+        solver.set_feasible_strategies(&[1.0, 0.0, 1.0], &[1.0, 0.0, 1.0]);
+        assert_eq!(solver.solve(), SolveStatus::Infeasible);
+        assert_eq!(solver.get_solution().err(), Some(SolveStatus::Infeasible));
+    }
+}
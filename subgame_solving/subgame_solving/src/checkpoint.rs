@@ -0,0 +1,225 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use efg_lite::schema::vector_capnp;
+use efg_lite::strategy::SequenceFormStrategy;
+use efg_lite::treeplex::Treeplex;
+use efg_lite::vector::TreeplexVector;
+
+/// Leader's strategy in the skinny treeplex of a solved subgame, together with the objective
+/// value of its bounded problem.
+pub struct SkinnySolution {
+    pub leader_strategy: SequenceFormStrategy,
+    pub objective_value: f64,
+}
+
+/// 64-bit FNV-1a hash, used for fingerprints since they are written to disk. Unlike that of
+/// `DefaultHasher`, its output does not depend on the Rust release or the platform.
+struct FnvHasher(u64);
+
+impl FnvHasher {
+    fn new() -> FnvHasher {
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes.iter() {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    /// Writes the length of `bytes` before them, so that consecutive writes are unambiguous.
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_u64(bytes.len() as u64);
+        self.write(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Run directory to which each subgame's solution is written as soon as it is solved, so that
+/// an interrupted run may be resumed without solving those subgames again.
+///
+/// Solutions are keyed by the subgame id and a fingerprint of the game, the blueprint and the
+/// parameters used to generate bounds. Solutions of a run with different inputs are thus never
+/// picked up, and several runs (or refinement passes) may share a directory.
+pub struct Checkpoint {
+    run_dir: PathBuf,
+    resume: bool,
+
+    // Fingerprint of the game file and of any settings the bounded problems depend on.
+    run_fingerprint: u64,
+}
+
+impl Checkpoint {
+    /// Creates the run directory if needed. Previously saved solutions are only loaded if
+    /// `resume` is set, otherwise they are overwritten.
+    pub fn new(
+        run_dir: &Path,
+        game_file: &Path,
+        settings: &str,
+        resume: bool,
+    ) -> std::io::Result<Checkpoint> {
+        std::fs::create_dir_all(run_dir)?;
+        let mut hasher = FnvHasher::new();
+        hasher.write_bytes(&std::fs::read(game_file)?);
+        hasher.write_bytes(settings.as_bytes());
+        Ok(Checkpoint {
+            run_dir: run_dir.to_path_buf(),
+            resume,
+            run_fingerprint: hasher.finish(),
+        })
+    }
+
+    /// Fingerprint of subgames resolved against `leader_blueprint`.
+    pub fn fingerprint(
+        &self,
+        leader_blueprint: &SequenceFormStrategy,
+        splitting_ratio: f64,
+        gift_factor: f64,
+    ) -> u64 {
+        let mut hasher = FnvHasher::new();
+        hasher.write_u64(self.run_fingerprint);
+        hasher.write_u64(leader_blueprint.inner().entries.len() as u64);
+        for entry in leader_blueprint.inner().entries.iter() {
+            hasher.write_u64(entry.to_bits());
+        }
+        hasher.write_u64(splitting_ratio.to_bits());
+        hasher.write_u64(gift_factor.to_bits());
+        hasher.finish()
+    }
+
+    fn path(&self, subgame_id: usize, fingerprint: u64, extension: &str) -> PathBuf {
+        self.run_dir
+            .join(format!("subgame_{}-{:016x}.{}", subgame_id, fingerprint, extension))
+    }
+
+    /// Loads the solution of a subgame if we are resuming and it was saved before. Solutions
+    /// which cannot be read in full are ignored, and the subgame is solved again.
+    pub fn load(
+        &self,
+        subgame_id: usize,
+        fingerprint: u64,
+        skinny_treeplex: &Arc<Treeplex>,
+    ) -> Option<SkinnySolution> {
+        if !self.resume {
+            return None;
+        }
+        let objective_value = std::fs::read_to_string(self.path(subgame_id, fingerprint, "obj"))
+            .ok()?
+            .trim()
+            .parse::<f64>()
+            .ok()?;
+
+        let file = File::open(self.path(subgame_id, fingerprint, "vec")).ok()?;
+        let message_reader = capnp::serialize::read_message(
+            &mut BufReader::new(file),
+            capnp::message::ReaderOptions {
+                traversal_limit_in_words: 8 * 1024 * 1024 * 1024,
+                nesting_limit: 64,
+            },
+        )
+        .ok()?;
+        let vector_reader = message_reader
+            .get_root::<vector_capnp::vector::Reader>()
+            .ok()?;
+        if vector_reader.get_entries().ok()?.len() as usize != skinny_treeplex.num_sequences() {
+            return None;
+        }
+        let vector = TreeplexVector::deserialize(&vector_reader, skinny_treeplex).ok()?;
+        Some(SkinnySolution {
+            leader_strategy: SequenceFormStrategy::from_treeplex_vector(vector),
+            objective_value,
+        })
+    }
+
+    /// Saves the solution of a subgame. The strategy is written last and moved into place once
+    /// complete, so a run interrupted while saving leaves no partial solution behind.
+    pub fn save(
+        &self,
+        subgame_id: usize,
+        fingerprint: u64,
+        solution: &SkinnySolution,
+    ) -> std::io::Result<()> {
+        std::fs::write(
+            self.path(subgame_id, fingerprint, "obj"),
+            format!("{:?}\n", solution.objective_value),
+        )?;
+        let partial_path = self.path(subgame_id, fingerprint, "vec.partial");
+        let mut file_writer = File::create(&partial_path)?;
+        solution.leader_strategy.inner().persist(&mut file_writer)?;
+        file_writer.sync_all()?;
+        std::fs::rename(partial_path, self.path(subgame_id, fingerprint, "vec"))
+    }
+}
+
+#[cfg(test)]
+pub mod test_fixtures {
+    use super::{Checkpoint, FnvHasher, SkinnySolution};
+    use crate::branch_and_bound_solver::test_fixtures::commitment_problem;
+    use efg_lite::game::Player;
+    use efg_lite::strategy::SequenceFormStrategy;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn fnv_hasher_is_stable() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = FnvHasher::new();
+            hasher.write(bytes);
+            hasher.finish()
+        };
+        assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(hash(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn save_and_resume() {
+        let problem = commitment_problem();
+        let treeplex = problem.game.treeplex(Player::Player1);
+        let run_dir = std::env::temp_dir().join(format!("checkpoint-test-{}", std::process::id()));
+        let game_file = run_dir.join("game");
+        std::fs::create_dir_all(&run_dir).unwrap();
+        std::fs::write(&game_file, b"game").unwrap();
+
+        let blueprint = SequenceFormStrategy::from_uniform_strategy(treeplex);
+        let mut vector = blueprint.inner().clone();
+        vector[0] = 0.25;
+        vector[1] = 0.75;
+        let solution = SkinnySolution {
+            leader_strategy: SequenceFormStrategy::from_treeplex_vector(vector),
+            objective_value: 1.0 / 3.0,
+        };
+
+        let checkpoint = Checkpoint::new(&run_dir, &game_file, "", false).unwrap();
+        let fingerprint = checkpoint.fingerprint(&blueprint, 0.5, 1.0);
+        checkpoint.save(3, fingerprint, &solution).unwrap();
+        assert!(checkpoint.load(3, fingerprint, treeplex).is_none());
+
+        let checkpoint = Checkpoint::new(&run_dir, &game_file, "", true).unwrap();
+        assert_eq!(checkpoint.fingerprint(&blueprint, 0.5, 1.0), fingerprint);
+        let loaded = checkpoint.load(3, fingerprint, treeplex).unwrap();
+        assert_eq!(loaded.objective_value, solution.objective_value);
+        assert_approx_eq!(loaded.leader_strategy.inner()[0], 0.25);
+        assert_approx_eq!(loaded.leader_strategy.inner()[1], 0.75);
+
+        // Solutions are not shared between subgames, blueprints or bound parameters.
+        assert!(checkpoint.load(4, fingerprint, treeplex).is_none());
+        assert_ne!(checkpoint.fingerprint(&solution.leader_strategy, 0.5, 1.0), fingerprint);
+        assert_ne!(checkpoint.fingerprint(&blueprint, 0.5, 0.5), fingerprint);
+        let other_settings = Checkpoint::new(&run_dir, &game_file, "weak", true).unwrap();
+        assert_ne!(other_settings.fingerprint(&blueprint, 0.5, 1.0), fingerprint);
+
+        std::fs::remove_dir_all(&run_dir).unwrap();
+    }
+}
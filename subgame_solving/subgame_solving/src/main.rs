@@ -1,9 +1,17 @@
+mod branch_and_bound_solver;
+mod checkpoint;
+#[cfg(feature = "gurobi")]
 mod cbc_solver;
+#[cfg(feature = "gurobi")]
 mod gurobi_solver;
 mod mip_solution;
+mod report;
+mod robust_model;
+mod robust_solver;
 mod solver;
+mod sse_model;
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 
 use std::io::BufReader;
@@ -11,20 +19,35 @@ use std::io::BufReader;
 use efg_lite::game::{ExtensiveFormGame, Player};
 use efg_lite::schema::game_capnp;
 use efg_lite::schema::vector_capnp;
-use efg_lite::sse::{BlueprintBr, GameBuilder};
+use efg_lite::sse::{
+    worst_case_leader_value, BlueprintBr, BoundedProblem, FollowerTypes, GameBuilder,
+    GiftSplittingRule, QuantalResponse, SafetyCertificate, TieBreaking, TieBreakingRule,
+    ValueBound,
+};
 use efg_lite::strategy::{BehavioralStrategy, SequenceFormStrategy};
 
-use efg_lite::treeplex::TreeplexTools;
+use efg_lite::treeplex::{SequenceId, TreeplexTools};
 use efg_lite::vector::TreeplexVector;
 use log::{debug, error, info, warn};
 
 use solver::SolverConfig;
 
+use crate::branch_and_bound_solver::{BranchAndBoundSolver, BranchingRule};
+use crate::checkpoint::{Checkpoint, SkinnySolution};
+#[cfg(feature = "gurobi")]
 use crate::cbc_solver::CbcSolver;
+#[cfg(feature = "gurobi")]
 use crate::gurobi_solver::GurobiSolver;
-use crate::solver::Solver;
+use crate::mip_solution::MIPSolution;
+use crate::report::{Payoffs, RunReport, SubgameReport, SubgameStatus};
+use crate::robust_model::RobustSolution;
+use crate::robust_solver::RobustSolver;
+use crate::solver::{SolveStatus, Solver};
+use crate::sse_model::{OnOffEncoding, SseModel};
 
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
 
 #[macro_use]
 extern crate approx;
@@ -52,9 +75,143 @@ struct Opt {
     #[structopt(short = "f", long = "gift_factor", default_value = "1.0")]
     gift_factor: f64,
 
+    // How gifts are split among infosets in the trunk: uniform, mass (proportional to the
+    // blueprint's reach probability) or optimized (maximizing the slack given to subgames).
+    #[structopt(long = "gift_splitting", default_value = "uniform")]
+    gift_splitting: GiftSplittingRule,
+
+    // Follower tie breaking: strong (in favour of the leader), weak or lowest_index.
+    #[structopt(long = "tie_breaking", default_value = "strong")]
+    tie_breaking: TieBreakingRule,
+
+    // Follower sequences within this value of the best one are considered tied.
+    #[structopt(long = "tie_breaking_epsilon", default_value = "1e-7")]
+    tie_breaking_epsilon: f64,
+
     // Time limit *per subgame*
     #[structopt(short = "t", long = "time_limit_per_subgame")]
     time_limit: f64,
+
+    // MILP solver: gurobi or bnb (embedded branch and bound).
+    #[structopt(short = "s", long = "solver", default_value = "gurobi")]
+    solver: SolverType,
+
+    // Branching rule for branch and bound: infoset or fractional.
+    #[structopt(long = "branching_rule", default_value = "infoset")]
+    branching_rule: BranchingRule,
+
+    // Encoding of the on-off constraints: big_m, indicator or sos1.
+    #[structopt(long = "on_off_encoding", default_value = "big_m")]
+    on_off_encoding: OnOffEncoding,
+
+    // Directory to write the MILP of each subgame to, in both MPS and LP format.
+    #[structopt(long = "model_dir")]
+    model_dir: Option<PathBuf>,
+
+    // Only write the MILPs to `model_dir` without solving them. Subgames keep the blueprint.
+    #[structopt(long = "write_models_only")]
+    write_models_only: bool,
+
+    // Solve the MILP of the entire game instead of its subgames, giving the exact SSE.
+    #[structopt(long = "full_game")]
+    full_game: bool,
+
+    // Maximize the leader's worst case over follower responses within this value of optimal
+    // at every infoset, instead of solving for the SSE. Blueprint best responses should then
+    // also be pessimistic, e.g., with `--tie_breaking weak --tie_breaking_epsilon` set to it.
+    #[structopt(long = "robust_epsilon")]
+    robust_epsilon: Option<f64>,
+
+    // Also report the leader's payoffs against a logit quantal-responding follower with this
+    // rationality parameter.
+    #[structopt(long = "quantal_lambda")]
+    quantal_lambda: Option<f64>,
+
+    // Follower types of a Bayesian game, as written by the `bayesian` generator. If given,
+    // payoffs are also reported conditioned on each type.
+    #[structopt(long = "follower_types")]
+    follower_types: Option<PathBuf>,
+
+    // Tolerance when checking the bounds at subgame heads against the refined strategy.
+    // The run fails if the refined strategy is not certified to be safe.
+    #[structopt(long = "safety_tolerance", default_value = "1e-6")]
+    safety_tolerance: f64,
+
+    // Number of subgames solved concurrently, each by a worker with its own solver
+    // environment, and the number of threads each worker's Gurobi environment may use
+    // (0 leaves this to Gurobi).
+    #[structopt(long = "num_workers", default_value = "1")]
+    num_workers: usize,
+
+    #[structopt(long = "solver_threads", default_value = "0")]
+    solver_threads: usize,
+
+    // Subgames nested up to this depth are resolved again, one level at a time, using the
+    // refined strategy of the level above as their blueprint. With 0, only top-level subgames
+    // are resolved (together with everything nested within them).
+    #[structopt(long = "max_subgame_depth", default_value = "0")]
+    max_subgame_depth: usize,
+
+    // Maximum number of refinement passes. After each pass, the refined strategy becomes the
+    // blueprint of the next, until the leader's value improves by less than `pass_tolerance`
+    // or a pass is not safe.
+    #[structopt(long = "max_passes", default_value = "1")]
+    max_passes: usize,
+
+    #[structopt(long = "pass_tolerance", default_value = "1e-6")]
+    pass_tolerance: f64,
+
+    // Directory to save the solution of each subgame to as soon as it is solved. With `resume`,
+    // subgames with a solution saved there by an earlier run of the same inputs are skipped.
+    #[structopt(long = "run_dir")]
+    run_dir: Option<PathBuf>,
+
+    #[structopt(long = "resume")]
+    resume: bool,
+
+    // Directory to write strategies, values and the run report (report.json) to.
+    #[structopt(long = "output_dir", default_value = ".")]
+    output_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SolverType {
+    Gurobi,
+    BranchAndBound,
+}
+
+impl FromStr for SolverType {
+    type Err = String;
+    fn from_str(solver_type: &str) -> Result<Self, Self::Err> {
+        match solver_type {
+            "gurobi" | "Gurobi" => Ok(SolverType::Gurobi),
+            "bnb" | "branch_and_bound" => Ok(SolverType::BranchAndBound),
+            s => Err(format!("Unknown solver type: {}", s)),
+        }
+    }
+}
+
+impl FromStr for BranchingRule {
+    type Err = String;
+    fn from_str(branching_rule: &str) -> Result<Self, Self::Err> {
+        match branching_rule {
+            "infoset" | "infoset_top_down" => Ok(BranchingRule::InfosetTopDown),
+            "fractional" | "most_fractional" => Ok(BranchingRule::MostFractional),
+            s => Err(format!("Unknown branching rule: {}", s)),
+        }
+    }
+}
+
+impl FromStr for OnOffEncoding {
+    type Err = String;
+    fn from_str(on_off_encoding: &str) -> Result<Self, Self::Err> {
+        match on_off_encoding {
+            "big_m" | "bigm" => Ok(OnOffEncoding::BigM),
+            "indicator" => Ok(OnOffEncoding::Indicator),
+            "sos1" | "sos" => Ok(OnOffEncoding::Sos1),
+            s => Err(format!("Unknown on-off encoding: {}", s)),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -76,118 +233,513 @@ impl FromStr for BlueprintType {
     }
 }
 
-fn solve_all_subgames<'a, 'b>(
-    game: &'a ExtensiveFormGame,
-    leader_blueprint: &'a SequenceFormStrategy<'a>,
-    opt: &'b Opt,
-) -> SequenceFormStrategy<'a> {
-    let blueprint_br = BlueprintBr::new(game, &leader_blueprint);
+fn solve_with_branch_and_bound(
+    bounded_problem: &BoundedProblem,
+    solver_config: &SolverConfig,
+    feasible_leader: &[f64],
+    feasible_follower: &[f64],
+) -> Result<MIPSolution, SolveStatus> {
+    let solver = BranchAndBoundSolver::new(bounded_problem, solver_config);
+    solver.set_feasible_strategies(feasible_leader, feasible_follower);
+    solver.solve();
+    solver.get_solution()
+}
 
-    let follower_treeplex_tools = TreeplexTools::new(game.treeplex(Player::Player2));
-    let leader_treeplex_tools = TreeplexTools::new(game.treeplex(Player::Player1));
+/// Writes the MILP of a problem to `<name>.mps` and `<name>.lp` in `model_dir`.
+fn write_model(
+    bounded_problem: &BoundedProblem,
+    on_off_encoding: OnOffEncoding,
+    model_dir: &Path,
+    name: &str,
+) {
+    let sse_model = SseModel::new(bounded_problem, on_off_encoding);
+    for extension in ["mps", "lp"].iter() {
+        let path = model_dir.join(format!("{}.{}", name, extension));
+        info!("Writing model to {:?}", path);
+        sse_model.model().write_to_file(&path).unwrap();
+    }
+}
 
-    let game_builder = GameBuilder::new(
-        game,
-        &follower_treeplex_tools,
-        &leader_treeplex_tools,
-        &blueprint_br,
-        opt.splitting_ratio,
-        opt.gift_factor,
+fn solve_bounded_problem(
+    bounded_problem: &BoundedProblem,
+    solver_config: &SolverConfig,
+    solver_type: SolverType,
+    feasible_leader: &[f64],
+    feasible_follower: &[f64],
+) -> Result<MIPSolution, SolveStatus> {
+    match solver_type {
+        #[cfg(feature = "gurobi")]
+        SolverType::Gurobi => {
+            // let solver = CbcSolver::new(&bounded_problem, &solver_config);
+            let solver = GurobiSolver::new(bounded_problem, solver_config);
+
+            // solver.set_feasible_blueprint(&blueprint_br);
+            solver.set_feasible_strategies(feasible_leader, feasible_follower);
+
+            solver.solve();
+            solver.get_solution()
+        }
+        #[cfg(not(feature = "gurobi"))]
+        SolverType::Gurobi => {
+            warn!("Built without Gurobi, using branch and bound instead");
+            solve_with_branch_and_bound(bounded_problem, solver_config, feasible_leader, feasible_follower)
+        }
+        SolverType::BranchAndBound => {
+            solve_with_branch_and_bound(bounded_problem, solver_config, feasible_leader, feasible_follower)
+        }
+    }
+}
+
+/// Solves for the leader strategy maximizing the worst case against an epsilon-rational
+/// follower, using Gurobi or the embedded branch and bound.
+fn solve_robust_problem(
+    bounded_problem: &BoundedProblem,
+    solver_config: &SolverConfig,
+    solver_type: SolverType,
+    epsilon: f64,
+) -> Result<RobustSolution, SolveStatus> {
+    let solver = RobustSolver::new(bounded_problem, epsilon, solver_config);
+    match solver_type {
+        #[cfg(feature = "gurobi")]
+        SolverType::Gurobi => solver.solve_with_gurobi(),
+        #[cfg(not(feature = "gurobi"))]
+        SolverType::Gurobi => {
+            warn!("Built without Gurobi, using branch and bound instead");
+            solver.solve_with_branch_and_bound()
+        }
+        SolverType::BranchAndBound => solver.solve_with_branch_and_bound(),
+    }
+}
+
+/// Reads the follower types of a Bayesian game, see `FollowerTypes::load`.
+fn load_follower_types(
+    game: &ExtensiveFormGame,
+    types_file: &Path,
+) -> std::io::Result<FollowerTypes> {
+    let mut file_reader = BufReader::new(File::open(types_file)?);
+    FollowerTypes::load(game.treeplex(Player::Player2), &mut file_reader)
+}
+
+/// Prints the payoffs of a strategy profile conditioned on each follower type, if the game
+/// is a Bayesian game.
+fn print_payoffs_by_type(br: &BlueprintBr, name: &str, follower_types: Option<&FollowerTypes>) {
+    let follower_types = match follower_types {
+        Some(follower_types) => follower_types,
+        None => return,
+    };
+    let leader_values = follower_types
+        .conditional_values(|x| br.leader_seq_value(br.follower_behavioral_index(x)));
+    let follower_values = follower_types.conditional_values(|x| br.follower_infoset_value(x));
+    for follower_type in 0..follower_types.num_types() {
+        println!(
+            "Payoff {} leader type {} --- {:?}",
+            name, follower_type, leader_values[follower_type]
+        );
+        println!(
+            "Payoff {} follower type {} --- {:?}",
+            name, follower_type, follower_values[follower_type]
+        );
+    }
+}
+
+fn print_safety_certificate(certificate: &SafetyCertificate) {
+    println!("Safety trunk preserved --- {:?}", certificate.trunk_preserved());
+    if !certificate.trunk_preserved() {
+        println!(
+            "Safety deviating infosets --- {:?}",
+            certificate.deviating_infosets
+        );
+    }
+    println!(
+        "Safety leader value BP/SS --- {:?} {:?}",
+        certificate.blueprint_leader_value, certificate.refined_leader_value
     );
+    println!(
+        "Safety bounds tight/violated/total --- {} {} {}",
+        certificate.num_tight_bounds(),
+        certificate.violated_bounds().len(),
+        certificate.bound_checks.len()
+    );
+    for check in certificate.violated_bounds().iter() {
+        println!(
+            "Safety violated bound at infoset {} --- {:?}, value {:?}",
+            check.infoset_id, check.bound, check.refined_value
+        );
+    }
+    // Only subgames whose contribution changed are worth printing.
+    for contribution in certificate.subgame_contributions.iter() {
+        debug!("Safety subgame contribution --- {:?}", contribution);
+        if contribution.blueprint_value != contribution.refined_value {
+            println!(
+                "Safety subgame {} leader BP/SS --- {:?} {:?}",
+                contribution.subgame_id, contribution.blueprint_value, contribution.refined_value
+            );
+        }
+    }
+}
 
-    let solver_config = SolverConfig {
+fn tie_breaking(opt: &Opt) -> TieBreaking {
+    TieBreaking::new(opt.tie_breaking.clone(), opt.tie_breaking_epsilon)
+}
+
+/// Solver configuration of a worker, each of which logs to its own file.
+fn solver_config(opt: &Opt, worker_id: usize) -> SolverConfig {
+    SolverConfig {
         time_limit: opt.time_limit,
-    };
+        on_off_encoding: opt.on_off_encoding,
+        branching_rule: opt.branching_rule,
+        log_file: format!("TestLog-{}", worker_id),
+        threads: opt.solver_threads,
+    }
+}
 
-    let mut leader_full_strategy = leader_blueprint.inner().clone();
+/// Solves the MILP of the entire game, giving the exact Strong Stackelberg Equilibrium
+/// as a baseline to measure the blueprint and refined strategies against. The blueprint
+/// and the follower's best response to it are used as the initial feasible solution.
+fn solve_full_game(
+    game: &ExtensiveFormGame,
+    leader_blueprint: &SequenceFormStrategy,
+    follower_types: Option<&FollowerTypes>,
+    opt: &Opt,
+) {
+    let blueprint_br = BlueprintBr::with_tie_breaking(game, leader_blueprint, tie_breaking(opt));
+    let bounded_problem = BoundedProblem::from_full_game(game);
+
+    if let Some(model_dir) = &opt.model_dir {
+        write_model(&bounded_problem, opt.on_off_encoding, model_dir, "full_game");
+    }
+    if opt.write_models_only {
+        return;
+    }
+    if let Some(epsilon) = opt.robust_epsilon {
+        solve_full_game_robust(game, leader_blueprint, &bounded_problem, epsilon, opt);
+        return;
+    }
 
-    let mut written_to = std::vec::from_elem(false, game.treeplex(Player::Player1).num_sequences());
+    let sol = match solve_bounded_problem(
+        &bounded_problem,
+        &solver_config(opt, 0),
+        opt.solver,
+        &leader_blueprint.inner().entries,
+        &blueprint_br.follower_sequence().inner().entries,
+    ) {
+        Ok(sol) => sol,
+        Err(status) => {
+            error!("No solution to the full game, solver status {:?}", status);
+            std::process::exit(1);
+        }
+    };
 
-    debug!("Preprocessed with blueprint");
+    let payoff_bp_leader = game.evaluate_payoffs(
+        leader_blueprint,
+        blueprint_br.follower_sequence(),
+        Player::Player1,
+    );
+    let payoff_sse_leader =
+        game.evaluate_payoffs(&sol.leader_strategy, &sol.follower_strategy, Player::Player1);
+    println!("Payoff BP leader --- {:?}", payoff_bp_leader);
+    println!("Payoff SSE leader --- {:?}", payoff_sse_leader);
+    println!(
+        "Payoff SSE follower --- {:?}",
+        game.evaluate_payoffs(&sol.leader_strategy, &sol.follower_strategy, Player::Player2)
+    );
+    println!("Optimality gap BP --- {:?}", payoff_sse_leader - payoff_bp_leader);
+    print_payoffs_by_type(&blueprint_br, "BP", follower_types);
 
-    let mut objective_values = vec![];
+    println!("Saving SSE leader strategy");
+    let mut file_writer = File::create(opt.output_dir.join("sse-leader-strategy.vec")).unwrap();
+    sol.leader_strategy.inner().persist(&mut file_writer).unwrap();
 
-    info!("Number of subgames{:?}", game.num_subgames());
+    println!("Saving SSE follower strategy");
+    let mut file_writer = File::create(opt.output_dir.join("sse-follower-strategy.vec")).unwrap();
+    sol.follower_strategy.inner().persist(&mut file_writer).unwrap();
 
-    for subgame_id in 0..game.num_subgames() {
-        debug!("Solving subgame {:?}", subgame_id);
-        let (bounded_problem, game_mapper, feasible_leader, feasible_follower) =
-            game_builder.bounded_problem(subgame_id);
+    // Values are computed in the same way as for refined strategies.
+    let br = BlueprintBr::with_tie_breaking(game, &sol.leader_strategy, tie_breaking(opt));
+    print_payoffs_by_type(&br, "SSE", follower_types);
 
-        // debug!("Mapper --- {:?}", game_mapper);
-        // debug!("Game --- {:?}", bounded_problem.game);
-        debug!("Solving");
-        // let solver = CbcSolver::new(&bounded_problem, &solver_config);
-        let solver = GurobiSolver::new(&bounded_problem, &solver_config);
+    println!("Saving SSE leader br-values");
+    let mut file_writer = File::create(opt.output_dir.join("sse-leader-br-values.vec")).unwrap();
+    br.leader_seq_values().persist(&mut file_writer).unwrap();
 
-        // Map feasible solution for BP to skinny treeplex.
+    println!("Saving SSE follower br-values");
+    let mut file_writer = File::create(opt.output_dir.join("sse-follower-br-values.vec")).unwrap();
+    br.follower_seq_values().persist(&mut file_writer).unwrap();
+}
 
+/// Solves the robust MILP of the entire game and compares the leader's worst case against
+/// an epsilon-rational follower with that of the blueprint.
+fn solve_full_game_robust(
+    game: &ExtensiveFormGame,
+    leader_blueprint: &SequenceFormStrategy,
+    bounded_problem: &BoundedProblem,
+    epsilon: f64,
+    opt: &Opt,
+) {
+    let sol = match solve_robust_problem(bounded_problem, &solver_config(opt, 0), opt.solver, epsilon)
+    {
+        Ok(sol) => sol,
+        Err(status) => {
+            error!("No solution to the full game, solver status {:?}", status);
+            std::process::exit(1);
+        }
+    };
 
-        // solver.set_feasible_blueprint(&blueprint_br);
-        solver.set_feasible_strategies(&feasible_leader, &feasible_follower);
+    let worst_case_bp_leader = worst_case_leader_value(game, leader_blueprint, epsilon);
+    println!("Worst case BP leader --- {:?}", worst_case_bp_leader);
+    println!("Worst case robust leader --- {:?}", sol.worst_case_value);
+    println!(
+        "Robustness gap BP --- {:?}",
+        sol.worst_case_value - worst_case_bp_leader
+    );
 
-        debug!("Feasible leader: {:?}", feasible_leader);
-        debug!("Feasible follower: {:?}", feasible_follower);
+    println!("Saving robust leader strategy");
+    let mut file_writer = File::create(opt.output_dir.join("robust-leader-strategy.vec")).unwrap();
+    sol.leader_strategy.inner().persist(&mut file_writer).unwrap();
+}
 
-        solver.solve();
+/// Leader's strategy in a resolved subgame, in terms of the original game.
+struct SubgameSolution {
+    subgame_id: usize,
 
-        debug!("Solved!");
-        let sol = solver.get_solution();
-        let strategy_pl1 = sol.leader_strategy.clone();
-        let strategy_pl2 = sol.follower_strategy.clone();
-        let leaf_probs = sol.leaf_probabilities.clone();
-        let slack = sol.follower_slack.clone();
-        let follower_value = sol.follower_value.clone();
+    // Behavioral strategy at each leader infoset within the subgame, top-down.
+    leader_behavioral: Vec<(usize, Vec<(SequenceId, f64)>)>,
+    objective_value: Option<f64>,
+    report: SubgameReport,
+}
 
-        debug!("Leader skinny strategy {:?}", strategy_pl1);
+/// Builds and solves the bounded problem of a top-level subgame, which is subgame
+/// `original_subgame_id` of the input game. Returns None if models are only written, in which
+/// case the subgame keeps the blueprint. Solutions are saved to (or when resuming, loaded from)
+/// the checkpoint, if any, under the given fingerprint. If the solver has no usable solution,
+/// the subgame falls back to the blueprint, which satisfies all bounds by construction.
+fn solve_subgame(
+    game_builder: &GameBuilder,
+    subgame_id: usize,
+    original_subgame_id: usize,
+    solver_config: &SolverConfig,
+    checkpoint: Option<(&Checkpoint, u64)>,
+    opt: &Opt,
+) -> Option<SubgameSolution> {
+    let start_time = Instant::now();
+    let (bounded_problem, game_mapper, feasible_leader, feasible_follower) =
+        game_builder.bounded_problem(subgame_id);
+
+    debug!("Feasible leader: {:?}", feasible_leader);
+    debug!("Feasible follower: {:?}", feasible_follower);
+
+    if let Some(model_dir) = &opt.model_dir {
+        let name = format!("subgame_{}", original_subgame_id);
+        write_model(&bounded_problem, opt.on_off_encoding, model_dir, &name);
+    }
+    if opt.write_models_only {
+        return None;
+    }
 
-        // Map strategy into full game
-        let behavior_strategy_pl1 = BehavioralStrategy::from_sequence_form_strategy(strategy_pl1);
+    let skinny_treeplex_leader = bounded_problem.game.treeplex(Player::Player1);
+    let saved_solution = checkpoint.and_then(|(checkpoint, fingerprint)| {
+        checkpoint.load(original_subgame_id, fingerprint, skinny_treeplex_leader)
+    });
+    let (solution, status, mip_gap) = match saved_solution {
+        Some(solution) => {
+            info!("Loaded solution of subgame {} from run directory", original_subgame_id);
+            (Some(solution), SubgameStatus::Resumed, None)
+        }
+        None => {
+            let result = match opt.robust_epsilon {
+                Some(epsilon) => {
+                    solve_robust_problem(&bounded_problem, solver_config, opt.solver, epsilon).map(
+                        |sol| {
+                            let solution = SkinnySolution {
+                                leader_strategy: sol.leader_strategy,
+                                objective_value: sol.worst_case_value,
+                            };
+                            (solution, sol.status, None)
+                        },
+                    )
+                }
+                None => solve_bounded_problem(
+                    &bounded_problem,
+                    solver_config,
+                    opt.solver,
+                    &feasible_leader,
+                    &feasible_follower,
+                )
+                .map(|sol| {
+                    let solution = SkinnySolution {
+                        leader_strategy: sol.leader_strategy,
+                        objective_value: sol.objective_value,
+                    };
+                    (solution, sol.status, Some(sol.mip_gap))
+                }),
+            };
+            match result {
+                Ok((solution, solve_status, mip_gap)) => {
+                    if let Some((checkpoint, fingerprint)) = checkpoint {
+                        checkpoint
+                            .save(original_subgame_id, fingerprint, &solution)
+                            .unwrap();
+                    }
+                    (Some(solution), SubgameStatus::from(solve_status), mip_gap)
+                }
+                Err(solve_status) => {
+                    // Not saved, so that the subgame is solved again when resuming.
+                    warn!(
+                        "No solution to subgame {} ({:?}), falling back to the blueprint",
+                        original_subgame_id, solve_status
+                    );
+                    (None, SubgameStatus::from(solve_status), None)
+                }
+            }
+        }
+    };
+    let (strategy_pl1, objective_value) = match solution {
+        Some(solution) => (solution.leader_strategy, Some(solution.objective_value)),
+        None => {
+            let vector = TreeplexVector::from_vec(skinny_treeplex_leader, feasible_leader.clone());
+            (SequenceFormStrategy::from_treeplex_vector(vector), None)
+        }
+    };
 
-        debug!(
-            "Behavioral strategy from sequence form {:?}",
-            behavior_strategy_pl1
-        );
-        let skinny_treeplex_leader = bounded_problem.game.treeplex(Player::Player1);
+    debug!("Solved subgame {}", original_subgame_id);
+    debug!("Leader skinny strategy {:?}", strategy_pl1);
+
+    let behavior_strategy_pl1 = BehavioralStrategy::from_sequence_form_strategy(strategy_pl1);
+    debug!(
+        "Behavioral strategy from sequence form {:?}",
+        behavior_strategy_pl1
+    );
 
-        for skinny_infoset_id in (0..skinny_treeplex_leader.num_infosets()).rev() {
+    let leader_behavioral = (0..skinny_treeplex_leader.num_infosets())
+        .rev()
+        .map(|skinny_infoset_id| {
             let skinny_infoset = skinny_treeplex_leader.infosets()[skinny_infoset_id];
             let original_infoset_id = game_mapper
                 .mapper_leader
                 .skinny_infoset_to_infoset(skinny_infoset_id);
-            let original_parent_sequence =
-                game.treeplex(Player::Player1).infosets()[original_infoset_id].parent_sequence;
-            let parent_prob = leader_full_strategy[original_parent_sequence];
-
-            for skinny_sequence_id in skinny_infoset.start_sequence..=skinny_infoset.end_sequence {
-                let original_child_sequence = game_mapper
-                    .mapper_leader
-                    .skinny_seq_to_seq(skinny_sequence_id);
-                assert!(written_to[original_child_sequence] == false);
-                leader_full_strategy[original_child_sequence] =
-                    behavior_strategy_pl1.inner()[skinny_sequence_id] * parent_prob;
-                written_to[original_child_sequence] = true;
+            let behavioral = (skinny_infoset.start_sequence..=skinny_infoset.end_sequence)
+                .map(|skinny_sequence_id| {
+                    (
+                        game_mapper.mapper_leader.skinny_seq_to_seq(skinny_sequence_id),
+                        behavior_strategy_pl1.inner()[skinny_sequence_id],
+                    )
+                })
+                .collect::<Vec<(SequenceId, f64)>>();
+            (original_infoset_id, behavioral)
+        })
+        .collect::<Vec<(usize, Vec<(SequenceId, f64)>)>>();
+
+    let report = SubgameReport::new(
+        original_subgame_id,
+        &bounded_problem,
+        &game_mapper,
+        status,
+        objective_value,
+        mip_gap,
+        start_time.elapsed().as_secs_f64(),
+    );
+    Some(SubgameSolution {
+        subgame_id: original_subgame_id,
+        leader_behavioral,
+        objective_value,
+        report,
+    })
+}
+
+/// Resolves the top-level subgames of `game` against the blueprint of `blueprint_br`, returning
+/// the refined strategy together with the bounds on follower infosets used while resolving and
+/// a report of each subgame. `original_subgame_ids` maps subgames of `game` to those of the
+/// input game.
+fn resolve_top_level_subgames(
+    game: &ExtensiveFormGame,
+    blueprint_br: &BlueprintBr,
+    original_subgame_ids: &[usize],
+    checkpoint: Option<&Checkpoint>,
+    opt: &Opt,
+) -> (SequenceFormStrategy, Vec<ValueBound>, Vec<SubgameReport>) {
+    let follower_treeplex_tools = TreeplexTools::new(game.treeplex(Player::Player2));
+    let leader_treeplex_tools = TreeplexTools::new(game.treeplex(Player::Player1));
+
+    let game_builder = GameBuilder::with_gift_splitting(
+        game,
+        &follower_treeplex_tools,
+        &leader_treeplex_tools,
+        blueprint_br,
+        opt.splitting_ratio,
+        opt.gift_factor,
+        opt.gift_splitting.policy(),
+    );
+
+    let mut leader_full_strategy = blueprint_br.leader_blueprint().inner().clone();
+
+    let mut written_to = std::vec::from_elem(false, game.treeplex(Player::Player1).num_sequences());
+
+    debug!("Preprocessed with blueprint");
+
+    let checkpoint = checkpoint.map(|checkpoint| {
+        let fingerprint = checkpoint.fingerprint(
+            blueprint_br.leader_blueprint(),
+            opt.splitting_ratio,
+            opt.gift_factor,
+        );
+        (checkpoint, fingerprint)
+    });
+
+    // Nested subgames are resolved as part of their top-level subgame. Workers take subgames
+    // in turn, and solutions are merged in order of subgame ids regardless of which worker
+    // finishes first.
+    let subgame_ids = game.top_level_subgames();
+    let next_subgame = AtomicUsize::new(0);
+    let mut solutions = std::thread::scope(|scope| {
+        let workers = (0..std::cmp::max(opt.num_workers, 1))
+            .map(|worker_id| {
+                let solver_config = solver_config(opt, worker_id);
+                let (subgame_ids, next_subgame, game_builder) =
+                    (&subgame_ids, &next_subgame, &game_builder);
+                scope.spawn(move || {
+                    let mut solutions = vec![];
+                    loop {
+                        let index = next_subgame.fetch_add(1, Ordering::SeqCst);
+                        if index >= subgame_ids.len() {
+                            return solutions;
+                        }
+                        let original_subgame_id = original_subgame_ids[subgame_ids[index]];
+                        debug!("Worker {} solving subgame {}", worker_id, original_subgame_id);
+                        solutions.extend(solve_subgame(
+                            game_builder,
+                            subgame_ids[index],
+                            original_subgame_id,
+                            &solver_config,
+                            checkpoint,
+                            opt,
+                        ));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect::<Vec<SubgameSolution>>()
+    });
+    solutions.sort_by_key(|solution| solution.subgame_id);
+
+    // Map strategies into the full game. Infosets are given top-down, so the parent sequence
+    // of each is either outside the subgame (and keeps the blueprint) or already written.
+    let mut objective_values = vec![];
+    for solution in solutions.iter() {
+        for (infoset_id, behavioral) in solution.leader_behavioral.iter() {
+            let parent_sequence =
+                game.treeplex(Player::Player1).infosets()[*infoset_id].parent_sequence;
+            let parent_prob = leader_full_strategy[parent_sequence];
+            for (sequence_id, prob) in behavioral.iter() {
+                assert!(!written_to[*sequence_id]);
+                leader_full_strategy[*sequence_id] = prob * parent_prob;
+                written_to[*sequence_id] = true;
             }
         }
-
-        objective_values.push(sol.objective_value);
+        objective_values.push(solution.objective_value);
     }
-    println!(
-        "Payoff BP leader --- {:?}",
-        game.evaluate_payoffs(
-            &leader_blueprint,
-            blueprint_br.follower_sequence(),
-            Player::Player1
-        )
-    );
-    println!(
-        "Payoff BP follower --- {:?}",
-        game.evaluate_payoffs(
-            &leader_blueprint,
-            blueprint_br.follower_sequence(),
-            Player::Player2
-        )
-    );
 
     debug!("Leader full strategy --- {:?}", leader_full_strategy);
 
@@ -196,8 +748,191 @@ fn solve_all_subgames<'a, 'b>(
         objective_values
     );
 
+    (
+        SequenceFormStrategy::from_treeplex_vector(leader_full_strategy),
+        game_builder.follower_payoff_bounds().clone(),
+        solutions.into_iter().map(|solution| solution.report).collect(),
+    )
+}
+
+/// Makes a single refinement pass over the blueprint of `blueprint_br`, returning the refined
+/// strategy, its certificate with respect to the blueprint and a report of each subgame.
+fn refine_strategy(
+    game: &ExtensiveFormGame,
+    blueprint_br: &BlueprintBr,
+    checkpoint: Option<&Checkpoint>,
+    opt: &Opt,
+) -> (SequenceFormStrategy, SafetyCertificate, Vec<SubgameReport>) {
+    let (mut leader_full_strategy, follower_bounds, mut subgame_reports) =
+        resolve_top_level_subgames(
+            game,
+            blueprint_br,
+            &(0..game.num_subgames()).collect::<Vec<usize>>(),
+            checkpoint,
+            opt,
+        );
+
+    // Deeper subgames are resolved against the strategy refined so far, with everything above
+    // them (including the subgames just resolved) treated as the trunk. Each level is safe with
+    // respect to the previous one, so the follower's behaviour in the original trunk is kept.
+    // A level may still lower the follower's values at the heads of top-level subgames below
+    // their bounds, since those heads are within the level's trunk. Should a level not be
+    // certified to be safe with respect to both the previous level and the blueprint, we keep
+    // the strategy of the level above and stop descending.
+    let max_depth = (0..game.num_subgames())
+        .map(|subgame_id| game.subgame_depth(subgame_id))
+        .max()
+        .unwrap_or(0);
+    for depth in 1..=std::cmp::min(opt.max_subgame_depth, max_depth) {
+        let (level_game, original_subgame_ids) = game.with_subgames_from_depth(depth);
+        info!("Resolving subgames {:?} at depth {}", original_subgame_ids, depth);
+
+        let level_br =
+            BlueprintBr::with_tie_breaking(&level_game, &leader_full_strategy, tie_breaking(opt));
+        let (refined_strategy, level_bounds, level_reports) = resolve_top_level_subgames(
+            &level_game,
+            &level_br,
+            &original_subgame_ids,
+            checkpoint,
+            opt,
+        );
+        subgame_reports.extend(level_reports.into_iter().map(|mut report| {
+            report.depth = depth;
+            report
+        }));
+        let level_certificate = SafetyCertificate::new(
+            &level_game,
+            &level_br,
+            &refined_strategy,
+            &level_bounds,
+            opt.safety_tolerance,
+        );
+        info!(
+            "Depth {} leader value {:?} -> {:?}, safe {:?}",
+            depth,
+            level_certificate.blueprint_leader_value,
+            level_certificate.refined_leader_value,
+            level_certificate.is_safe()
+        );
+        let certificate = SafetyCertificate::new(
+            game,
+            blueprint_br,
+            &refined_strategy,
+            &follower_bounds,
+            opt.safety_tolerance,
+        );
+        if !level_certificate.is_safe() || !certificate.is_safe() {
+            warn!(
+                "Subgames at depth {} are not certified to be safe, keeping depth {}",
+                depth,
+                depth - 1
+            );
+            break;
+        }
+        leader_full_strategy = refined_strategy;
+    }
+
+    // Certified against the blueprint and the bounds at top-level subgame heads.
+    let certificate = SafetyCertificate::new(
+        game,
+        blueprint_br,
+        &leader_full_strategy,
+        &follower_bounds,
+        opt.safety_tolerance,
+    );
+    (leader_full_strategy, certificate, subgame_reports)
+}
+
+/// Refines the leader's blueprint over one or more passes. The returned certificate is that
+/// of the last pass adopted, with respect to the strategy refined by the pass before it. A pass
+/// is adopted if it is certified to be safe and does not decrease the leader's value, otherwise
+/// the previous strategy is kept. No further passes are made once a pass is not adopted or
+/// improves the leader's value by less than `pass_tolerance`. The returned report covers the
+/// subgames of all passes, and is complete except for the refined strategy's payoffs and safety.
+fn solve_all_subgames(
+    game: &ExtensiveFormGame,
+    leader_blueprint: &SequenceFormStrategy,
+    follower_types: Option<&FollowerTypes>,
+    checkpoint: Option<&Checkpoint>,
+    opt: &Opt,
+) -> (SequenceFormStrategy, SafetyCertificate, RunReport) {
+    let blueprint_br = BlueprintBr::with_tie_breaking(game, leader_blueprint, tie_breaking(opt));
+
+    info!("Number of subgames{:?}", game.num_subgames());
+
+    let (mut leader_full_strategy, mut certificate, mut subgame_reports) =
+        refine_strategy(game, &blueprint_br, checkpoint, opt);
+    let mut leader_values = vec![
+        certificate.blueprint_leader_value,
+        certificate.refined_leader_value,
+    ];
+    let improvement = |certificate: &SafetyCertificate| {
+        certificate.refined_leader_value - certificate.blueprint_leader_value
+    };
+    for pass in 1..opt.max_passes {
+        if !certificate.is_safe() || improvement(&certificate) < opt.pass_tolerance {
+            break;
+        }
+
+        let pass_br =
+            BlueprintBr::with_tie_breaking(game, &leader_full_strategy, tie_breaking(opt));
+        let (refined_strategy, pass_certificate, pass_reports) =
+            refine_strategy(game, &pass_br, checkpoint, opt);
+        info!(
+            "Pass {} leader value {:?} -> {:?}, safe {:?}",
+            pass,
+            pass_certificate.blueprint_leader_value,
+            pass_certificate.refined_leader_value,
+            pass_certificate.is_safe()
+        );
+        subgame_reports.extend(pass_reports.into_iter().map(|mut report| {
+            report.pass = pass;
+            report
+        }));
+        if !pass_certificate.is_safe() || improvement(&pass_certificate) < 0.0 {
+            info!("Keeping the strategy refined before pass {}", pass);
+            break;
+        }
+        leader_full_strategy = refined_strategy;
+        certificate = pass_certificate;
+        leader_values.push(certificate.refined_leader_value);
+    }
+    info!("Leader value after each pass --- {:?}", leader_values);
+    if opt.max_passes > 1 {
+        println!("Passes --- {}", leader_values.len() - 1);
+        println!("Payoff leader by pass --- {:?}", leader_values);
+    }
+
+    let blueprint_payoffs = Payoffs {
+        leader: game.evaluate_payoffs(
+            &leader_blueprint,
+            blueprint_br.follower_sequence(),
+            Player::Player1,
+        ),
+        follower: game.evaluate_payoffs(
+            &leader_blueprint,
+            blueprint_br.follower_sequence(),
+            Player::Player2,
+        ),
+    };
+    println!("Payoff BP leader --- {:?}", blueprint_payoffs.leader);
+    println!("Payoff BP follower --- {:?}", blueprint_payoffs.follower);
+    if let Some(epsilon) = opt.robust_epsilon {
+        println!(
+            "Worst case BP leader --- {:?}",
+            worst_case_leader_value(game, leader_blueprint, epsilon)
+        );
+    }
+    print_payoffs_by_type(&blueprint_br, "BP", follower_types);
+    if let Some(lambda) = opt.quantal_lambda {
+        println!(
+            "Payoff QR BP leader --- {:?}",
+            QuantalResponse::new(game, leader_blueprint, lambda).leader_value()
+        );
+    }
+
     println!("Saving follower blueprint best response");
-    let mut file_writer = File::create("bp-follower-strategy.vec").unwrap();
+    let mut file_writer = File::create(opt.output_dir.join("bp-follower-strategy.vec")).unwrap();
     blueprint_br
         .follower_sequence()
         .inner()
@@ -205,14 +940,37 @@ fn solve_all_subgames<'a, 'b>(
         .unwrap();
 
     println!("Saving follower blueprint value");
-    let mut file_writer = File::create("bp-follower-value.vec").unwrap();
+    let mut file_writer = File::create(opt.output_dir.join("bp-follower-value.vec")).unwrap();
     blueprint_br
         .follower_seq_values()
         .persist(&mut file_writer)
         .unwrap();
 
-    SequenceFormStrategy::from_treeplex_vector(leader_full_strategy)
-
+    print_safety_certificate(&certificate);
+
+    let mut fallback_subgames = subgame_reports
+        .iter()
+        .filter(|report| report.fell_back)
+        .map(|report| report.subgame_id)
+        .collect::<Vec<usize>>();
+    fallback_subgames.sort();
+    fallback_subgames.dedup();
+    if !fallback_subgames.is_empty() {
+        warn!("Subgames {:?} fell back to the blueprint", fallback_subgames);
+    }
+    println!("Fallback subgames --- {:?}", fallback_subgames);
+
+    let report = RunReport {
+        game_file: opt.game_file.clone(),
+        subgames: subgame_reports,
+        fallback_subgames,
+        leader_value_by_pass: leader_values,
+        blueprint_payoffs,
+        refined_payoffs: Payoffs::default(),
+        safe: certificate.is_safe(),
+        wall_time: 0.0,
+    };
+    (leader_full_strategy, certificate, report)
 }
 
 /*
@@ -246,10 +1004,10 @@ fn solve_subgame(game_builder: &GameBuilder, subgame_id: usize) {
 }
 */
 
-fn leader_blueprint<'a>(
+fn leader_blueprint(
     blueprint_type: &BlueprintType,
-    game: &'a ExtensiveFormGame,
-) -> SequenceFormStrategy<'a> {
+    game: &ExtensiveFormGame,
+) -> SequenceFormStrategy {
     match blueprint_type {
         BlueprintType::Uniform => {
             // Assume leader's blueprint strategy is uniform by default.
@@ -281,9 +1039,20 @@ fn leader_blueprint<'a>(
 
 fn main() {
     env_logger::init();
+    let start_time = Instant::now();
 
     let opt = Opt::from_args();
 
+    std::fs::create_dir_all(&opt.output_dir).unwrap();
+    if let Some(model_dir) = &opt.model_dir {
+        std::fs::create_dir_all(model_dir).unwrap();
+    } else if opt.write_models_only {
+        warn!("write_models_only has no effect without model_dir");
+    }
+    if opt.resume && opt.run_dir.is_none() {
+        warn!("resume has no effect without run_dir");
+    }
+
     let game_file = File::open(&opt.game_file).unwrap();
     let mut game_file_reader = BufReader::new(game_file);
     let message_reader = capnp::serialize::read_message(
@@ -301,46 +1070,201 @@ fn main() {
 
     match ExtensiveFormGame::deserialize(&game_reader) {
         Ok(ref game) => {
+            let follower_types = opt.follower_types.as_ref().map(|types_file| {
+                match load_follower_types(game, types_file) {
+                    Ok(follower_types) => follower_types,
+                    Err(err) => {
+                        error!("Could not load follower types from {:?}: {}", types_file, err);
+                        std::process::exit(1);
+                    }
+                }
+            });
             let leader_blueprint = leader_blueprint(&opt.blueprint_file, &game);
-            let leader_strategy = solve_all_subgames(&game, &leader_blueprint, &opt);
+            if opt.full_game {
+                solve_full_game(game, &leader_blueprint, follower_types.as_ref(), &opt);
+                return;
+            }
+            // Solutions also depend on how bounds are generated and on the objective.
+            let checkpoint = opt.run_dir.as_ref().map(|run_dir| {
+                let settings = format!(
+                    "{:?} {:?} {:?}",
+                    opt.gift_splitting,
+                    tie_breaking(&opt),
+                    opt.robust_epsilon
+                );
+                Checkpoint::new(run_dir, &opt.game_file, &settings, opt.resume).unwrap()
+            });
+            let (leader_strategy, certificate, mut report) =
+                solve_all_subgames(
+                    game,
+                    &leader_blueprint,
+                    follower_types.as_ref(),
+                    checkpoint.as_ref(),
+                    &opt,
+                );
             info!("Subgames all solved");
 
-            let br = BlueprintBr::new(game, &leader_strategy);
+            let br = BlueprintBr::with_tie_breaking(game, &leader_strategy, tie_breaking(&opt));
 
             debug!("Leader strategy --- {:?}", leader_strategy);
             debug!("Follower strategy --- {:?}", br.follower_sequence());
 
-            println!(
-                "Payoff SS leader --- {:?}",
-                game.evaluate_payoffs(&leader_strategy, br.follower_sequence(), Player::Player1)
-            );
-            println!(
-                "Payoff SS follower --- {:?}",
-                game.evaluate_payoffs(&leader_strategy, br.follower_sequence(), Player::Player2)
-            );
+            report.refined_payoffs = Payoffs {
+                leader: game.evaluate_payoffs(
+                    &leader_strategy,
+                    br.follower_sequence(),
+                    Player::Player1,
+                ),
+                follower: game.evaluate_payoffs(
+                    &leader_strategy,
+                    br.follower_sequence(),
+                    Player::Player2,
+                ),
+            };
+            println!("Payoff SS leader --- {:?}", report.refined_payoffs.leader);
+            println!("Payoff SS follower --- {:?}", report.refined_payoffs.follower);
+
+            if let Some(epsilon) = opt.robust_epsilon {
+                println!(
+                    "Worst case SS leader --- {:?}",
+                    worst_case_leader_value(game, &leader_strategy, epsilon)
+                );
+            }
+            print_payoffs_by_type(&br, "SS", follower_types.as_ref());
+            if let Some(lambda) = opt.quantal_lambda {
+                println!(
+                    "Payoff QR SS leader --- {:?}",
+                    QuantalResponse::new(game, &leader_strategy, lambda).leader_value()
+                );
+            }
 
             println!("Saving refined leader strategy");
-            let mut file_writer = File::create("full-leader-strategy.vec").unwrap();
+            let mut file_writer =
+                File::create(opt.output_dir.join("full-leader-strategy.vec")).unwrap();
             leader_strategy.inner().persist(&mut file_writer).unwrap();
 
             println!("Saving refined follower strategy");
-            let mut file_writer = File::create("full-follower-strategy.vec").unwrap();
+            let mut file_writer =
+                File::create(opt.output_dir.join("full-follower-strategy.vec")).unwrap();
             br.follower_sequence()
                 .inner()
                 .persist(&mut file_writer)
                 .unwrap();
 
             println!("Saving refined leader br-values");
-            let mut file_writer = File::create("full-leader-br-values.vec").unwrap();
+            let mut file_writer =
+                File::create(opt.output_dir.join("full-leader-br-values.vec")).unwrap();
             br.leader_seq_values().persist(&mut file_writer).unwrap();
 
             println!("Saving refined follower br-values");
-            let mut file_writer = File::create("full-follower-br-values.vec").unwrap();
+            let mut file_writer =
+                File::create(opt.output_dir.join("full-follower-br-values.vec")).unwrap();
             br.follower_seq_values().persist(&mut file_writer).unwrap();
 
+            report.wall_time = start_time.elapsed().as_secs_f64();
+            let report_path = opt.output_dir.join("report.json");
+            println!("Saving run report to {:?}", report_path);
+            report.write(&report_path).unwrap();
+
+            if !certificate.is_safe() {
+                error!("Refined leader strategy is not certified to be safe");
+                std::process::exit(1);
+            }
         }
         Err(err) => {
             println!("{}", err);
         }
     }
 }
+
+#[cfg(test)]
+pub mod test_fixtures {
+    use super::{refine_strategy, solve_all_subgames, Opt};
+    use assert_approx_eq::assert_approx_eq;
+    use efg_lite::fixtures::nested_commitment_game;
+    use efg_lite::game::{ExtensiveFormGame, Player};
+    use efg_lite::sse::BlueprintBr;
+    use efg_lite::strategy::SequenceFormStrategy;
+    use efg_lite::vector::TreeplexVector;
+    use structopt::StructOpt;
+
+    /// The leader plays A with probability 0.25 after both Up and Down, against which the
+    /// follower plays In, Right and D.
+    fn nested_blueprint(game: &ExtensiveFormGame) -> SequenceFormStrategy {
+        SequenceFormStrategy::from_treeplex_vector(TreeplexVector::from_vec(
+            game.treeplex(Player::Player1),
+            vec![0.125, 0.375, 0.125, 0.375, 0.5, 0.5, 1.0],
+        ))
+    }
+
+    #[test]
+    fn refine_nested_subgames() {
+        let game = nested_commitment_game();
+        let opt = Opt::from_iter([
+            "subgame_solving",
+            "-g",
+            "game",
+            "-b",
+            "u",
+            "-t",
+            "60",
+            "-s",
+            "bnb",
+            "--max_subgame_depth",
+            "1",
+        ]);
+        let blueprint_br = BlueprintBr::new(&game, &nested_blueprint(&game));
+
+        let (leader_strategy, certificate, reports) =
+            refine_strategy(&game, &blueprint_br, None, &opt);
+        assert!(certificate.is_safe());
+        assert_approx_eq!(certificate.blueprint_leader_value, 3.25);
+        assert_approx_eq!(certificate.refined_leader_value, 3.5);
+        assert_approx_eq!(leader_strategy.inner()[0] + leader_strategy.inner()[2], 0.5);
+
+        // Subgame 0 is resolved at the top level, then subgame 1 within it at depth 1.
+        let subgames = reports
+            .iter()
+            .map(|report| (report.subgame_id, report.depth))
+            .collect::<Vec<_>>();
+        assert_eq!(subgames, vec![(0, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn passes_stop_without_improvement() {
+        let game = nested_commitment_game();
+        let output_dir = std::env::temp_dir().join(format!("passes-test-{}", std::process::id()));
+        std::fs::create_dir_all(&output_dir).unwrap();
+        let opt = Opt::from_iter([
+            "subgame_solving",
+            "-g",
+            "game",
+            "-b",
+            "u",
+            "-t",
+            "60",
+            "-s",
+            "bnb",
+            "--max_passes",
+            "3",
+            "--output_dir",
+            output_dir.to_str().unwrap(),
+        ]);
+
+        // The second pass is safe but cannot improve on the first. It is still adopted, and
+        // no third pass is made. The subgames of both passes are reported.
+        let (_, certificate, report) =
+            solve_all_subgames(&game, &nested_blueprint(&game), None, None, &opt);
+        assert!(certificate.is_safe());
+        assert_approx_eq!(certificate.blueprint_leader_value, 3.5);
+        assert_approx_eq!(certificate.refined_leader_value, 3.5);
+        assert_eq!(report.leader_value_by_pass.len(), 3);
+        assert_approx_eq!(report.leader_value_by_pass[0], 3.25);
+        assert_approx_eq!(report.leader_value_by_pass[1], 3.5);
+        assert!(report.subgames.iter().any(|subgame| subgame.pass == 0));
+        assert!(report.subgames.iter().any(|subgame| subgame.pass == 1));
+        assert!(report.subgames.iter().all(|subgame| subgame.pass <= 1));
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+}
@@ -1,24 +1,32 @@
+use crate::solver::SolveStatus;
 use efg_lite::vector::TreeplexVector;
 use efg_lite::strategy::SequenceFormStrategy;
 
-pub struct MIPSolution<'a> {
-    pub leader_strategy: SequenceFormStrategy<'a>,
-    pub follower_strategy: SequenceFormStrategy<'a>,
+pub struct MIPSolution {
+    pub leader_strategy: SequenceFormStrategy,
+    pub follower_strategy: SequenceFormStrategy,
     pub leaf_probabilities: Vec<f64>,
     pub objective_value: f64,
-    pub follower_slack: TreeplexVector<'a>,
+    pub follower_slack: TreeplexVector,
     pub follower_value: Vec<f64>,
+
+    // Relative gap between the objective value and the best bound, as reported by Gurobi.
+    // Zero unless the solver stopped before proving optimality.
+    pub mip_gap: f64,
+
+    // Either optimal, or the best solution found within the time limit.
+    pub status: SolveStatus,
 }
 
-impl<'a> MIPSolution<'a> {
+impl MIPSolution {
     pub fn new(
-        leader_strategy: SequenceFormStrategy<'a>,
-        follower_strategy: SequenceFormStrategy<'a>,
+        leader_strategy: SequenceFormStrategy,
+        follower_strategy: SequenceFormStrategy,
         leaf_probabilities: Vec<f64>,
         objective_value: f64,
-        follower_slack: TreeplexVector<'a>,
+        follower_slack: TreeplexVector,
         follower_value: Vec<f64>,
-    ) -> MIPSolution<'a> {
+    ) -> MIPSolution {
         let solution = MIPSolution {
             leader_strategy,
             follower_strategy,
@@ -26,6 +34,8 @@ impl<'a> MIPSolution<'a> {
             objective_value,
             follower_slack,
             follower_value,
+            mip_gap: 0.0,
+            status: SolveStatus::Optimal,
         };
         // solution.verify();
         solution
@@ -0,0 +1,166 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use crate::solver::SolveStatus;
+
+use efg_lite::game::Player;
+use efg_lite::sse::{BoundedProblem, GameMapper, ValueBound};
+
+use serde::Serialize;
+
+/// Machine-readable summary of a run, written as JSON to the output directory.
+#[derive(Serialize, Debug)]
+pub struct RunReport {
+    pub game_file: PathBuf,
+
+    // Subgames in the order they were resolved, over all passes and depths.
+    pub subgames: Vec<SubgameReport>,
+
+    // Subgames which kept the blueprint in some pass, since there was no usable solution.
+    pub fallback_subgames: Vec<usize>,
+
+    // Leader's value of the blueprint, followed by that after each pass adopted.
+    pub leader_value_by_pass: Vec<f64>,
+
+    pub blueprint_payoffs: Payoffs,
+    pub refined_payoffs: Payoffs,
+    pub safe: bool,
+    pub wall_time: f64,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, Default)]
+pub struct Payoffs {
+    pub leader: f64,
+    pub follower: f64,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubgameStatus {
+    Optimal,
+    TimeLimit,
+    Suboptimal,
+    Infeasible,
+    Error,
+
+    // Solved by an earlier run, and loaded from the run directory.
+    Resumed,
+}
+
+impl SubgameStatus {
+    /// Whether the subgame fell back to the blueprint.
+    pub fn fell_back(self) -> bool {
+        self == SubgameStatus::Infeasible || self == SubgameStatus::Error
+    }
+}
+
+impl From<SolveStatus> for SubgameStatus {
+    fn from(status: SolveStatus) -> SubgameStatus {
+        match status {
+            SolveStatus::Optimal => SubgameStatus::Optimal,
+            SolveStatus::TimeLimit => SubgameStatus::TimeLimit,
+            SolveStatus::Suboptimal => SubgameStatus::Suboptimal,
+            SolveStatus::Infeasible => SubgameStatus::Infeasible,
+            SolveStatus::Error => SubgameStatus::Error,
+        }
+    }
+}
+
+/// Bound on the follower's value at a subgame head, which is an infoset of the input game.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct HeadBound {
+    pub infoset_id: usize,
+    pub lower_bound: Option<f64>,
+    pub upper_bound: Option<f64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SubgameReport {
+    pub subgame_id: usize,
+
+    // Refinement pass (starting from 0), and the depth of the subgame when it was resolved.
+    pub pass: usize,
+    pub depth: usize,
+
+    // Sizes of the skinny game.
+    pub num_leader_sequences: usize,
+    pub num_leader_infosets: usize,
+    pub num_follower_sequences: usize,
+    pub num_follower_infosets: usize,
+    pub num_leaves: usize,
+
+    pub input_mass: f64,
+    pub bounds: Vec<HeadBound>,
+
+    pub status: SubgameStatus,
+    pub fell_back: bool,
+
+    // Not known for subgames which fell back.
+    pub objective_value: Option<f64>,
+
+    // Not known for subgames which were resumed, fell back, or solved for the robust objective.
+    pub mip_gap: Option<f64>,
+
+    // Time taken to build and solve the bounded problem.
+    pub wall_time: f64,
+}
+
+impl SubgameReport {
+    /// Report of a subgame with the sizes and bounds of its bounded problem. Bounds are
+    /// given in terms of the input game using the game mapper. The pass and depth are
+    /// left to the caller.
+    pub fn new(
+        subgame_id: usize,
+        bounded_problem: &BoundedProblem,
+        game_mapper: &GameMapper,
+        status: SubgameStatus,
+        objective_value: Option<f64>,
+        mip_gap: Option<f64>,
+        wall_time: f64,
+    ) -> SubgameReport {
+        let game = &bounded_problem.game;
+        let bounds = bounded_problem
+            .bounds
+            .iter()
+            .filter_map(|(skinny_infoset_id, bound)| {
+                let (lower_bound, upper_bound) = match bound {
+                    ValueBound::LowerBound(value) => (Some(*value), None),
+                    ValueBound::UpperBound(value) => (None, Some(*value)),
+                    ValueBound::None => return None,
+                };
+                Some(HeadBound {
+                    infoset_id: game_mapper
+                        .mapper_follower
+                        .skinny_infoset_to_infoset(*skinny_infoset_id),
+                    lower_bound,
+                    upper_bound,
+                })
+            })
+            .collect::<Vec<HeadBound>>();
+        SubgameReport {
+            subgame_id,
+            pass: 0,
+            depth: 0,
+            num_leader_sequences: game.treeplex(Player::Player1).num_sequences(),
+            num_leader_infosets: game.treeplex(Player::Player1).num_infosets(),
+            num_follower_sequences: game.treeplex(Player::Player2).num_sequences(),
+            num_follower_infosets: game.treeplex(Player::Player2).num_infosets(),
+            num_leaves: game.payoff_matrix().entries.len(),
+            input_mass: bounded_problem.input_mass,
+            bounds,
+            status,
+            fell_back: status.fell_back(),
+            objective_value,
+            mip_gap,
+            wall_time,
+        }
+    }
+}
+
+impl RunReport {
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let file_writer = File::create(path)?;
+        serde_json::to_writer_pretty(file_writer, self)?;
+        Ok(())
+    }
+}
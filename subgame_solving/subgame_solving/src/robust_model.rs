@@ -0,0 +1,455 @@
+use crate::solver::SolveStatus;
+
+use efg_lite::game::Player;
+use efg_lite::sse::{BoundedProblem, ValueBound};
+use efg_lite::strategy::SequenceFormStrategy;
+use efg_lite::treeplex::SequenceId;
+use efg_lite::vector::TreeplexVector;
+
+use embedded_solvers::{ConstraintSense, Model, ObjectiveSense, VariableType, INFINITY};
+
+/// MILP maximizing the leader's worst-case payoff against an epsilon-rational follower, who may
+/// choose any action whose value (assuming optimal play afterwards) is strictly within epsilon
+/// of the best one at each infoset. This matches `efg_lite::sse::worst_case_leader_value`.
+///
+/// For a follower sequence a with parent infoset I, we write
+///     V(a) = sum_{z at a} u2(z) c(z) x(seq_pl1(z)) + sum_{J after a} v(J),
+///     W(a) = sum_{z at a} u1(z) c(z) x(seq_pl1(z)) + sum_{J after a} w(J),
+/// for the follower's optimal value and the leader's worst-case value of a. Then
+///
+/// max  sum_{z at empty, z in trunk} u1(z) c(z) x(seq_pl1(z)) + sum_{J in trunk} w(J)
+/// x is a sequence form strategy of the leader, ------------------------------------(A)
+/// v(I) >= V(a), -------------------------------------------------------------------(B)
+/// v(I) <= V(a) + M(1 - b(a)), sum_{a in I} b(a) = 1, --------------------------------(C)
+/// V(a) - v(I) + epsilon <= M e(a), -------------------------------------------------(D)
+/// e(a) >= b(a), --------------------------------------------------------------------(E)
+/// w(I) <= W(a) + M(1 - e(a)), ------------------------------------------------------(F)
+/// v(I) satisfies the bounds of the problem, -----------------------------------------(G)
+///
+/// where b(a) selects a best action, so that (B, C) make v(I) the follower's optimal value,
+/// and e(a) is forced to 1 for all epsilon-optimal actions by (D). The objective only counts
+/// infosets following the empty sequence whose subtree contains leaves within the trunk.
+/// ===============================================================================================
+/// The numbering we will use for variables is
+///
+/// Sequence form representation of leader (x): [0,..., |S1|)
+/// Optimal value of follower infosets (v): [|S1|,..., |S1|+|I2|)
+/// Worst-case value of leader at follower infosets (w): [|S1|+|I2|,..., |S1|+2|I2|)
+/// Best response indicators (b): [|S1|+2|I2|,..., |S1|+2|I2|+|S2|)
+/// Epsilon-optimal indicators (e): [|S1|+2|I2|+|S2|,..., |S1|+2|I2|+2|S2|)
+/// ================================================================================================
+pub struct RobustModel<'a> {
+    problem: &'a BoundedProblem,
+    epsilon: f64,
+    model: Model,
+}
+
+pub struct RobustSolution {
+    pub leader_strategy: SequenceFormStrategy,
+    pub worst_case_value: f64,
+
+    // Either optimal, or the best solution found within the time limit.
+    pub status: SolveStatus,
+}
+
+/// Ranges of the values of follower sequences and infosets, given that the leader's sequence
+/// form variables lie in [0, 1].
+struct ValueRanges {
+    seq_lower: Vec<f64>,
+    seq_upper: Vec<f64>,
+    infoset_lower: Vec<f64>,
+    infoset_upper: Vec<f64>,
+}
+
+impl<'a> RobustModel<'a> {
+    pub fn new(problem: &'a BoundedProblem, epsilon: f64) -> RobustModel<'a> {
+        assert!(epsilon > 0.0, "Robust commitment requires a positive epsilon");
+        let mut robust_model = RobustModel {
+            problem,
+            epsilon,
+            model: Model::new("Skinny-robust-model", ObjectiveSense::Maximize),
+        };
+
+        let follower_ranges = robust_model.value_ranges(Player::Player2);
+        let leader_ranges = robust_model.value_ranges(Player::Player1);
+
+        println!("Making variables");
+        robust_model.make_variables(&leader_ranges);
+
+        println!("Making sequence form constraints (A)");
+        robust_model.make_sequence_form_constraints_pl1();
+
+        println!("Making follower value constraints (B, C, D, E)");
+        robust_model.make_follower_value_constraints(&follower_ranges);
+
+        println!("Making worst-case constraints (F)");
+        robust_model.make_worst_case_constraints(&leader_ranges);
+
+        println!("Making bounds constraints (G)");
+        robust_model.make_bounds_constraints();
+
+        robust_model
+    }
+
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+
+    pub fn get_seq_form_index_pl1(&self, sequence_id: SequenceId) -> usize {
+        sequence_id
+    }
+
+    pub fn get_follower_value_index(&self, infoset_id: usize) -> usize {
+        infoset_id + self.num_sequences(Player::Player1)
+    }
+
+    pub fn get_worst_case_value_index(&self, infoset_id: usize) -> usize {
+        infoset_id + self.num_sequences(Player::Player1) + self.num_infosets_pl2()
+    }
+
+    pub fn get_best_response_index(&self, sequence_id: SequenceId) -> usize {
+        sequence_id + self.num_sequences(Player::Player1) + 2 * self.num_infosets_pl2()
+    }
+
+    pub fn get_epsilon_optimal_index(&self, sequence_id: SequenceId) -> usize {
+        self.get_best_response_index(sequence_id) + self.num_sequences(Player::Player2)
+    }
+
+    /// Extracts the leader's strategy from the values of all variables in a solution.
+    pub fn solution(&self, values: &[f64], objective_value: f64) -> RobustSolution {
+        let treeplex = self.problem.game.treeplex(Player::Player1);
+        let start = self.get_seq_form_index_pl1(0);
+        let treeplex_vector = TreeplexVector::from_vec(
+            treeplex,
+            values[start..start + treeplex.num_sequences()].to_vec(),
+        );
+        RobustSolution {
+            leader_strategy: SequenceFormStrategy::from_treeplex_vector(treeplex_vector),
+            worst_case_value: objective_value,
+            status: SolveStatus::Optimal,
+        }
+    }
+
+    fn num_sequences(&self, player: Player) -> usize {
+        self.problem.game.treeplex(player).num_sequences()
+    }
+
+    fn num_infosets_pl2(&self) -> usize {
+        self.problem.game.treeplex(Player::Player2).num_infosets()
+    }
+
+    /// Payoffs of `player` at leaves at a follower sequence, as coefficients of x. Leaves
+    /// sharing a leader sequence (e.g., due to chance) are aggregated.
+    fn leaf_coeffs(&self, player: Player, sequence_id: SequenceId) -> Vec<(usize, f64)> {
+        let mut coeffs = Vec::<(usize, f64)>::new();
+        for leaf_index in self
+            .problem
+            .game_tools
+            .leaf_indices_at_sequence(Player::Player2, sequence_id)
+        {
+            let leaf = self.problem.game.payoff_matrix().entries[leaf_index];
+            let payoff = match player {
+                Player::Player1 => leaf.payoff_pl1,
+                Player::Player2 => leaf.payoff_pl2,
+            };
+            let index = self.get_seq_form_index_pl1(leaf.seq_pl1);
+            match coeffs.iter_mut().find(|(x, _)| *x == index) {
+                Some((_, coeff)) => *coeff += payoff * leaf.chance_factor,
+                None => coeffs.push((index, payoff * leaf.chance_factor)),
+            }
+        }
+        coeffs
+    }
+
+    /// Coefficients of V(a) (for the follower) or W(a) (for the leader).
+    fn seq_value_coeffs(&self, player: Player, sequence_id: SequenceId) -> Vec<(usize, f64)> {
+        let mut coeffs = self.leaf_coeffs(player, sequence_id);
+        for child_infoset_id in self
+            .problem
+            .treeplex_follower_tools
+            .seq_to_infoset_range(sequence_id)
+        {
+            coeffs.push(match player {
+                Player::Player1 => (self.get_worst_case_value_index(child_infoset_id), 1.0),
+                Player::Player2 => (self.get_follower_value_index(child_infoset_id), 1.0),
+            });
+        }
+        coeffs
+    }
+
+    /// Lower and upper bounds on V(a) and v(I) (for the follower) or W(a) and w(I) (for the
+    /// leader), computed bottom-up as in `SseModel::compute_big_m`. The follower's values are
+    /// tightened by the bounds of the problem.
+    fn value_ranges(&self, player: Player) -> ValueRanges {
+        let treeplex = self.problem.game.treeplex(Player::Player2);
+        let mut value_bounds = vec![ValueBound::None; treeplex.num_infosets()];
+        if player == Player::Player2 {
+            for (infoset_id, value_bound) in self.problem.bounds.iter() {
+                value_bounds[*infoset_id] = *value_bound;
+            }
+        }
+
+        let mut ranges = ValueRanges {
+            seq_lower: vec![0f64; treeplex.num_sequences()],
+            seq_upper: vec![0f64; treeplex.num_sequences()],
+            infoset_lower: vec![0f64; treeplex.num_infosets()],
+            infoset_upper: vec![0f64; treeplex.num_infosets()],
+        };
+        for (infoset_id, infoset) in treeplex.infosets().iter().enumerate() {
+            for sequence_id in infoset.start_sequence..=infoset.end_sequence {
+                for (_, payoff) in self.leaf_coeffs(player, sequence_id) {
+                    ranges.seq_lower[sequence_id] += payoff.min(0.0);
+                    ranges.seq_upper[sequence_id] += payoff.max(0.0);
+                }
+            }
+
+            // The follower's value is the maximum over all sequences, while the leader's
+            // worst-case value is the minimum over some of them.
+            let sequences = infoset.start_sequence..=infoset.end_sequence;
+            let upper = sequences
+                .clone()
+                .map(|sequence_id| ranges.seq_upper[sequence_id])
+                .fold(f64::NEG_INFINITY, f64::max);
+            let lower = match player {
+                Player::Player1 => sequences
+                    .map(|sequence_id| ranges.seq_lower[sequence_id])
+                    .fold(f64::INFINITY, f64::min),
+                Player::Player2 => sequences
+                    .map(|sequence_id| ranges.seq_lower[sequence_id])
+                    .fold(f64::NEG_INFINITY, f64::max),
+            };
+            let (lower, upper) = match value_bounds[infoset_id] {
+                ValueBound::LowerBound(lb) => (lower.max(lb), upper),
+                ValueBound::UpperBound(ub) => (lower, upper.min(ub)),
+                ValueBound::None => (lower, upper),
+            };
+            ranges.infoset_lower[infoset_id] = lower;
+            ranges.infoset_upper[infoset_id] = upper;
+
+            // Infosets are ordered bottom-up, so all child infosets of a sequence are
+            // processed before its parent infoset.
+            ranges.seq_lower[infoset.parent_sequence] += lower;
+            ranges.seq_upper[infoset.parent_sequence] += upper;
+        }
+        ranges
+    }
+
+    fn make_variables(&mut self, leader_ranges: &ValueRanges) {
+        for leader_sequence in 0..self.num_sequences(Player::Player1) {
+            self.model.add_variable(
+                &format!("leader_sequence_form_{}", leader_sequence),
+                VariableType::Continuous,
+                0f64, // Objective is filled in below.
+                0f64, // Sequence probability must be >= 0
+                1f64, // Sequence probability may not be more than 1 (not really required in theory).
+            );
+        }
+        for follower_infoset in 0..self.num_infosets_pl2() {
+            self.model.add_variable(
+                &format!("follower_infoset_value_{}", follower_infoset),
+                VariableType::Continuous,
+                0f64,      // No objective.
+                -INFINITY, // Values can range from -INF to INF
+                INFINITY,
+            );
+        }
+        for follower_infoset in 0..self.num_infosets_pl2() {
+            self.model.add_variable(
+                &format!("leader_worst_case_value_{}", follower_infoset),
+                VariableType::Continuous,
+                0f64,
+                leader_ranges.infoset_lower[follower_infoset],
+                leader_ranges.infoset_upper[follower_infoset],
+            );
+        }
+        let empty_sequence_id = self.problem.game.treeplex(Player::Player2).empty_sequence_id();
+        for name in ["follower_best_response", "follower_epsilon_optimal"].iter() {
+            for follower_sequence in 0..self.num_sequences(Player::Player2) {
+                // The empty sequence has no parent infoset, and its indicators are unused.
+                let upper_bound = match follower_sequence == empty_sequence_id {
+                    true => 0f64,
+                    false => 1f64,
+                };
+                self.model.add_variable(
+                    &format!("{}_{}", name, follower_sequence),
+                    VariableType::Binary,
+                    0f64,
+                    0f64,
+                    upper_bound,
+                );
+            }
+        }
+
+        // Objective: leaves at the empty sequence and infosets right below it, within the trunk.
+        let treeplex_tools = &self.problem.treeplex_follower_tools;
+        let empty_sequence_id = self.problem.game.treeplex(Player::Player2).empty_sequence_id();
+        let within_trunk = |leaf_index: &usize| self.problem.leaves_within_trunk.contains(leaf_index);
+        let mut objective_coeffs = vec![0f64; self.model.num_variables()];
+        for leaf_index in self
+            .problem
+            .game_tools
+            .leaf_indices_at_sequence(Player::Player2, empty_sequence_id)
+            .iter()
+            .filter(|leaf_index| within_trunk(leaf_index))
+        {
+            let leaf = self.problem.game.payoff_matrix().entries[*leaf_index];
+            objective_coeffs[self.get_seq_form_index_pl1(leaf.seq_pl1)] +=
+                leaf.payoff_pl1 * leaf.chance_factor;
+        }
+        for infoset_id in treeplex_tools.seq_to_infoset_range(empty_sequence_id) {
+            if self
+                .problem
+                .game_tools
+                .leaf_indices_at_or_under_infoset(Player::Player2, treeplex_tools, infoset_id)
+                .iter()
+                .any(within_trunk)
+            {
+                objective_coeffs[self.get_worst_case_value_index(infoset_id)] = 1.0;
+            }
+        }
+        for (variable, objective_coeff) in objective_coeffs.into_iter().enumerate() {
+            self.model.set_objective_coeff(variable, objective_coeff);
+        }
+    }
+
+    /// Constraints (A)
+    fn make_sequence_form_constraints_pl1(&mut self) {
+        let treeplex = self.problem.game.treeplex(Player::Player1);
+        let empty_sequence_id = treeplex.empty_sequence_id();
+
+        self.model.add_constraint(
+            "seq_form_constraints_empty_seq_pl1",
+            vec![(self.get_seq_form_index_pl1(empty_sequence_id), 1.0)],
+            ConstraintSense::Equal,
+            1f64,
+        );
+
+        for (infoset_id, infoset) in treeplex.infosets().iter().enumerate() {
+            let mut coeffs = vec![(self.get_seq_form_index_pl1(infoset.parent_sequence), 1.0)];
+            for sequence_id in infoset.start_sequence..=infoset.end_sequence {
+                coeffs.push((self.get_seq_form_index_pl1(sequence_id), -1.0));
+            }
+            self.model.add_constraint(
+                &format!("seq_form_constraints_{}_pl1", infoset_id),
+                coeffs,
+                ConstraintSense::Equal,
+                0f64,
+            );
+        }
+    }
+
+    /// Constraints (B), (C), (D) and (E)
+    fn make_follower_value_constraints(&mut self, follower_ranges: &ValueRanges) {
+        let treeplex = self.problem.game.treeplex(Player::Player2);
+        for (infoset_id, infoset) in treeplex.infosets().iter().enumerate() {
+            let value_index = self.get_follower_value_index(infoset_id);
+            let mut best_response_coeffs = vec![];
+            for sequence_id in infoset.start_sequence..=infoset.end_sequence {
+                let seq_value_coeffs = self.seq_value_coeffs(Player::Player2, sequence_id);
+                let best_response_index = self.get_best_response_index(sequence_id);
+                let epsilon_optimal_index = self.get_epsilon_optimal_index(sequence_id);
+                best_response_coeffs.push((best_response_index, 1.0));
+
+                // (B) v(I) - V(a) >= 0
+                let mut coeffs = vec![(value_index, 1.0)];
+                coeffs.extend(seq_value_coeffs.iter().map(|(index, coeff)| (*index, -coeff)));
+                self.model.add_constraint(
+                    &format!("follower_value_upper_{}", sequence_id),
+                    coeffs,
+                    ConstraintSense::GreaterEqual,
+                    0f64,
+                );
+
+                // (C) v(I) - V(a) + M b(a) <= M
+                let big_m = (follower_ranges.infoset_upper[infoset_id]
+                    - follower_ranges.seq_lower[sequence_id])
+                    .max(0.0);
+                let mut coeffs = vec![(value_index, 1.0), (best_response_index, big_m)];
+                coeffs.extend(seq_value_coeffs.iter().map(|(index, coeff)| (*index, -coeff)));
+                self.model.add_constraint(
+                    &format!("follower_value_lower_{}", sequence_id),
+                    coeffs,
+                    ConstraintSense::LessEqual,
+                    big_m,
+                );
+
+                // (D) V(a) - v(I) - M e(a) <= -epsilon
+                let big_m = (follower_ranges.seq_upper[sequence_id]
+                    - follower_ranges.infoset_lower[infoset_id]
+                    + self.epsilon)
+                    .max(0.0);
+                let mut coeffs = seq_value_coeffs;
+                coeffs.push((value_index, -1.0));
+                coeffs.push((epsilon_optimal_index, -big_m));
+                self.model.add_constraint(
+                    &format!("epsilon_optimal_{}", sequence_id),
+                    coeffs,
+                    ConstraintSense::LessEqual,
+                    -self.epsilon,
+                );
+
+                // (E) e(a) - b(a) >= 0
+                self.model.add_constraint(
+                    &format!("best_response_epsilon_optimal_{}", sequence_id),
+                    vec![(epsilon_optimal_index, 1.0), (best_response_index, -1.0)],
+                    ConstraintSense::GreaterEqual,
+                    0f64,
+                );
+            }
+
+            self.model.add_constraint(
+                &format!("best_response_{}", infoset_id),
+                best_response_coeffs,
+                ConstraintSense::Equal,
+                1f64,
+            );
+        }
+    }
+
+    /// Constraints (F)
+    fn make_worst_case_constraints(&mut self, leader_ranges: &ValueRanges) {
+        let treeplex = self.problem.game.treeplex(Player::Player2);
+        for (infoset_id, infoset) in treeplex.infosets().iter().enumerate() {
+            for sequence_id in infoset.start_sequence..=infoset.end_sequence {
+                // w(I) - W(a) + M e(a) <= M
+                let big_m = (leader_ranges.infoset_upper[infoset_id]
+                    - leader_ranges.seq_lower[sequence_id])
+                    .max(0.0);
+                let mut coeffs = vec![
+                    (self.get_worst_case_value_index(infoset_id), 1.0),
+                    (self.get_epsilon_optimal_index(sequence_id), big_m),
+                ];
+                coeffs.extend(
+                    self.seq_value_coeffs(Player::Player1, sequence_id)
+                        .into_iter()
+                        .map(|(index, coeff)| (index, -coeff)),
+                );
+                self.model.add_constraint(
+                    &format!("worst_case_{}", sequence_id),
+                    coeffs,
+                    ConstraintSense::LessEqual,
+                    big_m,
+                );
+            }
+        }
+    }
+
+    /// Constraints (G)
+    fn make_bounds_constraints(&mut self) {
+        for (infoset_id, value_bound) in self.problem.bounds.iter() {
+            let coeffs = vec![(self.get_follower_value_index(*infoset_id), 1.0)];
+            let name = format!("value_bounds_{}", infoset_id);
+            match value_bound {
+                ValueBound::LowerBound(lb) => {
+                    self.model
+                        .add_constraint(&name, coeffs, ConstraintSense::GreaterEqual, *lb);
+                }
+                ValueBound::UpperBound(ub) => {
+                    self.model
+                        .add_constraint(&name, coeffs, ConstraintSense::LessEqual, *ub);
+                }
+                ValueBound::None => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::robust_model::{RobustModel, RobustSolution};
+use crate::solver::{SolveStatus, SolverConfig};
+
+use efg_lite::sse::BoundedProblem;
+
+use embedded_solvers::{LpSolution, LpStatus, Model, VariableType};
+
+#[cfg(feature = "gurobi")]
+use crate::gurobi_solver;
+#[cfg(feature = "gurobi")]
+use optimizers::GurobiModel;
+
+use log::{info, warn};
+
+use std::time::Instant;
+
+/// Binary variables within this distance of 0 or 1 are considered integral.
+const INTEGRALITY_TOLERANCE: f64 = 1e-6;
+
+/// Nodes whose LP bound does not exceed the incumbent by more than this are pruned.
+const PRUNING_TOLERANCE: f64 = 1e-9;
+
+/// Solves the MILP built by `RobustModel`, using Gurobi or a plain depth-first branch and bound
+/// over its binary variables with the embedded simplex solver. Unlike `BranchAndBoundSolver`,
+/// there is no structure to exploit here beyond branching on the most fractional indicator.
+pub struct RobustSolver<'a> {
+    robust_model: RobustModel<'a>,
+    time_limit: f64,
+    #[cfg(feature = "gurobi")]
+    log_file: String,
+    #[cfg(feature = "gurobi")]
+    threads: usize,
+}
+
+impl<'a> RobustSolver<'a> {
+    pub fn new(
+        problem: &'a BoundedProblem,
+        epsilon: f64,
+        solver_config: &SolverConfig,
+    ) -> RobustSolver<'a> {
+        RobustSolver {
+            robust_model: RobustModel::new(problem, epsilon),
+            time_limit: solver_config.time_limit,
+            #[cfg(feature = "gurobi")]
+            log_file: solver_config.log_file.clone(),
+            #[cfg(feature = "gurobi")]
+            threads: solver_config.threads,
+        }
+    }
+
+    /// Solves the model, returning the status of the solver if there is no usable solution.
+    #[cfg(feature = "gurobi")]
+    pub fn solve_with_gurobi(&self) -> Result<RobustSolution, SolveStatus> {
+        let gurobi_model = GurobiModel::new(self.robust_model.model(), &self.log_file);
+        gurobi_model.set_double_param("TimeLimit", self.time_limit);
+        gurobi_model.set_int_param("Threads", self.threads as i32);
+        gurobi_model.set_double_param("FeasibilityTol", 1e-8f64);
+        gurobi_model.set_double_param("IntFeasTol", 1e-8f64);
+        let status = gurobi_solver::optimize(&gurobi_model);
+        if !status.has_solution() {
+            return Err(status);
+        }
+        let mut solution = self
+            .robust_model
+            .solution(&gurobi_model.values(), gurobi_model.objective_value());
+        solution.status = status;
+        Ok(solution)
+    }
+
+    pub fn solve_with_branch_and_bound(&self) -> Result<RobustSolution, SolveStatus> {
+        let start_time = Instant::now();
+        let model = self.robust_model.model();
+        let binary_variables = (0..model.num_variables())
+            .filter(|x| model.variables()[*x].variable_type == VariableType::Binary)
+            .collect::<Vec<usize>>();
+
+        let mut incumbent: Option<LpSolution> = None;
+        let mut stack = vec![Vec::<(usize, f64)>::new()];
+        let mut num_nodes = 0;
+        let mut num_failed_nodes = 0;
+        let mut time_limit_reached = false;
+        while let Some(fixings) = stack.pop() {
+            if start_time.elapsed().as_secs_f64() > self.time_limit {
+                warn!("Time limit reached, {} open nodes remaining", stack.len());
+                time_limit_reached = true;
+                break;
+            }
+            num_nodes += 1;
+
+            // As in `BranchAndBoundSolver`, only nodes proven to be infeasible are pruned.
+            let lp_solution = Self::solve_node(model, &fixings);
+            match lp_solution.status {
+                LpStatus::Optimal => {}
+                LpStatus::Infeasible => continue,
+                status => {
+                    warn!("LP relaxation of a node failed with status {:?}", status);
+                    num_failed_nodes += 1;
+                    continue;
+                }
+            }
+            let incumbent_value = incumbent
+                .as_ref()
+                .map_or(f64::NEG_INFINITY, |x| x.objective_value);
+            if lp_solution.objective_value <= incumbent_value + PRUNING_TOLERANCE {
+                continue;
+            }
+
+            let most_fractional = binary_variables
+                .iter()
+                .filter(|x| {
+                    let value = lp_solution.values[**x];
+                    value > INTEGRALITY_TOLERANCE && value < 1.0 - INTEGRALITY_TOLERANCE
+                })
+                .min_by(|a, b| {
+                    (lp_solution.values[**a] - 0.5)
+                        .abs()
+                        .partial_cmp(&(lp_solution.values[**b] - 0.5).abs())
+                        .unwrap()
+                });
+            match most_fractional {
+                Some(variable) => {
+                    // Explore the closer integer first. The stack is last in, first out.
+                    let mut children = [0.0, 1.0];
+                    if lp_solution.values[*variable] > 0.5 {
+                        children.reverse();
+                    }
+                    for value in children.iter().rev() {
+                        let mut child_fixings = fixings.clone();
+                        child_fixings.push((*variable, *value));
+                        stack.push(child_fixings);
+                    }
+                }
+                None => incumbent = Some(lp_solution),
+            }
+        }
+
+        let incumbent = match incumbent {
+            Some(incumbent) => incumbent,
+            None if time_limit_reached || num_failed_nodes > 0 => return Err(SolveStatus::Error),
+            None => return Err(SolveStatus::Infeasible),
+        };
+        info!(
+            "Branch and bound explored {} nodes ({} failed) in {:?}s, objective {:?}",
+            num_nodes,
+            num_failed_nodes,
+            start_time.elapsed().as_secs_f64(),
+            incumbent.objective_value
+        );
+        let mut solution = self
+            .robust_model
+            .solution(&incumbent.values, incumbent.objective_value);
+        if time_limit_reached {
+            solution.status = SolveStatus::TimeLimit;
+        } else if num_failed_nodes > 0 {
+            solution.status = SolveStatus::Suboptimal;
+        }
+        Ok(solution)
+    }
+
+    fn solve_node(model: &Model, fixings: &[(usize, f64)]) -> LpSolution {
+        let mut model = model.clone();
+        for (variable, value) in fixings.iter() {
+            model.set_bounds(*variable, *value, *value);
+        }
+        model.solve_relaxation()
+    }
+}
+
+#[cfg(test)]
+pub mod test_fixtures {
+    use super::RobustSolver;
+    use crate::branch_and_bound_solver::test_fixtures::commitment_problem;
+    use crate::branch_and_bound_solver::BranchingRule;
+    use crate::solver::SolverConfig;
+    use crate::sse_model::OnOffEncoding;
+    use efg_lite::sse::worst_case_leader_value;
+    use efg_lite::strategy::SequenceFormStrategy;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn robust_commitment_game() {
+        // The follower is only guaranteed to play Right if it is better than Left by at least
+        // epsilon, so the leader plays Up with probability (1 - epsilon) / 2 instead of 0.5.
+        let problem = commitment_problem();
+        let solver_config = SolverConfig {
+            time_limit: f64::INFINITY,
+            on_off_encoding: OnOffEncoding::BigM,
+            branching_rule: BranchingRule::InfosetTopDown,
+            log_file: String::from("TestLog"),
+            threads: 0,
+        };
+        let solver = RobustSolver::new(&problem, 0.1, &solver_config);
+        let solution = solver.solve_with_branch_and_bound().unwrap();
+        assert_approx_eq!(solution.worst_case_value, 3.45);
+        assert_approx_eq!(solution.leader_strategy.inner()[0], 0.45);
+
+        // The SSE commitment is not robust, since the follower is indifferent.
+        let leader_strategy = |prob_up: f64| {
+            let mut vector = solution.leader_strategy.inner().clone();
+            vector[0] = prob_up;
+            vector[1] = 1.0 - prob_up;
+            SequenceFormStrategy::from_treeplex_vector(vector)
+        };
+        assert_approx_eq!(worst_case_leader_value(&problem.game, &leader_strategy(0.5), 0.1), 1.5);
+        assert_approx_eq!(worst_case_leader_value(&problem.game, &leader_strategy(0.4), 0.1), 3.4);
+    }
+}
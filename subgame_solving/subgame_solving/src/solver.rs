@@ -1,12 +1,57 @@
+use crate::branch_and_bound_solver::BranchingRule;
 use crate::mip_solution::MIPSolution;
+use crate::sse_model::OnOffEncoding;
 use efg_lite::sse::BoundedProblem;
 
+/// Outcome of solving a MILP.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SolveStatus {
+    Optimal,
+
+    /// The time limit was reached after finding a feasible solution, which may be suboptimal.
+    TimeLimit,
+
+    /// A feasible solution was found, but parts of the search could not be completed, e.g.,
+    /// due to numerical trouble, so it may be suboptimal.
+    Suboptimal,
+
+    Infeasible,
+
+    /// Any other failure, e.g., numerical trouble or reaching the time limit before finding
+    /// a feasible solution.
+    Error,
+}
+
+impl SolveStatus {
+    /// Whether the solver has a solution we may use.
+    #[cfg(feature = "gurobi")]
+    pub fn has_solution(self) -> bool {
+        match self {
+            SolveStatus::Optimal | SolveStatus::TimeLimit | SolveStatus::Suboptimal => true,
+            SolveStatus::Infeasible | SolveStatus::Error => false,
+        }
+    }
+}
+
 pub trait Solver<'a> {
     fn new(problem: &'a BoundedProblem, solver_config: &SolverConfig) -> Self;
-    fn solve(&self);
-    fn get_solution(&self) -> MIPSolution<'a>;
+    fn solve(&self) -> SolveStatus;
+
+    /// The best solution found, or the status of the solver if there is none.
+    fn get_solution(&self) -> Result<MIPSolution, SolveStatus>;
 }
 
 pub struct SolverConfig {
     pub time_limit: f64,
+
+    // Encoding of the on-off constraints (20).
+    pub on_off_encoding: OnOffEncoding,
+
+    // Only used by the branch and bound solver.
+    pub branching_rule: BranchingRule,
+
+    // Log file of the Gurobi environment, and the number of threads it may use (0 leaves
+    // this to Gurobi). Each worker solving subgames in parallel has its own environment.
+    pub log_file: String,
+    pub threads: usize,
 }
\ No newline at end of file
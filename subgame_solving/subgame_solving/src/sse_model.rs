@@ -0,0 +1,494 @@
+use crate::mip_solution::MIPSolution;
+
+use efg_lite::game::Player;
+use efg_lite::sse::{BoundedProblem, ValueBound};
+use efg_lite::strategy::SequenceFormStrategy;
+use efg_lite::treeplex::SequenceId;
+use efg_lite::vector::TreeplexVector;
+
+use embedded_solvers::{ConstraintSense, Model, ObjectiveSense, VariableType, INFINITY};
+
+/// How the on-off constraints (20), which force the slack of sequences played by the
+/// follower to zero, are encoded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OnOffEncoding {
+    /// slack + M * x2 <= M, with the per-sequence M given by `SseModel::big_m`.
+    BigM,
+
+    /// x2 = 1 -> slack = 0.
+    Indicator,
+
+    /// SOS1 constraint over the pair (slack, x2).
+    Sos1,
+}
+
+/// The MILP of Bosansky and Cermak for a `BoundedProblem`, built once and handed to
+/// whichever backend (Gurobi, CBC or the embedded branch and bound) solves it.
+///
+/// Variables (columns) are ordered in the order
+///
+/// |L| = num_leaves
+/// |S1| = num_sequences (leader)
+/// |S2| = num_sequences (follower)
+/// |I2| = num_infosets (follower)
+///
+/// Probability of reaching leaves: [0,..., |L|) --- Technically we only need leaf variables within the (follower's) trunk.
+/// Number of slack variables for follower: [|L|,...|L|+|S2|) --- Technically we do not need one for the empty sequence, but w/e.
+/// Value of information sets for follower: [|L|+|S2|,...|L|+|S2|+|I2|)
+/// Sequence form representation of leader: [|L|+|S2|+|I2|,...|L|+|S1|+|S2|+|I2|)
+/// Sequence form representation of the follower: [|L|+|S1|+|S2|+|I2|,...|L|+|S1|+2|S2|+|I2|)
+pub struct SseModel<'a> {
+    problem: &'a BoundedProblem,
+    model: Model,
+
+    // Big M constant of the on-off constraint for each follower sequence.
+    big_m: Vec<f64>,
+}
+
+impl<'a> SseModel<'a> {
+    pub fn new(problem: &'a BoundedProblem, on_off_encoding: OnOffEncoding) -> SseModel<'a> {
+        let mut sse_model = SseModel {
+            problem,
+            model: Model::new("Skinny-sse-model", ObjectiveSense::Maximize),
+            big_m: vec![],
+        };
+        sse_model.big_m = sse_model.compute_big_m();
+
+        println!(
+            "Num sequences P1 {:?}",
+            problem.game.treeplex(Player::Player1).num_sequences()
+        );
+        println!(
+            "Num sequences P2 {:?}",
+            problem.game.treeplex(Player::Player2).num_sequences()
+        );
+        println!(
+            "Num infosets P1 {:?}",
+            problem.game.treeplex(Player::Player1).num_infosets()
+        );
+        println!(
+            "Num infosets P2 {:?}",
+            problem.game.treeplex(Player::Player2).num_infosets()
+        );
+
+        println!("Making variables");
+        sse_model.make_variables();
+
+        println!("Making slack constraints");
+        sse_model.make_slack_constraints();
+
+        println!("Making sequence form constraints");
+        sse_model.make_sequence_form_constraints(Player::Player1);
+        sse_model.make_sequence_form_constraints(Player::Player2);
+
+        println!("Making on-off constraints");
+        sse_model.make_on_off_constraints(on_off_encoding);
+
+        println!("Making leaf max prob constraints");
+        sse_model.make_leaf_max_prob_constraints();
+
+        println!("Making leaf sum prob constraints");
+        sse_model.make_leaf_sum_prob_constraints();
+
+        println!("Making bound constraints");
+        sse_model.make_bounds_constraints();
+
+        sse_model
+    }
+
+    pub fn problem(&self) -> &'a BoundedProblem {
+        self.problem
+    }
+
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+
+    pub fn get_leaf_prob_index(&self, leaf_index: usize) -> usize {
+        leaf_index
+    }
+
+    pub fn get_value_slack_index(&self, sequence_id: SequenceId) -> usize {
+        sequence_id + self.problem.leaves_within_trunk.len()
+    }
+
+    pub fn get_value_infoset_index(&self, infoset_id: usize) -> usize {
+        infoset_id
+            + self.problem.leaves_within_trunk.len()
+            + self.problem.game.treeplex(Player::Player2).num_sequences()
+    }
+
+    pub fn get_seq_form_index(&self, player: Player, sequence_id: SequenceId) -> usize {
+        match player {
+            Player::Player1 => self.get_seq_form_index_pl1(sequence_id),
+            Player::Player2 => self.get_seq_form_index_pl2(sequence_id),
+        }
+    }
+
+    pub fn get_seq_form_index_pl1(&self, sequence_id: SequenceId) -> usize {
+        sequence_id
+            + self.problem.leaves_within_trunk.len()
+            + self.problem.game.treeplex(Player::Player2).num_sequences()
+            + self.problem.game.treeplex(Player::Player2).num_infosets()
+    }
+
+    pub fn get_seq_form_index_pl2(&self, sequence_id: SequenceId) -> usize {
+        sequence_id
+            + self.problem.leaves_within_trunk.len()
+            + self.problem.game.treeplex(Player::Player2).num_sequences()
+            + self.problem.game.treeplex(Player::Player2).num_infosets()
+            + self.problem.game.treeplex(Player::Player1).num_sequences()
+    }
+
+    pub fn big_m(&self, sequence_id: SequenceId) -> f64 {
+        self.big_m[sequence_id]
+    }
+
+    /// Computes the smallest valid big M for each follower sequence, i.e., an upper bound
+    /// on its slack. The slack of a sequence is the value of its parent infoset minus the
+    /// value of the sequence itself, where the value of a sequence is the sum of the
+    /// (chance and leader weighted) payoffs of leaves at the sequence and the values of
+    /// its child infosets.
+    ///
+    /// Since the leader's sequence form variables lie in [0, 1], each leaf contributes
+    /// between min(0, payoff) and max(0, payoff). The value of an infoset is the maximum
+    /// over its sequences, so lower and upper bounds on sequence values propagate up to
+    /// infosets by taking maximums, and are tightened by the `ValueBound`s of the problem.
+    /// Parent sequences are numbered after their children, so a single pass suffices.
+    fn compute_big_m(&self) -> Vec<f64> {
+        let treeplex = self.problem.game.treeplex(Player::Player2);
+        let treeplex_tools = &self.problem.treeplex_follower_tools;
+
+        let mut value_bounds = vec![ValueBound::None; treeplex.num_infosets()];
+        for (infoset_id, value_bound) in self.problem.bounds.iter() {
+            value_bounds[*infoset_id] = *value_bound;
+        }
+
+        let mut seq_lower = vec![0f64; treeplex.num_sequences()];
+        let mut seq_upper = vec![0f64; treeplex.num_sequences()];
+        let infoset_range = |infoset_id: usize, seq_lower: &[f64], seq_upper: &[f64]| {
+            let infoset = treeplex.infosets()[infoset_id];
+            let sequences = infoset.start_sequence..=infoset.end_sequence;
+            let lower = sequences
+                .clone()
+                .map(|sequence_id| seq_lower[sequence_id])
+                .fold(f64::NEG_INFINITY, f64::max);
+            let upper = sequences
+                .map(|sequence_id| seq_upper[sequence_id])
+                .fold(f64::NEG_INFINITY, f64::max);
+            match value_bounds[infoset_id] {
+                ValueBound::LowerBound(lb) => (lower.max(lb), upper),
+                ValueBound::UpperBound(ub) => (lower, upper.min(ub)),
+                ValueBound::None => (lower, upper),
+            }
+        };
+
+        for sequence_id in 0..treeplex.num_sequences() {
+            for leaf_index in self
+                .problem
+                .game_tools
+                .leaf_indices_at_sequence(Player::Player2, sequence_id)
+            {
+                let leaf = self.problem.game.payoff_matrix().entries[leaf_index];
+                let payoff = leaf.payoff_pl2 * leaf.chance_factor;
+                seq_lower[sequence_id] += payoff.min(0.0);
+                seq_upper[sequence_id] += payoff.max(0.0);
+            }
+            for child_infoset_id in treeplex_tools.seq_to_infoset_range(sequence_id) {
+                let (lower, upper) = infoset_range(child_infoset_id, &seq_lower, &seq_upper);
+                seq_lower[sequence_id] += lower;
+                seq_upper[sequence_id] += upper;
+            }
+        }
+
+        (0..treeplex.num_sequences())
+            .map(|sequence_id| match treeplex_tools.parent_infoset_of_seq(sequence_id) {
+                Some(parent_infoset_id) => {
+                    let (_, upper) = infoset_range(parent_infoset_id, &seq_lower, &seq_upper);
+                    (upper - seq_lower[sequence_id]).max(0.0)
+                }
+                // The empty sequence is always played, so its slack is zero.
+                None => 0.0,
+            })
+            .collect()
+    }
+
+    /// Reads off the strategies, slacks and values from the values of all variables.
+    pub fn solution(&self, values: &[f64], objective_value: f64) -> MIPSolution {
+        let follower_treeplex = self.problem.game.treeplex(Player::Player2);
+        let slice = |start: usize, len: usize| values[start..start + len].to_vec();
+
+        let strategy = |player: Player| {
+            let treeplex = self.problem.game.treeplex(player);
+            let treeplex_vector = TreeplexVector::from_vec(
+                treeplex,
+                slice(self.get_seq_form_index(player, 0), treeplex.num_sequences()),
+            );
+            SequenceFormStrategy::from_treeplex_vector(treeplex_vector)
+        };
+
+        assert_eq!(values.len(), self.model.num_variables());
+        MIPSolution::new(
+            strategy(Player::Player1),
+            strategy(Player::Player2),
+            slice(
+                self.get_leaf_prob_index(0),
+                self.problem.leaves_within_trunk.len(),
+            ),
+            objective_value,
+            TreeplexVector::from_vec(
+                follower_treeplex,
+                slice(
+                    self.get_value_slack_index(0),
+                    follower_treeplex.num_sequences(),
+                ),
+            ),
+            slice(
+                self.get_value_infoset_index(0),
+                follower_treeplex.num_infosets(),
+            ),
+        )
+    }
+
+    fn make_variables(&mut self) {
+        // Add leaf probabilities.
+        for leaf_index in self.problem.leaves_within_trunk.iter() {
+            let leaf = self.problem.game.payoff_matrix().entries[*leaf_index];
+            self.model.add_variable(
+                &format!("leaf_probabilities_{}", leaf_index),
+                VariableType::Continuous,
+                leaf.chance_factor * leaf.payoff_pl1, // Expression (16)
+                0f64,                                 // Probabilities must be >= 0.
+                INFINITY,
+            );
+        }
+        // Slack variables (for each follower sequence).
+        for follower_sequence in 0..self.problem.game.treeplex(Player::Player2).num_sequences() {
+            self.model.add_variable(
+                &format!("follower_slack_{}", follower_sequence),
+                VariableType::Continuous,
+                0f64,     // No objective.
+                0f64,                          // Slack must be >= 0.
+                self.big_m(follower_sequence), // Slack never exceeds its big M.
+            );
+        }
+        // Infoset values (followers).
+        for follower_infoset in 0..self.problem.game.treeplex(Player::Player2).num_infosets() {
+            self.model.add_variable(
+                &format!("follower_infoset_value_{}", follower_infoset),
+                VariableType::Continuous,
+                0f64,      // No objective.
+                -INFINITY, // Values can range from -INF to INF
+                INFINITY,
+            );
+        }
+        // Sequence form representation (leader).
+        for leader_sequence in 0..self.problem.game.treeplex(Player::Player1).num_sequences() {
+            self.model.add_variable(
+                &format!("leader_sequence_form_{}", leader_sequence),
+                VariableType::Continuous,
+                0f64, // No objective.
+                0f64, // Sequence probability must be >= 0
+                1f64, // Sequence probability may not be more than 1 (not really required in theory).
+            );
+        }
+        // Sequence form representation (follower).
+        for follower_sequence in 0..self.problem.game.treeplex(Player::Player2).num_sequences() {
+            self.model.add_variable(
+                &format!("follower_sequence_form_{}", follower_sequence),
+                VariableType::Binary,
+                0f64, // No objective.
+                0f64,
+                1f64,
+            );
+        }
+    }
+
+    fn make_bounds_constraints(&mut self) {
+        for (infoset_id, value_bound) in self.problem.bounds.iter() {
+            let coeffs = vec![(self.get_value_infoset_index(*infoset_id), 1.0)];
+            let name = format!("value_bounds_{}", infoset_id);
+            match value_bound {
+                ValueBound::LowerBound(lb) => {
+                    self.model
+                        .add_constraint(&name, coeffs, ConstraintSense::GreaterEqual, *lb);
+                }
+                ValueBound::UpperBound(ub) => {
+                    self.model
+                        .add_constraint(&name, coeffs, ConstraintSense::LessEqual, *ub);
+                }
+                ValueBound::None => {}
+            }
+        }
+    }
+
+    /// Constraints (17)
+    fn make_slack_constraints(&mut self) {
+        let treeplex = self.problem.game.treeplex(Player::Player2);
+        for (parent_infoset_id, parent_infoset) in treeplex.infosets().iter().enumerate() {
+            for sequence_id in parent_infoset.start_sequence..=parent_infoset.end_sequence {
+                // We go about the constraints from the left to right from the
+                // original paper by Bonsansky and Cermak.
+                let mut coeffs = vec![
+                    (self.get_value_infoset_index(parent_infoset_id), 1.0),
+                    (self.get_value_slack_index(sequence_id), -1.0),
+                ];
+                for child_infoset_id in self
+                    .problem
+                    .treeplex_follower_tools
+                    .seq_to_infoset_range(sequence_id)
+                {
+                    coeffs.push((self.get_value_infoset_index(child_infoset_id), -1.0));
+                }
+                for leaf_index in self
+                    .problem
+                    .game_tools
+                    .leaf_indices_at_sequence(Player::Player2, sequence_id)
+                {
+                    let leaf = self.problem.game.payoff_matrix().entries[leaf_index];
+                    coeffs.push((
+                        self.get_seq_form_index_pl1(leaf.seq_pl1),
+                        -leaf.payoff_pl2 * leaf.chance_factor,
+                    ));
+                }
+                self.model.add_constraint(
+                    &format!("slack_constraints_{}", sequence_id),
+                    coeffs,
+                    ConstraintSense::Equal,
+                    0f64,
+                );
+            }
+        }
+    }
+
+    /// Constraints (18, 19).
+    fn make_sequence_form_constraints(&mut self, player: Player) {
+        let treeplex = self.problem.game.treeplex(player);
+        let empty_sequence_id = treeplex.empty_sequence_id();
+
+        self.model.add_constraint(
+            &format!("seq_form_constraints_empty_seq_{:?}", player),
+            vec![(self.get_seq_form_index(player, empty_sequence_id), 1.0)],
+            ConstraintSense::Equal,
+            1f64,
+        );
+
+        for (infoset_id, infoset) in treeplex.infosets().iter().enumerate() {
+            let mut coeffs = vec![(self.get_seq_form_index(player, infoset.parent_sequence), 1.0)];
+            for sequence_id in infoset.start_sequence..=infoset.end_sequence {
+                coeffs.push((self.get_seq_form_index(player, sequence_id), -1.0));
+            }
+            self.model.add_constraint(
+                &format!("seq_form_constraints_{}_{:?}", infoset_id, player),
+                coeffs,
+                ConstraintSense::Equal,
+                0f64,
+            );
+        }
+    }
+
+    /// Constraints (20). With `OnOffEncoding::BigM`, the big M of each sequence is given
+    /// by `compute_big_m`.
+    fn make_on_off_constraints(&mut self, on_off_encoding: OnOffEncoding) {
+        for sequence_id in 0..self.problem.game.treeplex(Player::Player2).num_sequences() {
+            let name = format!("on_off_constraints_{}", sequence_id);
+            let slack_index = self.get_value_slack_index(sequence_id);
+            let seq_form_index = self.get_seq_form_index_pl2(sequence_id);
+            match on_off_encoding {
+                OnOffEncoding::BigM => {
+                    self.model.add_constraint(
+                        &name,
+                        vec![(slack_index, 1.0), (seq_form_index, self.big_m(sequence_id))],
+                        ConstraintSense::LessEqual,
+                        self.big_m(sequence_id),
+                    );
+                }
+                OnOffEncoding::Indicator => {
+                    self.model.add_indicator_constraint(
+                        &name,
+                        seq_form_index,
+                        true,
+                        vec![(slack_index, 1.0)],
+                        ConstraintSense::Equal,
+                        0f64,
+                    );
+                }
+                OnOffEncoding::Sos1 => {
+                    let variables = vec![(slack_index, 1.0), (seq_form_index, 2.0)];
+                    self.model.add_sos1_constraint(&name, variables);
+                }
+            }
+        }
+    }
+
+    /// Constraints (21, 22).
+    fn make_leaf_max_prob_constraints(&mut self) {
+        for (idx, leaf_index) in self.problem.leaves_within_trunk.iter().enumerate() {
+            let leaf = self.problem.game.payoff_matrix().entries[*leaf_index];
+            for player in [Player::Player1, Player::Player2].iter() {
+                let sequence_id = match player {
+                    Player::Player1 => leaf.seq_pl1,
+                    Player::Player2 => leaf.seq_pl2,
+                };
+                self.model.add_constraint(
+                    &format!("prob_max_constraints_{:?}_leaf_{}", player, leaf_index),
+                    vec![
+                        (self.get_leaf_prob_index(idx), 1.0),
+                        (self.get_seq_form_index(*player, sequence_id), -1.0),
+                    ],
+                    ConstraintSense::LessEqual,
+                    0f64,
+                );
+            }
+        }
+    }
+
+    /// Constraint (23).
+    fn make_leaf_sum_prob_constraints(&mut self) {
+        let coeffs = self
+            .problem
+            .leaves_within_trunk
+            .iter()
+            .enumerate()
+            .map(|(idx, leaf_index)| {
+                let leaf = self.problem.game.payoff_matrix().entries[*leaf_index];
+                (self.get_leaf_prob_index(idx), leaf.chance_factor)
+            })
+            .collect::<Vec<(usize, f64)>>();
+        self.model.add_constraint(
+            "prob_sum_constraints",
+            coeffs,
+            ConstraintSense::Equal,
+            self.problem.input_mass,
+        );
+    }
+}
+
+#[cfg(test)]
+pub mod test_fixtures {
+    use super::{OnOffEncoding, SseModel};
+    use crate::branch_and_bound_solver::test_fixtures::commitment_problem;
+    use efg_lite::sse::{BoundedProblem, ValueBound};
+
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn big_m_is_tight() {
+        let problem = commitment_problem();
+        let sse_model = SseModel::new(&problem, OnOffEncoding::BigM);
+        // Each follower action yields between 0 and 1, and the empty sequence has no slack.
+        assert_approx_eq!(sse_model.big_m(0), 1.0);
+        assert_approx_eq!(sse_model.big_m(1), 1.0);
+        assert_approx_eq!(sse_model.big_m(2), 0.0);
+
+        let bounded_problem = BoundedProblem::new(
+            problem.game,
+            problem.input_mass,
+            vec![(0, ValueBound::UpperBound(0.5))],
+            problem.leaves_within_trunk,
+        );
+        let sse_model = SseModel::new(&bounded_problem, OnOffEncoding::BigM);
+        assert_approx_eq!(sse_model.big_m(0), 0.5);
+        assert_approx_eq!(sse_model.big_m(1), 0.5);
+    }
+}
@@ -0,0 +1,312 @@
+use crate::solver::{IterativeProgress, Solver, SolverConfig};
+use crate::zero_sum_solution::ZeroSumSolution;
+use efg_lite::game::{ExtensiveFormGame, Player};
+use efg_lite::strategy::{BehavioralStrategy, SequenceFormStrategy};
+use efg_lite::treeplex::Treeplex;
+use efg_lite::vector::TreeplexVector;
+
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Regret minimization scheme used by `CfrSolver`.
+/// `Vanilla` is the original CFR with uniform averaging, `Plus` is CFR+ (regrets floored
+/// at zero, linear averaging) and `Discounted` is DCFR of Brown and Sandholm, where
+/// at iteration t, positive regrets are scaled by t^alpha / (t^alpha + 1), negative
+/// regrets by t^beta / (t^beta + 1) and contributions to the average strategy are
+/// weighted in proportion to t^gamma. Linear CFR corresponds to alpha = beta = gamma = 1.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CfrVariant {
+    Vanilla,
+    Plus,
+    Discounted { alpha: f64, beta: f64, gamma: f64 },
+}
+
+impl CfrVariant {
+    /// Factor by which the cumulative strategy is discounted prior to adding the
+    /// strategy of iteration `iteration` (1-indexed).
+    fn average_discount(&self, iteration: usize) -> f64 {
+        let t = iteration as f64;
+        match *self {
+            CfrVariant::Vanilla => 1.0,
+            CfrVariant::Plus => (t - 1.0) / t,
+            CfrVariant::Discounted { gamma, .. } => ((t - 1.0) / t).powf(gamma),
+        }
+    }
+
+    /// Updates the cumulative regret of a single sequence after iteration `iteration`.
+    fn update_regret(&self, cumulative_regret: f64, instantaneous_regret: f64, iteration: usize) -> f64 {
+        let regret = cumulative_regret + instantaneous_regret;
+        match *self {
+            CfrVariant::Vanilla => regret,
+            CfrVariant::Plus => regret.max(0.0),
+            CfrVariant::Discounted { alpha, beta, .. } => {
+                let t = iteration as f64;
+                match regret > 0.0 {
+                    true => regret * t.powf(alpha) / (t.powf(alpha) + 1.0),
+                    false => regret * t.powf(beta) / (t.powf(beta) + 1.0),
+                }
+            }
+        }
+    }
+}
+
+/// Regret minimizer over the sequence-form strategy space of a single player. Regrets
+/// are decomposed over infosets (counterfactual regret minimization), with regret
+/// matching at each infoset.
+struct TreeplexRegretMinimizer {
+    treeplex: Arc<Treeplex>,
+
+    // Cumulative counterfactual regrets, indexed by sequence. The entry belonging to
+    // the empty sequence is unused.
+    regrets: TreeplexVector,
+
+    // Weighted sum of sequence-form strategies played so far, together with the total weight.
+    cumulative_strategy: TreeplexVector,
+    cumulative_weight: f64,
+}
+
+impl TreeplexRegretMinimizer {
+    fn new(treeplex: &Arc<Treeplex>) -> TreeplexRegretMinimizer {
+        TreeplexRegretMinimizer {
+            treeplex: treeplex.clone(),
+            regrets: TreeplexVector::from_constant(treeplex, 0f64),
+            cumulative_strategy: TreeplexVector::from_constant(treeplex, 0f64),
+            cumulative_weight: 0f64,
+        }
+    }
+
+    /// Behavioral strategy given by regret matching on the cumulative regrets. Infosets
+    /// with no positive regret are played uniformly.
+    fn current_behavioral(&self) -> BehavioralStrategy {
+        let mut vector = TreeplexVector::from_constant(&self.treeplex, 0f64);
+        vector[self.treeplex.empty_sequence_id()] = 1.0;
+        for infoset in self.treeplex.infosets().iter() {
+            let sequences = infoset.start_sequence..=infoset.end_sequence;
+            let total_positive_regret: f64 =
+                sequences.clone().map(|x| self.regrets[x].max(0.0)).sum();
+            let num_sequences = infoset.end_sequence - infoset.start_sequence + 1;
+            for sequence_id in sequences {
+                vector[sequence_id] = match total_positive_regret > 0.0 {
+                    true => self.regrets[sequence_id].max(0.0) / total_positive_regret,
+                    false => 1.0 / (num_sequences as f64),
+                };
+            }
+        }
+        BehavioralStrategy::from_treeplex_vector(vector)
+    }
+
+    fn current_strategy(&self) -> SequenceFormStrategy {
+        SequenceFormStrategy::from_behavioral_strategy(self.current_behavioral())
+    }
+
+    /// Updates regrets given the sequence-form `gradient` of the player's payoffs.
+    /// Counterfactual values are computed bottom-up, in the same manner as
+    /// `Treeplex::inplace_behavioral_br`, but taking expectations under the current
+    /// strategy instead of maximizing.
+    fn observe_gradient(&mut self, mut gradient: TreeplexVector, variant: CfrVariant, iteration: usize) {
+        let behavioral = self.current_behavioral();
+        for infoset in self.treeplex.infosets().iter() {
+            let infoset_value: f64 = (infoset.start_sequence..=infoset.end_sequence)
+                .map(|x| behavioral.inner()[x] * gradient[x])
+                .sum();
+            for sequence_id in infoset.start_sequence..=infoset.end_sequence {
+                self.regrets[sequence_id] = variant.update_regret(
+                    self.regrets[sequence_id],
+                    gradient[sequence_id] - infoset_value,
+                    iteration,
+                );
+            }
+            gradient[infoset.parent_sequence] += infoset_value;
+        }
+    }
+
+    fn accumulate_strategy(&mut self, strategy: &SequenceFormStrategy, variant: CfrVariant, iteration: usize) {
+        let discount = variant.average_discount(iteration);
+        self.cumulative_strategy *= discount;
+        self.cumulative_strategy += strategy.inner();
+        self.cumulative_weight = self.cumulative_weight * discount + 1.0;
+    }
+
+    fn average_strategy(&self) -> SequenceFormStrategy {
+        match self.cumulative_weight > 0.0 {
+            true => SequenceFormStrategy::from_treeplex_vector(
+                &self.cumulative_strategy * (1.0 / self.cumulative_weight),
+            ),
+            false => SequenceFormStrategy::from_uniform_strategy(&self.treeplex),
+        }
+    }
+}
+
+/// Solves zero-sum games using the CFR family of regret minimization algorithms,
+/// without requiring an external LP solver. Both players run a `TreeplexRegretMinimizer`
+/// with alternating updates, and the average strategies converge to a Nash equilibrium.
+///
+/// The game is assumed to be zero-sum, i.e., payoffs of Player 2 are the negation
+/// of those of Player 1 (see `ExtensiveFormGame::zero_sum`). Iterations stop once
+/// the number of iterations or time limit in `SolverConfig` is reached, or when the
+/// exploitability of the average strategies drops below the target exploitability.
+pub struct CfrSolver<'a> {
+    game: &'a ExtensiveFormGame,
+    solver_config: &'a SolverConfig,
+
+    minimizer_pl1: RefCell<TreeplexRegretMinimizer>,
+    minimizer_pl2: RefCell<TreeplexRegretMinimizer>,
+
+    // Iterations completed so far, with the exploitability of the average strategies.
+    progress: RefCell<IterativeProgress>,
+}
+
+impl<'a> CfrSolver<'a> {
+    fn iterate(&self, iteration: usize) {
+        let variant = self.solver_config.cfr_variant;
+        let mut minimizer_pl1 = self.minimizer_pl1.borrow_mut();
+        let mut minimizer_pl2 = self.minimizer_pl2.borrow_mut();
+
+        let strategy_pl2 = minimizer_pl2.current_strategy();
+        let strategy_pl1 = minimizer_pl1.current_strategy();
+        minimizer_pl1.observe_gradient(
+            self.game.gradient(Player::Player1, &strategy_pl2),
+            variant,
+            iteration,
+        );
+        minimizer_pl1.accumulate_strategy(&strategy_pl1, variant, iteration);
+
+        // Alternating updates: Player 2 responds to the *updated* strategy of Player 1.
+        let strategy_pl1 = minimizer_pl1.current_strategy();
+        minimizer_pl2.observe_gradient(
+            self.game.gradient(Player::Player2, &strategy_pl1),
+            variant,
+            iteration,
+        );
+        minimizer_pl2.accumulate_strategy(&strategy_pl2, variant, iteration);
+    }
+}
+
+impl<'a> Solver<'a> for CfrSolver<'a> {
+    fn new(game: &'a ExtensiveFormGame, solver_config: &'a SolverConfig) -> CfrSolver<'a> {
+        CfrSolver {
+            game,
+            solver_config,
+            minimizer_pl1: RefCell::new(TreeplexRegretMinimizer::new(
+                game.treeplex(Player::Player1),
+            )),
+            minimizer_pl2: RefCell::new(TreeplexRegretMinimizer::new(
+                game.treeplex(Player::Player2),
+            )),
+            progress: RefCell::new(IterativeProgress::default()),
+        }
+    }
+
+    fn solve(&self) {
+        let start_time = Instant::now();
+        let num_completed = self.progress.borrow().num_iterations;
+        for iteration in (num_completed + 1)..=self.solver_config.num_iterations {
+            self.iterate(iteration);
+
+            let stop = self.progress.borrow_mut().complete_iteration(
+                self.game,
+                self.solver_config,
+                start_time,
+                || {
+                    (
+                        self.minimizer_pl1.borrow().average_strategy(),
+                        self.minimizer_pl2.borrow().average_strategy(),
+                    )
+                },
+            );
+            if stop {
+                break;
+            }
+        }
+    }
+
+    fn get_solution(&self) -> ZeroSumSolution {
+        let strategy_pl1 = self.minimizer_pl1.borrow().average_strategy();
+        let strategy_pl2 = self.minimizer_pl2.borrow().average_strategy();
+        let game_value = self
+            .game
+            .evaluate_payoffs(&strategy_pl1, &strategy_pl2, Player::Player1);
+        let mut solution = ZeroSumSolution::new(strategy_pl1, game_value);
+        let progress = self.progress.borrow();
+        solution.num_iterations = progress.num_iterations;
+        solution.exploitabilities = progress.exploitabilities.clone();
+        solution
+    }
+}
+
+#[cfg(test)]
+pub mod test_fixtures {
+    use super::{CfrSolver, CfrVariant};
+    use crate::first_order_solver::FirstOrderMethod;
+    use crate::solver::{Solver, SolverConfig};
+    use efg_lite::game::{ExtensiveFormGame, Infoset, PayoffMatrix, PayoffMatrixEntry, Player};
+    use efg_lite::treeplex::Treeplex;
+
+    use assert_approx_eq::assert_approx_eq;
+    use std::sync::Arc;
+
+    /// Matching pennies with payoffs [[2, -1], [-1, 1]] for Player 1, who
+    /// plays Heads with probability 0.4 in equilibrium, for a game value of 0.2.
+    pub fn skewed_matching_pennies() -> ExtensiveFormGame {
+        let treeplex_pl1 = Treeplex::new(
+            Player::Player1,
+            3,
+            vec![Infoset::new(2, 0, 1)].into_boxed_slice(),
+        );
+        let treeplex_pl2 = Treeplex::new(
+            Player::Player2,
+            3,
+            vec![Infoset::new(2, 0, 1)].into_boxed_slice(),
+        );
+        let payoff_matrix = PayoffMatrix::new(vec![
+            PayoffMatrixEntry::new(0, 0, 1.0, 2.0, -2.0),
+            PayoffMatrixEntry::new(0, 1, 1.0, -1.0, 1.0),
+            PayoffMatrixEntry::new(1, 0, 1.0, -1.0, 1.0),
+            PayoffMatrixEntry::new(1, 1, 1.0, 1.0, -1.0),
+        ]);
+        ExtensiveFormGame::new(
+            Arc::new(treeplex_pl1),
+            Arc::new(treeplex_pl2),
+            payoff_matrix,
+            vec![],
+            vec![],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn cfr_variants_converge() {
+        let game = skewed_matching_pennies();
+        for variant in [
+            CfrVariant::Vanilla,
+            CfrVariant::Plus,
+            CfrVariant::Discounted {
+                alpha: 1.5,
+                beta: 0.0,
+                gamma: 2.0,
+            },
+        ]
+        .iter()
+        {
+            let solver_config = SolverConfig {
+                time_limit: f64::INFINITY,
+                num_iterations: 2000,
+                target_exploitability: 1e-4,
+                cfr_variant: *variant,
+                first_order_method: FirstOrderMethod::MirrorProx,
+                step_size: 1.0,
+                log_interval: 10,
+            };
+            let solver = CfrSolver::new(&game, &solver_config);
+            solver.solve();
+
+            let solution = solver.get_solution();
+            let exploitabilities = &solution.exploitabilities;
+            assert!(*exploitabilities.last().unwrap() <= 1e-3);
+            assert!(exploitabilities.iter().all(|x| *x >= -1e-9));
+            assert_approx_eq!(solution.game_value, 0.2, 1e-2);
+            assert_approx_eq!(solution.strategy_pl1.inner()[0], 0.4, 1e-2);
+        }
+    }
+}
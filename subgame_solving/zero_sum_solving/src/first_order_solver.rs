@@ -0,0 +1,431 @@
+use crate::solver::{IterativeProgress, Solver, SolverConfig};
+use crate::zero_sum_solution::ZeroSumSolution;
+use efg_lite::game::{ExtensiveFormGame, Player};
+use efg_lite::strategy::SequenceFormStrategy;
+use efg_lite::treeplex::{DilatedDgf, SimplexRegularizer};
+use efg_lite::vector::TreeplexVector;
+
+use log::warn;
+use std::cell::RefCell;
+use std::time::Instant;
+
+// Maximum number of times the initial smoothing parameters of EGT are doubled while
+// searching for a point satisfying the excessive gap condition.
+const MAX_EGT_INIT_ATTEMPTS: usize = 64;
+
+/// First-order saddle point method used by `FirstOrderSolver`.
+/// `ExcessiveGap` is Nesterov's excessive gap technique, whose iterates (rather than
+/// averages) converge at a rate of O(1/T). `MirrorProx` is Nemirovski's extragradient
+/// method and `OptimisticOmd` is optimistic online mirror descent. For the latter two,
+/// uniform averages of the iterates converge at a rate of O(1/T).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FirstOrderMethod {
+    ExcessiveGap,
+    MirrorProx,
+    OptimisticOmd,
+}
+
+struct FirstOrderState {
+    // Current iterates. For optimistic OMD, these are the secondary iterates which
+    // are updated using the observed gradients.
+    strategy_pl1: SequenceFormStrategy,
+    strategy_pl2: SequenceFormStrategy,
+
+    // Gradients observed in the previous iteration, used by optimistic OMD.
+    prev_gradient_pl1: TreeplexVector,
+    prev_gradient_pl2: TreeplexVector,
+
+    // Sums of strategies to be averaged, used by mirror prox and optimistic OMD.
+    strategy_sum_pl1: TreeplexVector,
+    strategy_sum_pl2: TreeplexVector,
+    num_summed: usize,
+
+    // Smoothing parameters, used by EGT.
+    mu_pl1: f64,
+    mu_pl2: f64,
+}
+
+/// Solves zero-sum games using first-order methods over the sequence-form polytopes,
+/// regularized by the dilated DGF with infoset regularizer `R` (e.g., `Entropy` or
+/// `Euclidean`). As with `CfrSolver`, payoffs of Player 2 are assumed to be the
+/// negation of those of Player 1, and both players maximize their own payoffs.
+///
+/// `SolverConfig::step_size` is the step size of mirror prox and optimistic OMD.
+/// For EGT, the smoothing parameters are initialized to the reciprocal of the step
+/// size, and increased as needed to satisfy the excessive gap condition.
+pub struct FirstOrderSolver<'a, R: SimplexRegularizer> {
+    game: &'a ExtensiveFormGame,
+    solver_config: &'a SolverConfig,
+
+    dgf_pl1: DilatedDgf<R>,
+    dgf_pl2: DilatedDgf<R>,
+
+    // Prox centers of the DGFs.
+    center_pl1: SequenceFormStrategy,
+    center_pl2: SequenceFormStrategy,
+
+    state: RefCell<FirstOrderState>,
+
+    // Iterations completed so far, with the exploitability of the solution.
+    progress: RefCell<IterativeProgress>,
+}
+
+impl<'a, R: SimplexRegularizer + Default> FirstOrderSolver<'a, R> {
+    fn gradient_pl1(&self, strategy_pl2: &SequenceFormStrategy) -> TreeplexVector {
+        self.game.gradient(Player::Player1, strategy_pl2)
+    }
+
+    fn gradient_pl2(&self, strategy_pl1: &SequenceFormStrategy) -> TreeplexVector {
+        self.game.gradient(Player::Player2, strategy_pl1)
+    }
+
+    /// Returns (1 - tau) * x + tau * y.
+    fn convex_combination(
+        x: &SequenceFormStrategy,
+        y: &SequenceFormStrategy,
+        tau: f64,
+    ) -> SequenceFormStrategy {
+        SequenceFormStrategy::from_treeplex_vector(x.inner() * (1.0 - tau) + &(y.inner() * tau))
+    }
+
+    /// Solution reported by the solver: the current iterates for EGT, and the average
+    /// iterates otherwise.
+    fn solution_strategies(&self) -> (SequenceFormStrategy, SequenceFormStrategy) {
+        let state = self.state.borrow();
+        match (self.solver_config.first_order_method, state.num_summed) {
+            (FirstOrderMethod::ExcessiveGap, _) | (_, 0) => {
+                (state.strategy_pl1.clone(), state.strategy_pl2.clone())
+            }
+            _ => {
+                let scale = 1.0 / (state.num_summed as f64);
+                (
+                    SequenceFormStrategy::from_treeplex_vector(&state.strategy_sum_pl1 * scale),
+                    SequenceFormStrategy::from_treeplex_vector(&state.strategy_sum_pl2 * scale),
+                )
+            }
+        }
+    }
+
+    // ============================================================================================
+    // Excessive gap technique. Here, the DGFs are shifted such that they are nonnegative and
+    // equal to zero at the prox center, i.e., we use D(x || center) in place of d(x).
+    // ============================================================================================
+
+    /// Maximizer and maximum of <gradient, x> - mu * D(x || center).
+    fn egt_smoothed_br(
+        dgf: &DilatedDgf<R>,
+        center: &SequenceFormStrategy,
+        gradient: &TreeplexVector,
+        mu: f64,
+    ) -> (f64, SequenceFormStrategy) {
+        let strategy = dgf.prox(gradient, center, 1.0 / mu);
+        let value = gradient.inner(strategy.inner()) - mu * dgf.bregman_divergence(&strategy, center);
+        (value, strategy)
+    }
+
+    /// Difference between the smoothed lower bound on the value of Player 1 given `strategy_pl1`
+    /// and the smoothed upper bound given `strategy_pl2`. The excessive gap condition holds
+    /// when this is nonnegative.
+    fn excessive_gap(
+        &self,
+        strategy_pl1: &SequenceFormStrategy,
+        strategy_pl2: &SequenceFormStrategy,
+        mu_pl1: f64,
+        mu_pl2: f64,
+    ) -> f64 {
+        let (value_pl1, _) = Self::egt_smoothed_br(
+            &self.dgf_pl1,
+            &self.center_pl1,
+            &self.gradient_pl1(strategy_pl2),
+            mu_pl1,
+        );
+        let (value_pl2, _) = Self::egt_smoothed_br(
+            &self.dgf_pl2,
+            &self.center_pl2,
+            &self.gradient_pl2(strategy_pl1),
+            mu_pl2,
+        );
+        -value_pl1 - value_pl2
+    }
+
+    fn egt_initialize(&self) {
+        let mut mu = 1.0 / self.solver_config.step_size;
+        for _ in 0..MAX_EGT_INIT_ATTEMPTS {
+            let (_, strategy_pl2) = Self::egt_smoothed_br(
+                &self.dgf_pl2,
+                &self.center_pl2,
+                &self.gradient_pl2(&self.center_pl1),
+                mu,
+            );
+            let strategy_pl1 = self.dgf_pl1.prox(
+                &self.gradient_pl1(&strategy_pl2),
+                &self.center_pl1,
+                1.0 / mu,
+            );
+            let gap = self.excessive_gap(&strategy_pl1, &strategy_pl2, mu, mu);
+
+            let mut state = self.state.borrow_mut();
+            state.strategy_pl1 = strategy_pl1;
+            state.strategy_pl2 = strategy_pl2;
+            state.mu_pl1 = mu;
+            state.mu_pl2 = mu;
+            if gap >= 0.0 {
+                return;
+            }
+            mu *= 2.0;
+        }
+        warn!("Excessive gap condition not satisfied at initialization");
+    }
+
+    /// Decreases the smoothing parameter of Player 1 by a factor of (1 - tau).
+    fn egt_shrink_pl1(&self, tau: f64) {
+        let mut state = self.state.borrow_mut();
+        let (mu_pl1, mu_pl2) = (state.mu_pl1, state.mu_pl2);
+        let (_, br_pl1) = Self::egt_smoothed_br(
+            &self.dgf_pl1,
+            &self.center_pl1,
+            &self.gradient_pl1(&state.strategy_pl2),
+            mu_pl1,
+        );
+        let mixed_pl1 = Self::convex_combination(&state.strategy_pl1, &br_pl1, tau);
+        let (_, br_pl2) = Self::egt_smoothed_br(
+            &self.dgf_pl2,
+            &self.center_pl2,
+            &self.gradient_pl2(&mixed_pl1),
+            mu_pl2,
+        );
+        let prox_pl1 = self.dgf_pl1.prox(
+            &self.gradient_pl1(&br_pl2),
+            &br_pl1,
+            tau / ((1.0 - tau) * mu_pl1),
+        );
+        state.strategy_pl1 = Self::convex_combination(&state.strategy_pl1, &prox_pl1, tau);
+        state.strategy_pl2 = Self::convex_combination(&state.strategy_pl2, &br_pl2, tau);
+        state.mu_pl1 = (1.0 - tau) * mu_pl1;
+    }
+
+    /// Decreases the smoothing parameter of Player 2 by a factor of (1 - tau).
+    fn egt_shrink_pl2(&self, tau: f64) {
+        let mut state = self.state.borrow_mut();
+        let (mu_pl1, mu_pl2) = (state.mu_pl1, state.mu_pl2);
+        let (_, br_pl2) = Self::egt_smoothed_br(
+            &self.dgf_pl2,
+            &self.center_pl2,
+            &self.gradient_pl2(&state.strategy_pl1),
+            mu_pl2,
+        );
+        let mixed_pl2 = Self::convex_combination(&state.strategy_pl2, &br_pl2, tau);
+        let (_, br_pl1) = Self::egt_smoothed_br(
+            &self.dgf_pl1,
+            &self.center_pl1,
+            &self.gradient_pl1(&mixed_pl2),
+            mu_pl1,
+        );
+        let prox_pl2 = self.dgf_pl2.prox(
+            &self.gradient_pl2(&br_pl1),
+            &br_pl2,
+            tau / ((1.0 - tau) * mu_pl2),
+        );
+        state.strategy_pl2 = Self::convex_combination(&state.strategy_pl2, &prox_pl2, tau);
+        state.strategy_pl1 = Self::convex_combination(&state.strategy_pl1, &br_pl1, tau);
+        state.mu_pl2 = (1.0 - tau) * mu_pl2;
+    }
+
+    fn egt_iterate(&self, iteration: usize) {
+        let t = iteration - 1;
+        let tau = 2.0 / ((t + 3) as f64);
+        match t % 2 {
+            0 => self.egt_shrink_pl1(tau),
+            _ => self.egt_shrink_pl2(tau),
+        }
+    }
+
+    // ============================================================================================
+    // Mirror prox and optimistic OMD.
+    // ============================================================================================
+
+    fn accumulate(
+        state: &mut FirstOrderState,
+        strategy_pl1: &SequenceFormStrategy,
+        strategy_pl2: &SequenceFormStrategy,
+    ) {
+        state.strategy_sum_pl1 += strategy_pl1.inner();
+        state.strategy_sum_pl2 += strategy_pl2.inner();
+        state.num_summed += 1;
+    }
+
+    fn mirror_prox_iterate(&self) {
+        let step_size = self.solver_config.step_size;
+        let mut state = self.state.borrow_mut();
+
+        // Extrapolation step.
+        let half_pl1 = self.dgf_pl1.prox(
+            &self.gradient_pl1(&state.strategy_pl2),
+            &state.strategy_pl1,
+            step_size,
+        );
+        let half_pl2 = self.dgf_pl2.prox(
+            &self.gradient_pl2(&state.strategy_pl1),
+            &state.strategy_pl2,
+            step_size,
+        );
+
+        // Update step, using gradients at the extrapolated point.
+        state.strategy_pl1 =
+            self.dgf_pl1
+                .prox(&self.gradient_pl1(&half_pl2), &state.strategy_pl1, step_size);
+        state.strategy_pl2 =
+            self.dgf_pl2
+                .prox(&self.gradient_pl2(&half_pl1), &state.strategy_pl2, step_size);
+
+        Self::accumulate(&mut state, &half_pl1, &half_pl2);
+    }
+
+    fn optimistic_omd_iterate(&self) {
+        let step_size = self.solver_config.step_size;
+        let mut state = self.state.borrow_mut();
+
+        // Play using the previous gradients as predictions of the next ones.
+        let played_pl1 =
+            self.dgf_pl1
+                .prox(&state.prev_gradient_pl1, &state.strategy_pl1, step_size);
+        let played_pl2 =
+            self.dgf_pl2
+                .prox(&state.prev_gradient_pl2, &state.strategy_pl2, step_size);
+
+        let gradient_pl1 = self.gradient_pl1(&played_pl2);
+        let gradient_pl2 = self.gradient_pl2(&played_pl1);
+        state.strategy_pl1 = self
+            .dgf_pl1
+            .prox(&gradient_pl1, &state.strategy_pl1, step_size);
+        state.strategy_pl2 = self
+            .dgf_pl2
+            .prox(&gradient_pl2, &state.strategy_pl2, step_size);
+        state.prev_gradient_pl1 = gradient_pl1;
+        state.prev_gradient_pl2 = gradient_pl2;
+
+        Self::accumulate(&mut state, &played_pl1, &played_pl2);
+    }
+}
+
+impl<'a, R: SimplexRegularizer + Default> Solver<'a> for FirstOrderSolver<'a, R> {
+    fn new(game: &'a ExtensiveFormGame, solver_config: &'a SolverConfig) -> FirstOrderSolver<'a, R> {
+        let treeplex_pl1 = game.treeplex(Player::Player1);
+        let treeplex_pl2 = game.treeplex(Player::Player2);
+        let dgf_pl1 = DilatedDgf::uniform(treeplex_pl1, R::default());
+        let dgf_pl2 = DilatedDgf::uniform(treeplex_pl2, R::default());
+        let center_pl1 = dgf_pl1.center();
+        let center_pl2 = dgf_pl2.center();
+
+        let state = FirstOrderState {
+            strategy_pl1: center_pl1.clone(),
+            strategy_pl2: center_pl2.clone(),
+            prev_gradient_pl1: TreeplexVector::from_constant(treeplex_pl1, 0f64),
+            prev_gradient_pl2: TreeplexVector::from_constant(treeplex_pl2, 0f64),
+            strategy_sum_pl1: TreeplexVector::from_constant(treeplex_pl1, 0f64),
+            strategy_sum_pl2: TreeplexVector::from_constant(treeplex_pl2, 0f64),
+            num_summed: 0,
+            mu_pl1: 0f64,
+            mu_pl2: 0f64,
+        };
+
+        let solver = FirstOrderSolver {
+            game,
+            solver_config,
+            dgf_pl1,
+            dgf_pl2,
+            center_pl1,
+            center_pl2,
+            state: RefCell::new(state),
+            progress: RefCell::new(IterativeProgress::default()),
+        };
+        if solver_config.first_order_method == FirstOrderMethod::ExcessiveGap {
+            solver.egt_initialize();
+        }
+        solver
+    }
+
+    fn solve(&self) {
+        let start_time = Instant::now();
+        let num_completed = self.progress.borrow().num_iterations;
+        for iteration in (num_completed + 1)..=self.solver_config.num_iterations {
+            match self.solver_config.first_order_method {
+                FirstOrderMethod::ExcessiveGap => self.egt_iterate(iteration),
+                FirstOrderMethod::MirrorProx => self.mirror_prox_iterate(),
+                FirstOrderMethod::OptimisticOmd => self.optimistic_omd_iterate(),
+            }
+
+            let stop = self.progress.borrow_mut().complete_iteration(
+                self.game,
+                self.solver_config,
+                start_time,
+                || self.solution_strategies(),
+            );
+            if stop {
+                break;
+            }
+        }
+    }
+
+    fn get_solution(&self) -> ZeroSumSolution {
+        let (strategy_pl1, strategy_pl2) = self.solution_strategies();
+        let game_value = self
+            .game
+            .evaluate_payoffs(&strategy_pl1, &strategy_pl2, Player::Player1);
+        let mut solution = ZeroSumSolution::new(strategy_pl1, game_value);
+        let progress = self.progress.borrow();
+        solution.num_iterations = progress.num_iterations;
+        solution.exploitabilities = progress.exploitabilities.clone();
+        solution
+    }
+}
+
+#[cfg(test)]
+pub mod test_fixtures {
+    use super::{FirstOrderMethod, FirstOrderSolver};
+    use crate::cfr_solver::test_fixtures::skewed_matching_pennies;
+    use crate::cfr_solver::CfrVariant;
+    use crate::solver::{Solver, SolverConfig};
+    use efg_lite::game::ExtensiveFormGame;
+    use efg_lite::treeplex::{Entropy, Euclidean, SimplexRegularizer};
+
+    use assert_approx_eq::assert_approx_eq;
+
+    fn check_convergence<R: SimplexRegularizer + Default>(
+        game: &ExtensiveFormGame,
+        method: FirstOrderMethod,
+        step_size: f64,
+    ) {
+        let solver_config = SolverConfig {
+            time_limit: f64::INFINITY,
+            num_iterations: 2000,
+            target_exploitability: 1e-4,
+            cfr_variant: CfrVariant::Plus,
+            first_order_method: method,
+            step_size,
+            log_interval: 10,
+        };
+        let solver = FirstOrderSolver::<R>::new(game, &solver_config);
+        solver.solve();
+
+        let solution = solver.get_solution();
+        assert!(*solution.exploitabilities.last().unwrap() <= 2e-3);
+        assert_approx_eq!(solution.game_value, 0.2, 1e-2);
+        assert_approx_eq!(solution.strategy_pl1.inner()[0], 0.4, 1e-2);
+    }
+
+    #[test]
+    fn first_order_methods_converge() {
+        let game = skewed_matching_pennies();
+        for method in [
+            FirstOrderMethod::ExcessiveGap,
+            FirstOrderMethod::MirrorProx,
+            FirstOrderMethod::OptimisticOmd,
+        ]
+        .iter()
+        {
+            check_convergence::<Entropy>(&game, *method, 0.5);
+            check_convergence::<Euclidean>(&game, *method, 0.1);
+        }
+    }
+}
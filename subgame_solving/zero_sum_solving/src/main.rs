@@ -1,5 +1,10 @@
+mod cfr_solver;
+mod first_order_solver;
+#[cfg(feature = "gurobi")]
 mod gurobi_solver;
+mod simplex_solver;
 mod solver;
+mod zero_sum_model;
 mod zero_sum_solution;
 
 use std::fs::File;
@@ -7,18 +12,26 @@ use std::path::PathBuf;
 use structopt::StructOpt;
 
 use std::io::BufReader;
+use std::str::FromStr;
 
 use efg_lite::game::{ExtensiveFormGame, Player};
 use efg_lite::schema::game_capnp;
 use efg_lite::strategy::{BehavioralStrategy, SequenceFormStrategy};
+use efg_lite::treeplex::{Entropy, Euclidean};
 
 use log::{debug, error, info, warn};
 
 use solver::SolverConfig;
 
 // use crate::cbc_solver::CbcSolver; // TODO (chunkail)
+use crate::cfr_solver::{CfrSolver, CfrVariant};
+use crate::first_order_solver::{FirstOrderMethod, FirstOrderSolver};
+#[cfg(feature = "gurobi")]
 use crate::gurobi_solver::GurobiSolver;
+use crate::simplex_solver::SimplexSolver;
 use crate::solver::Solver;
+use crate::zero_sum_model::ZeroSumModel;
+use crate::zero_sum_solution::ZeroSumSolution;
 
 #[derive(StructOpt, Debug)]
 #[structopt(name = "ZeroSumSolver")]
@@ -30,6 +43,118 @@ struct Opt {
     // Time limit
     #[structopt(short = "t", long = "time_limit")]
     time_limit: f64,
+
+    // Solver to use: gurobi, simplex, cfr, cfr+, dcfr, egt, mirror_prox or omd.
+    #[structopt(short = "s", long = "solver", default_value = "gurobi")]
+    solver: SolverType,
+
+    // Maximum number of iterations (iterative solvers only)
+    #[structopt(short = "n", long = "num_iterations", default_value = "1000")]
+    num_iterations: usize,
+
+    // Stop once the exploitability falls below this value (iterative solvers only)
+    #[structopt(short = "e", long = "target_exploitability", default_value = "0.0")]
+    target_exploitability: f64,
+
+    // Discounting parameters for DCFR
+    #[structopt(long = "dcfr_alpha", default_value = "1.5")]
+    dcfr_alpha: f64,
+
+    #[structopt(long = "dcfr_beta", default_value = "0.0")]
+    dcfr_beta: f64,
+
+    #[structopt(long = "dcfr_gamma", default_value = "2.0")]
+    dcfr_gamma: f64,
+
+    // Distance-generating function for first-order methods: entropy or euclidean.
+    #[structopt(long = "dgf", default_value = "entropy")]
+    dgf: DgfType,
+
+    // Step size for first-order methods (for EGT, the reciprocal of the initial smoothing)
+    #[structopt(long = "step_size", default_value = "1.0")]
+    step_size: f64,
+
+    // Compute and log the exploitability every this many iterations (iterative solvers only)
+    #[structopt(long = "log_interval", default_value = "100")]
+    log_interval: usize,
+
+    // Write the LP to this file before solving, in MPS (.mps) or LP (.lp) format.
+    #[structopt(long = "model_file")]
+    model_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SolverType {
+    Gurobi,
+    Simplex,
+    Cfr,
+    CfrPlus,
+    Dcfr,
+    FirstOrder(FirstOrderMethod),
+}
+
+impl FromStr for SolverType {
+    type Err = String;
+    fn from_str(solver_type: &str) -> Result<Self, Self::Err> {
+        match solver_type {
+            "gurobi" | "Gurobi" => Ok(SolverType::Gurobi),
+            "simplex" | "Simplex" => Ok(SolverType::Simplex),
+            "cfr" | "CFR" => Ok(SolverType::Cfr),
+            "cfr+" | "CFR+" | "cfrplus" => Ok(SolverType::CfrPlus),
+            "dcfr" | "DCFR" => Ok(SolverType::Dcfr),
+            "egt" | "EGT" => Ok(SolverType::FirstOrder(FirstOrderMethod::ExcessiveGap)),
+            "mirror_prox" | "mp" => Ok(SolverType::FirstOrder(FirstOrderMethod::MirrorProx)),
+            "omd" | "optimistic_omd" => {
+                Ok(SolverType::FirstOrder(FirstOrderMethod::OptimisticOmd))
+            }
+            s => Err(format!("Unknown solver type: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum DgfType {
+    Entropy,
+    Euclidean,
+}
+
+impl FromStr for DgfType {
+    type Err = String;
+    fn from_str(dgf_type: &str) -> Result<Self, Self::Err> {
+        match dgf_type {
+            "entropy" | "Entropy" => Ok(DgfType::Entropy),
+            "euclidean" | "Euclidean" => Ok(DgfType::Euclidean),
+            s => Err(format!("Unknown distance-generating function: {}", s)),
+        }
+    }
+}
+
+fn run_solver<'a, S: Solver<'a>>(
+    game: &'a ExtensiveFormGame,
+    solver_config: &'a SolverConfig,
+) -> ZeroSumSolution {
+    let solver = S::new(game, solver_config);
+    solver.solve();
+    solver.get_solution()
+}
+
+/// Solves the LP using Gurobi, or using the embedded simplex solver if we were built
+/// without Gurobi.
+#[cfg(feature = "gurobi")]
+fn run_lp_solver(
+    game: &ExtensiveFormGame,
+    solver_config: &SolverConfig,
+) -> ZeroSumSolution {
+    run_solver::<GurobiSolver>(game, solver_config)
+}
+
+#[cfg(not(feature = "gurobi"))]
+fn run_lp_solver(
+    game: &ExtensiveFormGame,
+    solver_config: &SolverConfig,
+) -> ZeroSumSolution {
+    warn!("Built without Gurobi, using the embedded simplex solver instead");
+    run_solver::<SimplexSolver>(game, solver_config)
 }
 
 fn main() {
@@ -64,19 +189,62 @@ fn main() {
             println!("Num sequences: {:?}, {:?}", game.treeplex(Player::Player1).num_sequences(), game.treeplex(Player::Player2).num_sequences());
             // println!("{:?}", game.treeplex(Player::Player1).infosets()[5999]);
 
+            if let Some(model_file) = &opt.model_file {
+                info!("Writing model to {:?}", model_file);
+                ZeroSumModel::new(&game)
+                    .model()
+                    .write_to_file(model_file)
+                    .unwrap();
+            }
+
+            let cfr_variant = match opt.solver {
+                SolverType::Cfr => CfrVariant::Vanilla,
+                SolverType::CfrPlus => CfrVariant::Plus,
+                _ => CfrVariant::Discounted {
+                    alpha: opt.dcfr_alpha,
+                    beta: opt.dcfr_beta,
+                    gamma: opt.dcfr_gamma,
+                },
+            };
             let solver_config = SolverConfig {
                 time_limit: opt.time_limit,
+                num_iterations: opt.num_iterations,
+                target_exploitability: opt.target_exploitability,
+                cfr_variant,
+                first_order_method: match opt.solver {
+                    SolverType::FirstOrder(method) => method,
+                    _ => FirstOrderMethod::ExcessiveGap,
+                },
+                step_size: opt.step_size,
+                log_interval: opt.log_interval,
             };
 
-            let solver = GurobiSolver::new(&game, &solver_config);
-            solver.solve();
-
-            let sol = solver.get_solution();
+            let sol = match opt.solver {
+                SolverType::Gurobi => run_lp_solver(&game, &solver_config),
+                SolverType::Simplex => run_solver::<SimplexSolver>(&game, &solver_config),
+                SolverType::Cfr | SolverType::CfrPlus | SolverType::Dcfr => {
+                    run_solver::<CfrSolver>(&game, &solver_config)
+                }
+                SolverType::FirstOrder(_) => match opt.dgf {
+                    DgfType::Entropy => {
+                        run_solver::<FirstOrderSolver<Entropy>>(&game, &solver_config)
+                    }
+                    DgfType::Euclidean => {
+                        run_solver::<FirstOrderSolver<Euclidean>>(&game, &solver_config)
+                    }
+                },
+            };
             let sol_strategy_p1 = sol.strategy_pl1;
             let sol_game_value = sol.game_value;
 
             // println!("pl1 strategy: {:?}", sol_strategy_p1);
             println!("game value: {:?}", sol_game_value);
+            if let Some(exploitability) = sol.exploitabilities.last() {
+                println!(
+                    "exploitability: {:?} after {} iterations",
+                    exploitability, sol.num_iterations
+                );
+            }
 
             println!("Saving zero-sum pl1 strategy");
             let mut file_writer = File::create("pl1-zero-sum-strategy.vec").unwrap();
@@ -0,0 +1,90 @@
+use crate::solver::{Solver, SolverConfig};
+use crate::zero_sum_model::ZeroSumModel;
+use crate::zero_sum_solution::ZeroSumSolution;
+use efg_lite::game::ExtensiveFormGame;
+
+use embedded_solvers::{LpSolution, LpStatus};
+
+use std::cell::RefCell;
+
+/// Solves the LP built by `ZeroSumModel` (Geoff's formulation, see zero_sum_model.rs) using
+/// the embedded simplex solver, so that no native solver libraries are required.
+/// The game value is reported using the same sign convention as `GurobiSolver`.
+pub struct SimplexSolver<'a> {
+    zero_sum_model: ZeroSumModel<'a>,
+    lp_solution: RefCell<Option<LpSolution>>,
+}
+
+impl<'a> Solver<'a> for SimplexSolver<'a> {
+    fn new(game: &'a ExtensiveFormGame, _solver_config: &'a SolverConfig) -> SimplexSolver<'a> {
+        SimplexSolver {
+            zero_sum_model: ZeroSumModel::new(game),
+            lp_solution: RefCell::new(None),
+        }
+    }
+
+    fn solve(&self) {
+        let lp_solution = self.zero_sum_model.model().solve();
+        // The LP is always feasible and bounded, since the game has a finite value.
+        assert_eq!(lp_solution.status, LpStatus::Optimal);
+        *self.lp_solution.borrow_mut() = Some(lp_solution);
+    }
+
+    fn get_solution(&self) -> ZeroSumSolution {
+        let lp_solution = self.lp_solution.borrow();
+        let lp_solution = lp_solution
+            .as_ref()
+            .expect("get_solution() called before solve()");
+
+        ZeroSumSolution::new(
+            self.zero_sum_model.strategy_pl1(&lp_solution.values),
+            lp_solution.objective_value,
+        )
+    }
+}
+
+#[cfg(test)]
+pub mod test_fixtures {
+    use super::SimplexSolver;
+    use crate::cfr_solver::test_fixtures::skewed_matching_pennies;
+    use crate::cfr_solver::CfrVariant;
+    use crate::first_order_solver::FirstOrderMethod;
+    use crate::solver::{exploitability, Solver, SolverConfig};
+    use efg_lite::game::Player;
+    use efg_lite::strategy::SequenceFormStrategy;
+    use efg_lite::vector::TreeplexVector;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn simplex_solves_matching_pennies() {
+        let game = skewed_matching_pennies();
+        let solver_config = SolverConfig {
+            time_limit: f64::INFINITY,
+            num_iterations: 0,
+            target_exploitability: 0.0,
+            cfr_variant: CfrVariant::Plus,
+            first_order_method: FirstOrderMethod::ExcessiveGap,
+            step_size: 1.0,
+            log_interval: 0,
+        };
+        let solver = SimplexSolver::new(&game, &solver_config);
+        solver.solve();
+        let solution = solver.get_solution();
+
+        // Same sign convention as `GurobiSolver`.
+        assert_approx_eq!(solution.game_value, -0.2);
+        assert_approx_eq!(solution.strategy_pl1.inner()[0], 0.4);
+        assert_approx_eq!(solution.strategy_pl1.inner()[1], 0.6);
+
+        // The follower's equilibrium strategy is to play Heads with probability 0.4 too.
+        let strategy_pl2 = SequenceFormStrategy::from_treeplex_vector(TreeplexVector::from_vec(
+            game.treeplex(Player::Player2),
+            vec![0.4, 0.6, 1.0],
+        ));
+        assert_approx_eq!(
+            exploitability(&game, &solution.strategy_pl1, &strategy_pl2),
+            0.0
+        );
+    }
+}
@@ -1,12 +1,86 @@
-use efg_lite::game::ExtensiveFormGame;
+use efg_lite::game::{ExtensiveFormGame, Player};
+use efg_lite::strategy::SequenceFormStrategy;
+use crate::cfr_solver::CfrVariant;
+use crate::first_order_solver::FirstOrderMethod;
 use crate::zero_sum_solution::ZeroSumSolution;
 
+use log::info;
+use std::time::Instant;
+
 pub trait Solver<'a> {
     fn new(game: &'a ExtensiveFormGame, solver_config: &'a SolverConfig) -> Self;
     fn solve(&self);
-    fn get_solution(&self) -> ZeroSumSolution<'a>;
+    fn get_solution(&self) -> ZeroSumSolution;
 }
 
 pub struct SolverConfig {
     pub time_limit: f64,
-}
\ No newline at end of file
+
+    // Parameters for iterative solvers. These are ignored by LP-based solvers.
+    pub num_iterations: usize,
+    pub target_exploitability: f64,
+    pub cfr_variant: CfrVariant,
+    pub first_order_method: FirstOrderMethod,
+    pub step_size: f64,
+    // Exploitability is computed and logged every `log_interval` iterations, and after the last
+    // iteration. If zero, only after the last iteration.
+    pub log_interval: usize,
+}
+
+/// Sum of the best response values of both players against the strategy of the other.
+/// For zero-sum games, this is the saddle point gap, which is zero only at a Nash equilibrium.
+pub fn exploitability(
+    game: &ExtensiveFormGame,
+    strategy_pl1: &SequenceFormStrategy,
+    strategy_pl2: &SequenceFormStrategy,
+) -> f64 {
+    let (br_value_pl1, _) = game
+        .treeplex(Player::Player1)
+        .inplace_sequence_form_br(game.gradient(Player::Player1, strategy_pl2));
+    let (br_value_pl2, _) = game
+        .treeplex(Player::Player2)
+        .inplace_sequence_form_br(game.gradient(Player::Player2, strategy_pl1));
+    br_value_pl1 + br_value_pl2
+}
+
+/// Progress of an iterative solver, i.e., the number of iterations completed so far and the
+/// exploitabilities computed along the way.
+#[derive(Default)]
+pub struct IterativeProgress {
+    pub num_iterations: usize,
+    pub exploitabilities: Vec<f64>,
+}
+
+impl IterativeProgress {
+    /// Records that another iteration was completed, and computes the exploitability of the
+    /// strategies if it is due. Returns true if the solver should stop, i.e., the target
+    /// exploitability or the time limit was reached.
+    pub fn complete_iteration<F>(
+        &mut self,
+        game: &ExtensiveFormGame,
+        solver_config: &SolverConfig,
+        start_time: Instant,
+        strategies: F,
+    ) -> bool
+    where
+        F: FnOnce() -> (SequenceFormStrategy, SequenceFormStrategy),
+    {
+        self.num_iterations += 1;
+        let iteration = self.num_iterations;
+        let timed_out = start_time.elapsed().as_secs_f64() > solver_config.time_limit;
+        let is_last = timed_out || iteration >= solver_config.num_iterations;
+        if is_last || iteration.is_multiple_of(solver_config.log_interval) {
+            let (strategy_pl1, strategy_pl2) = strategies();
+            let exploitability = exploitability(game, &strategy_pl1, &strategy_pl2);
+            info!("Iteration {}, exploitability: {:?}", iteration, exploitability);
+            self.exploitabilities.push(exploitability);
+            if exploitability <= solver_config.target_exploitability {
+                return true;
+            }
+        }
+        if timed_out {
+            info!("Time limit reached after {} iterations", iteration);
+        }
+        timed_out
+    }
+}
@@ -0,0 +1,221 @@
+use efg_lite::game::{EFGTools, ExtensiveFormGame, Player};
+use efg_lite::strategy::SequenceFormStrategy;
+use efg_lite::treeplex::{SequenceId, TreeplexTools};
+use efg_lite::vector::TreeplexVector;
+
+use embedded_solvers::{ConstraintSense, Model, ObjectiveSense, VariableType, INFINITY};
+
+/// ===============================================================================================
+/// We use the dualized formulation in
+/// http://www.cs.cmu.edu/~ggordon/poker/source/zsumextformlp.m
+///
+/// Specifically,
+///
+///  Ax = a,  x >= 0, A, a are sequence form constraints for Player 1 and empty sequence identifier.
+///  By = b,  y >= 0, B, b are sequence form constraints for Player 2 and empty sequence identifier.
+///  x = arg max y' * R * x,
+///  y = arg min y' * R * x,
+///  where R is the reward matrix for Player 1 (who chooses x)
+///
+/// Or,
+/// min_y max_x x' R y
+/// Ax = a, x >= 0
+/// By = b, y >= 0
+///
+/// which after taking the dual in the middle maximum and performing some sign changes, gives
+/// min_{y,z} a' z
+/// A'z - Ry >= 0
+/// By = b, y >= 0
+///
+/// Or, if we swap min/max's and play around with signs, we get what is given by Geoff's formulation
+/// min_{x,z} b'z
+/// Rx + B'z >= 0  ---------------(A)
+/// Ax = a, ----------------------(B)
+/// x >= 0,
+///
+/// We will use Geoff's formulation for simplicity.
+/// ===============================================================================================
+/// The number of variables (cols) are
+///
+/// (|I2| + 1) + |S1|, where
+/// |S1| = num_sequences (pl2) and
+/// |I2| = num_infosets (pl1).
+///
+/// The number of constraints (rows) are
+/// |S2| + (|I1| + 1)
+///
+/// where
+/// |S1| = num_sequences (Pl1),
+/// |S2| = num_sequences (Pl2),
+/// |I1| = num_infosets (Pl1),
+/// |I2| = num_infosets (Pl2).
+///
+/// The additional 1 variable in the constraints involving |I| are due tothe (primal) constraint associated
+/// with the empy sequence.
+/// ===============================================================================================
+/// The numbering we will use for variables is
+///
+/// Sequence form representation of pl1 (x): [0,..., |S1|)
+/// Dual variables for infosets of pl2 (z): [|S1|,... |S1|+|I2|)
+/// Dual variables for empty sequence of pl2 (z): |S1|+|I2|
+/// ================================================================================================
+/// The model is built once here and handed to whichever backend solves it.
+pub struct ZeroSumModel<'a> {
+    game: &'a ExtensiveFormGame,
+    model: Model,
+}
+
+impl<'a> ZeroSumModel<'a> {
+    pub fn new(game: &'a ExtensiveFormGame) -> ZeroSumModel<'a> {
+        let mut zero_sum_model = ZeroSumModel {
+            game,
+            model: Model::new("zero-sum-model", ObjectiveSense::Minimize),
+        };
+
+        println!("Making variables");
+        zero_sum_model.make_variables();
+
+        println!("Making constraints-per-sequence (A)");
+        zero_sum_model.make_constraints_per_sequence();
+
+        println!("Make sequence form constraints (B)");
+        zero_sum_model.make_sequence_form_constraints_pl1();
+
+        zero_sum_model
+    }
+
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+
+    pub fn get_seq_form_index_pl1(&self, sequence_id: SequenceId) -> usize {
+        sequence_id
+    }
+
+    pub fn get_infoset_index_pl2(&self, infoset_id: usize) -> usize {
+        infoset_id + self.game.treeplex(Player::Player1).num_sequences()
+    }
+
+    pub fn get_empty_seq_constr_index_pl2(&self) -> usize {
+        self.game.treeplex(Player::Player1).num_sequences()
+            + self.game.treeplex(Player::Player2).num_infosets()
+    }
+
+    /// Extracts the strategy of Player 1 from the values of all variables in a solution.
+    pub fn strategy_pl1(&self, values: &[f64]) -> SequenceFormStrategy {
+        let treeplex = self.game.treeplex(Player::Player1);
+        let start = self.get_seq_form_index_pl1(0);
+        let treeplex_vector = TreeplexVector::from_vec(
+            treeplex,
+            values[start..start + treeplex.num_sequences()].to_vec(),
+        );
+        SequenceFormStrategy::from_treeplex_vector(treeplex_vector)
+    }
+
+    fn make_variables(&mut self) {
+        // Sequence form representation pl1.
+        for leader_sequence in 0..self.game.treeplex(Player::Player1).num_sequences() {
+            self.model.add_variable(
+                &format!("pl1_sequence_form_{}", leader_sequence),
+                VariableType::Continuous,
+                0f64, // No objective.
+                0f64, // Sequence probability must be >= 0
+                1f64, // Sequence probability may not be more than 1 (not really required in theory).
+            );
+        }
+
+        // Infoset values pl2 (|I2| variables)
+        for follower_infoset in 0..self.game.treeplex(Player::Player2).num_infosets() {
+            self.model.add_variable(
+                &format!("pl2_infoset_value_{}", follower_infoset),
+                VariableType::Continuous,
+                0f64,
+                -INFINITY, // Values can range from -INF to INF
+                INFINITY,
+            );
+        }
+
+        // Extra variable coressponding to dual of empty sequence constraint for player 2.
+        self.model.add_variable(
+            "pl2_infoset_empty_sequence_value",
+            VariableType::Continuous,
+            1f64, // Objective of 1.
+            -INFINITY,
+            INFINITY,
+        );
+    }
+
+    /// Constraints (A)
+    fn make_constraints_per_sequence(&mut self) {
+        let efg_tools = EFGTools::new(self.game);
+
+        let treeplex = self.game.treeplex(Player::Player2);
+        let treeplex_tools = TreeplexTools::new(treeplex);
+
+        for seq_id in 0..treeplex.num_sequences() {
+            let mut coeffs = Vec::<(usize, f64)>::new();
+
+            // Handle the part of the constraint dealing with Rx.
+            for leaf_index in efg_tools.leaf_indices_at_sequence(Player::Player2, seq_id) {
+                let leaf = self.game.payoff_matrix().entries[leaf_index];
+                coeffs.push((
+                    self.get_seq_form_index_pl1(leaf.seq_pl1),
+                    leaf.payoff_pl1 * leaf.chance_factor,
+                ));
+            }
+
+            // Handle the part of the constraint dealing with B'z.
+            // We will need to get (I) the parent infoset which contained this
+            // sequence and (II) all direct children infosets from this sequence.
+
+            // (I)
+            // If the parent infoset of the sequence does not exist (i.e., seq_id is the empty
+            // sequence), then we use the dual variable of the empty sequence constraint.
+            match seq_id == treeplex.empty_sequence_id() {
+                true => coeffs.push((self.get_empty_seq_constr_index_pl2(), 1.0)),
+                false => {
+                    let parent_infoset = treeplex_tools.parent_infoset_of_seq(seq_id).unwrap();
+                    coeffs.push((self.get_infoset_index_pl2(parent_infoset), 1.0));
+                }
+            }
+
+            // (II)
+            for child_infoset in treeplex_tools.seq_to_infoset_range(seq_id) {
+                coeffs.push((self.get_infoset_index_pl2(child_infoset), -1.0));
+            }
+
+            self.model.add_constraint(
+                &format!("best_response_constraints_player2_{}", seq_id),
+                coeffs,
+                ConstraintSense::GreaterEqual,
+                0f64,
+            );
+        }
+    }
+
+    /// Constraints (B)
+    fn make_sequence_form_constraints_pl1(&mut self) {
+        let treeplex = self.game.treeplex(Player::Player1);
+        let empty_sequence_id = treeplex.empty_sequence_id();
+
+        self.model.add_constraint(
+            "seq_form_constraints_empty_seq_pl1",
+            vec![(self.get_seq_form_index_pl1(empty_sequence_id), 1.0)],
+            ConstraintSense::Equal,
+            1f64,
+        );
+
+        for (infoset_id, infoset) in treeplex.infosets().iter().enumerate() {
+            let mut coeffs = vec![(self.get_seq_form_index_pl1(infoset.parent_sequence), 1.0)];
+            for sequence_id in infoset.start_sequence..=infoset.end_sequence {
+                coeffs.push((self.get_seq_form_index_pl1(sequence_id), -1.0));
+            }
+            self.model.add_constraint(
+                &format!("seq_form_constraints_{}_pl1", infoset_id),
+                coeffs,
+                ConstraintSense::Equal,
+                0f64,
+            );
+        }
+    }
+}
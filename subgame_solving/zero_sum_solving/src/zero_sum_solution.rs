@@ -1,20 +1,25 @@
-
-use efg_lite::vector::TreeplexVector;
 use efg_lite::strategy::SequenceFormStrategy;
 
-pub struct ZeroSumSolution<'a> {
-    pub strategy_pl1: SequenceFormStrategy<'a>,
+pub struct ZeroSumSolution {
+    pub strategy_pl1: SequenceFormStrategy,
     pub game_value: f64,
+
+    // Number of iterations run, and the exploitability every `log_interval` iterations and
+    // after the last one (iterative solvers only).
+    pub num_iterations: usize,
+    pub exploitabilities: Vec<f64>,
 }
 
-impl<'a> ZeroSumSolution<'a> {
+impl ZeroSumSolution {
     pub fn new(
-        strategy_pl1: SequenceFormStrategy<'a>,
+        strategy_pl1: SequenceFormStrategy,
         game_value: f64,
-    ) -> ZeroSumSolution<'a> {
+    ) -> ZeroSumSolution {
         ZeroSumSolution {
             strategy_pl1,
             game_value,
+            num_iterations: 0,
+            exploitabilities: Vec::<f64>::new(),
         }
     }
 }